@@ -2,7 +2,7 @@ use criterion::{Criterion, criterion_group, criterion_main};
 use std::hint::black_box;
 use strum::IntoEnumIterator;
 use ww_driver_trait::Driver;
-use ww_effects::EffectNameList;
+use ww_effects::{EffectNameList, SimulatedClock};
 use ww_frame::{FrameType, RGBArray};
 use ww_gift_coords::COORDS;
 
@@ -44,12 +44,14 @@ fn debug_effects(c: &mut Criterion) {
         c.bench_function(&format!("(SimpleDriver) {}", name.effect_name()), |b| {
             let mut effect = name.default_dispatch();
             let config = name.config_name().default_dispatch();
+            let mut clock = SimulatedClock::default();
             let mut driver = unsafe { SimpleDriver::init() };
 
             b.iter(|| {
                 if let Some(number) = effect.loops_to_test() {
                     for i in 0..u16::from(number) {
-                        if let Some((frame, _duration)) = effect.next_frame(&config) {
+                        if let Some((frame, duration)) = effect.next_frame(&config, &clock.tick()) {
+                            clock.advance(duration);
                             driver.display_frame(frame, 100);
                         } else {
                             panic!(
@@ -59,7 +61,8 @@ fn debug_effects(c: &mut Criterion) {
                         }
                     }
                 } else {
-                    while let Some((frame, _duration)) = effect.next_frame(&config) {
+                    while let Some((frame, duration)) = effect.next_frame(&config, &clock.tick()) {
+                        clock.advance(duration);
                         driver.display_frame(frame, 100);
                     }
                 }
@@ -70,12 +73,14 @@ fn debug_effects(c: &mut Criterion) {
             |b| {
                 let mut effect = name.default_dispatch();
                 let config = name.config_name().default_dispatch();
+                let mut clock = SimulatedClock::default();
                 let mut driver = unsafe { ConvertFrameDriver::init() };
 
                 b.iter(|| {
                     if let Some(number) = effect.loops_to_test() {
                         for i in 0..u16::from(number) {
-                            if let Some((frame, _duration)) = effect.next_frame(&config) {
+                            if let Some((frame, duration)) = effect.next_frame(&config, &clock.tick()) {
+                                clock.advance(duration);
                                 driver.display_frame(frame, 100);
                             } else {
                                 panic!(
@@ -85,7 +90,8 @@ fn debug_effects(c: &mut Criterion) {
                             }
                         }
                     } else {
-                        while let Some((frame, _duration)) = effect.next_frame(&config) {
+                        while let Some((frame, duration)) = effect.next_frame(&config, &clock.tick()) {
+                            clock.advance(duration);
                             driver.display_frame(frame, 100);
                         }
                     }
@@ -11,8 +11,14 @@ use std::{
 use strum::IntoEnumIterator;
 use tracing::{debug, error, info, instrument, trace, warn};
 use tracing_unwrap::ResultExt;
-use ww_effects::EffectNameList;
-use ww_shared::{ClientState, ClientToServerMsg, ServerToClientMsg};
+use ww_effects::{
+    BlendMode, EffectCategory, EffectLayer, EffectMetadata, EffectNameList, Modulation,
+    ModulationShape, PresetBundle, PresetLibrary,
+    layers::MAX_LAYERS,
+    list::EffectConfigDispatchList,
+    metadata::{THUMBNAIL_HEIGHT, THUMBNAIL_WIDTH},
+};
+use ww_shared::{ClientState, ClientToServerMsg, ExternalEffectName, ServerToClientMsg};
 
 /// The current state of the app and its connection to the server.
 #[derive(Clone, Debug, PartialEq)]
@@ -40,6 +46,29 @@ enum AppState {
     },
 }
 
+/// The state of the GUI for saving and sharing presets, which isn't shared with the server.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct PresetsGui {
+    /// The name to save the current config under.
+    new_name: String,
+
+    /// The text of an exported bundle, or a bundle to import.
+    bundle_text: String,
+
+    /// The error from the last bundle that couldn't be imported.
+    import_error: Option<String>,
+}
+
+/// The state of the effect browser GUI, which isn't shared with the server.
+#[derive(Default)]
+struct EffectBrowserGui {
+    /// The current search query.
+    search: String,
+
+    /// The textures of the thumbnails that we've already loaded.
+    thumbnails: Vec<(EffectNameList, egui::TextureHandle)>,
+}
+
 /// The app type itself.
 pub struct App {
     /// The receiver end of a channel used to recieve messages from the server.
@@ -58,6 +87,21 @@ pub struct App {
     /// An async runtime used to send async messages.
     async_runtime: prokio::Runtime,
 
+    /// All the presets that the server knows about.
+    presets: PresetLibrary,
+
+    /// The state of the presets GUI.
+    presets_gui: PresetsGui,
+
+    /// All the external effects that the server knows about.
+    external_effects: Vec<ExternalEffectName>,
+
+    /// The metadata of all the effects that are compiled into the server.
+    effect_metadata: Vec<EffectMetadata>,
+
+    /// The state of the effect browser GUI.
+    effect_browser_gui: EffectBrowserGui,
+
     // This is used only by [`App::respond_to_server_messages`].
     #[doc(hidden)]
     tracked_server_version: Option<String>,
@@ -122,6 +166,11 @@ impl App {
             reconnect_tx,
             state,
             async_runtime,
+            presets: PresetLibrary::default(),
+            presets_gui: PresetsGui::default(),
+            external_effects: Vec::new(),
+            effect_metadata: Vec::new(),
+            effect_browser_gui: EffectBrowserGui::default(),
             tracked_server_version: None,
         }
     }
@@ -283,6 +332,16 @@ impl App {
                         });
                     }
                 }
+                ServerToClientMsg::UpdatePresets(presets) => {
+                    self.presets = presets;
+                }
+                ServerToClientMsg::UpdateExternalEffects(external_effects) => {
+                    self.external_effects = external_effects;
+                }
+                ServerToClientMsg::UpdateEffectMetadata(effect_metadata) => {
+                    self.effect_metadata = effect_metadata;
+                    self.effect_browser_gui.thumbnails.clear();
+                }
                 ServerToClientMsg::TerminateConnection => {
                     *self.state.write().unwrap_or_log() = AppState::WaitingForConnection;
 
@@ -322,30 +381,55 @@ impl App {
         };
 
         egui::CentralPanel::default().show(ctx, |ui| {
-            let new_effect_selected = egui::ComboBox::from_label("Current effect")
-                .selected_text(
-                    state
-                        .effect_name
-                        .map_or("None", |effect| effect.effect_name()),
-                )
+            let mut new_external_effect_selected = None;
+
+            let new_effect_from_combo_box = egui::ComboBox::from_label("Current effect")
+                .selected_text(match (&state.effect_name, &state.external_effect) {
+                    (Some(effect), _) => effect.effect_name().to_string(),
+                    (None, Some(external)) => external.display_name(),
+                    (None, None) => "None".to_string(),
+                })
                 .show_ui(ui, |ui| {
                     let selected_none = ui
                         .selectable_value(&mut state.effect_name, None, "None")
                         .clicked();
 
-                    let selected_new_effect = EffectNameList::iter().any(|effect| {
-                        // We remember which value was initially selected and whether this
-                        // value is a new one
-                        let different = Some(effect) != state.effect_name;
-                        let resp = ui.selectable_value(
-                            &mut state.effect_name,
-                            Some(effect),
-                            effect.effect_name(),
-                        );
+                    // The effects are grouped under a heading for each category
+                    let mut selected_new_effect = false;
+                    for category in EffectCategory::iter() {
+                        ui.separator();
+                        ui.label(egui::RichText::new(category.name()).strong());
+
+                        for effect in EffectNameList::iter().filter(|e| e.category() == category) {
+                            // We remember which value was initially selected and whether this
+                            // value is a new one
+                            let different = Some(effect) != state.effect_name;
+                            let resp = ui.selectable_value(
+                                &mut state.effect_name,
+                                Some(effect),
+                                effect.effect_name(),
+                            );
 
-                        // If the value is different from the old and has been clicked, then we care
-                        resp.clicked() && different
-                    });
+                            // If the value is different from the old and has been clicked, then
+                            // we care
+                            selected_new_effect |= resp.clicked() && different;
+                        }
+                    }
+
+                    if !self.external_effects.is_empty() {
+                        ui.separator();
+                    }
+
+                    for external in &self.external_effects {
+                        let selected = state.external_effect.as_ref() == Some(external);
+                        if ui
+                            .selectable_label(selected, external.display_name())
+                            .clicked()
+                            && !selected
+                        {
+                            new_external_effect_selected = Some(external.clone());
+                        }
+                    }
 
                     if selected_new_effect || selected_none {
                         Some(state.effect_name)
@@ -356,6 +440,29 @@ impl App {
                 .inner
                 .flatten();
 
+            let list_external_effects = ui
+                .small_button("Look for new scripts and plugins")
+                .clicked();
+
+            let new_effect_from_browser = ui
+                .collapsing("Effect browser", |ui| {
+                    Self::display_effect_browser_gui(
+                        ctx,
+                        ui,
+                        &self.effect_metadata,
+                        &mut self.effect_browser_gui,
+                        state.effect_name,
+                    )
+                })
+                .body_returned
+                .flatten();
+            if let Some(effect) = new_effect_from_browser {
+                state.effect_name = Some(effect);
+            }
+
+            let new_effect_selected =
+                new_effect_from_combo_box.or(new_effect_from_browser.map(Some));
+
             let pause_time = ui
                 .add(
                     egui::Slider::new(&mut state.pause_time_ms, 0..=3000)
@@ -378,6 +485,17 @@ impl App {
 
             let restart_effect = ui.button("Restart current effect").clicked();
 
+            let preset_message = if let Some(effect) = state.effect_name {
+                ui.separator();
+                ui.collapsing("Presets", |ui| {
+                    Self::display_presets_gui(ui, effect, &self.presets, &mut self.presets_gui)
+                })
+                .body_returned
+                .flatten()
+            } else {
+                None
+            };
+
             let effect_config_changed = if let Some(config) = &mut state.effect_config {
                 ui.separator();
                 config
@@ -387,6 +505,20 @@ impl App {
                 None
             };
 
+            let modulations_changed = if let Some(config) = &state.effect_config {
+                ui.separator();
+                ui.collapsing("Modulations", |ui| {
+                    Self::display_modulations_gui(ui, &mut state.modulations, config)
+                })
+                .body_returned
+                .unwrap_or(false)
+            } else {
+                false
+            };
+
+            ui.separator();
+            let layers_changed = Self::display_layers_gui(ctx, ui, &mut state.layers);
+
             // We could condense these into a single call to `self.async_runtime.spawn_pinned()`,
             // but we're only ever going to call it once in a single frame anyway since we can't
             // change two things at once. To condese these calls, we'd probably want to allocate a
@@ -407,6 +539,40 @@ impl App {
                 });
             }
 
+            if let Some(external) = new_external_effect_selected {
+                trace!("New external effect selected, sending message");
+
+                self.async_runtime.spawn_pinned({
+                    let message_tx = self.message_tx.clone();
+
+                    move || async move {
+                        message_tx
+                            .send(ClientToServerMsg::ChangeExternalEffect(external))
+                            .await
+                            .expect_or_log(
+                                "Unable to send ChangeExternalEffect message down channel",
+                            );
+                    }
+                });
+            }
+
+            if list_external_effects {
+                trace!("Requesting external effects, sending message");
+
+                self.async_runtime.spawn_pinned({
+                    let message_tx = self.message_tx.clone();
+
+                    move || async move {
+                        message_tx
+                            .send(ClientToServerMsg::ListExternalEffects)
+                            .await
+                            .expect_or_log(
+                                "Unable to send ListExternalEffects message down channel",
+                            );
+                    }
+                });
+            }
+
             if pause_time {
                 trace!("Pause time changed, sending message");
 
@@ -458,6 +624,53 @@ impl App {
                 });
             }
 
+            if layers_changed {
+                trace!("Layers changed, sending message");
+
+                self.async_runtime.spawn_pinned({
+                    let message_tx = self.message_tx.clone();
+                    let layers = state.layers.clone();
+
+                    move || async move {
+                        message_tx
+                            .send(ClientToServerMsg::ChangeLayers(layers))
+                            .await
+                            .expect_or_log("Unable to send ChangeLayers message down channel");
+                    }
+                });
+            }
+
+            if modulations_changed {
+                trace!("Modulations changed, sending message");
+
+                self.async_runtime.spawn_pinned({
+                    let message_tx = self.message_tx.clone();
+                    let modulations = state.modulations.clone();
+
+                    move || async move {
+                        message_tx
+                            .send(ClientToServerMsg::ChangeModulations(modulations))
+                            .await
+                            .expect_or_log("Unable to send ChangeModulations message down channel");
+                    }
+                });
+            }
+
+            if let Some(message) = preset_message {
+                trace!(?message, "Presets changed, sending message");
+
+                self.async_runtime.spawn_pinned({
+                    let message_tx = self.message_tx.clone();
+
+                    move || async move {
+                        message_tx
+                            .send(message)
+                            .await
+                            .expect_or_log("Unable to send preset message down channel");
+                    }
+                });
+            }
+
             if let Some(config) = effect_config_changed {
                 trace!("Effect config changed, sending message");
 
@@ -506,6 +719,385 @@ impl App {
         });
     }
 
+    /// Display the GUI for browsing and searching the effects with their metadata and thumbnails,
+    /// and return the effect that was clicked, if it's not the current effect.
+    fn display_effect_browser_gui(
+        ctx: &eframe::egui::Context,
+        ui: &mut egui::Ui,
+        effect_metadata: &[EffectMetadata],
+        gui: &mut EffectBrowserGui,
+        current_effect: Option<EffectNameList>,
+    ) -> Option<EffectNameList> {
+        let mut clicked_effect = None;
+
+        ui.horizontal(|ui| {
+            ui.label("Search:");
+            ui.text_edit_singleline(&mut gui.search);
+        });
+
+        for category in EffectCategory::iter() {
+            let matching: Vec<_> = effect_metadata
+                .iter()
+                .filter(|metadata| metadata.category == category && metadata.matches(&gui.search))
+                .collect();
+
+            if matching.is_empty() {
+                continue;
+            }
+
+            ui.separator();
+            ui.heading(category.name());
+
+            for metadata in matching {
+                ui.push_id(metadata.effect.effect_name(), |ui| {
+                    ui.horizontal(|ui| {
+                        if let Some(thumbnail) = &metadata.thumbnail {
+                            let texture = match gui
+                                .thumbnails
+                                .iter()
+                                .find(|(effect, _)| *effect == metadata.effect)
+                            {
+                                Some((_, texture)) => texture.clone(),
+                                None => {
+                                    let texture = ctx.load_texture(
+                                        format!("thumbnail-{}", metadata.effect.effect_name()),
+                                        egui::ColorImage::from_rgb(
+                                            [THUMBNAIL_WIDTH, THUMBNAIL_HEIGHT],
+                                            &thumbnail.to_rgb_bytes(),
+                                        ),
+                                        egui::TextureOptions::NEAREST,
+                                    );
+                                    gui.thumbnails.push((metadata.effect, texture.clone()));
+                                    texture
+                                }
+                            };
+
+                            ui.image((
+                                texture.id(),
+                                egui::vec2(THUMBNAIL_WIDTH as f32, THUMBNAIL_HEIGHT as f32) * 2.,
+                            ));
+                        }
+
+                        ui.vertical(|ui| {
+                            ui.horizontal_wrapped(|ui| {
+                                let selected = current_effect == Some(metadata.effect);
+                                if ui
+                                    .selectable_label(
+                                        selected,
+                                        RichText::new(metadata.effect.effect_name()).strong(),
+                                    )
+                                    .clicked()
+                                    && !selected
+                                {
+                                    clicked_effect = Some(metadata.effect);
+                                }
+
+                                if metadata.unattended {
+                                    ui.label(RichText::new("Good for unattended loops").weak());
+                                }
+                            });
+
+                            ui.label(&metadata.description);
+                            ui.label(
+                                RichText::new(format!("By {}", metadata.author))
+                                    .small()
+                                    .weak(),
+                            );
+
+                            if !metadata.tags.is_empty() {
+                                ui.label(
+                                    RichText::new(format!("Tags: {}", metadata.tags.join(", ")))
+                                        .small()
+                                        .weak(),
+                                );
+                            }
+                        });
+                    });
+                });
+            }
+        }
+
+        clicked_effect
+    }
+
+    /// Display the GUI for choosing, saving, and sharing presets of the given effect and return the
+    /// message to send to the server, if any.
+    fn display_presets_gui(
+        ui: &mut egui::Ui,
+        effect: EffectNameList,
+        presets: &PresetLibrary,
+        gui: &mut PresetsGui,
+    ) -> Option<ClientToServerMsg> {
+        let mut message = None;
+
+        for (idx, (preset, user)) in presets.presets_for(effect).into_iter().enumerate() {
+            ui.push_id(idx, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button(preset.name.as_str()).clicked() {
+                        message = Some(ClientToServerMsg::SelectPreset(preset.name.clone()));
+                    }
+
+                    if user {
+                        if ui.small_button("Delete").clicked() {
+                            message = Some(ClientToServerMsg::DeletePreset(preset.name.clone()));
+                        }
+                    } else {
+                        ui.label(RichText::new("Built in").weak());
+                    }
+                });
+            });
+        }
+
+        ui.add_space(10.);
+
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut gui.new_name);
+
+            let name = gui.new_name.trim();
+            if ui
+                .add_enabled(!name.is_empty(), egui::Button::new("Save current config"))
+                .clicked()
+            {
+                message = Some(ClientToServerMsg::SavePreset(name.to_string()));
+                gui.new_name.clear();
+            }
+        });
+
+        ui.collapsing("Share presets", |ui| {
+            ui.label(
+                "Export all your saved presets to copy them to another tree, or paste presets \
+                exported from another tree and import them. Both RON and JSON work.",
+            );
+
+            ui.horizontal(|ui| {
+                if ui.button("Export as RON").clicked() {
+                    gui.bundle_text = presets.export().to_ron();
+                    gui.import_error = None;
+                }
+
+                if ui.button("Export as JSON").clicked() {
+                    gui.bundle_text = presets.export().to_json();
+                    gui.import_error = None;
+                }
+
+                if ui.button("Import").clicked() {
+                    match PresetBundle::parse(&gui.bundle_text) {
+                        Ok(bundle) => {
+                            message = Some(ClientToServerMsg::ImportPresets(bundle));
+                            gui.bundle_text.clear();
+                            gui.import_error = None;
+                        }
+                        Err(error) => gui.import_error = Some(error.to_string()),
+                    }
+                }
+            });
+
+            if let Some(error) = &gui.import_error {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+
+            ui.add(
+                egui::TextEdit::multiline(&mut gui.bundle_text)
+                    .code_editor()
+                    .desired_width(f32::INFINITY),
+            );
+        });
+
+        message
+    }
+
+    /// Display the GUI for editing the layers drawn on top of the current effect and return
+    /// whether any of the layers have changed.
+    fn display_layers_gui(
+        ctx: &eframe::egui::Context,
+        ui: &mut egui::Ui,
+        layers: &mut Vec<EffectLayer>,
+    ) -> bool {
+        let mut layers_changed = false;
+        let mut layer_to_remove = None;
+
+        ui.collapsing("Layers", |ui| {
+            ui.label("Layers are drawn on top of the current effect, in order from bottom to top.");
+
+            for (idx, layer) in layers.iter_mut().enumerate() {
+                ui.push_id(idx, |ui| {
+                    ui.separator();
+
+                    ui.horizontal(|ui| {
+                        let current_effect = EffectNameList::from(&layer.config);
+
+                        egui::ComboBox::from_label(format!("Layer {}", idx + 1))
+                            .selected_text(current_effect.effect_name())
+                            .show_ui(ui, |ui| {
+                                for effect in EffectNameList::iter() {
+                                    let clicked = ui
+                                        .selectable_label(
+                                            effect == current_effect,
+                                            effect.effect_name(),
+                                        )
+                                        .clicked();
+
+                                    if clicked && effect != current_effect {
+                                        layer.config = effect.config_name().default_dispatch();
+                                        layer.modulations.clear();
+                                        layers_changed = true;
+                                    }
+                                }
+                            });
+
+                        if ui.button("Remove").clicked() {
+                            layer_to_remove = Some(idx);
+                        }
+                    });
+
+                    layers_changed |= ui
+                        .add(egui::Slider::new(&mut layer.opacity, 0.0..=1.0).text("Opacity"))
+                        .changed();
+
+                    ui.horizontal(|ui| {
+                        ui.label("Blend mode:");
+                        for mode in BlendMode::iter() {
+                            layers_changed |= ui
+                                .selectable_value(&mut layer.blend_mode, mode, mode.name())
+                                .changed();
+                        }
+                    });
+
+                    ui.collapsing("Options", |ui| {
+                        layers_changed |= layer.config.render_full_options_gui(ctx, ui);
+                    });
+
+                    ui.collapsing("Modulations", |ui| {
+                        layers_changed |= Self::display_modulations_gui(
+                            ui,
+                            &mut layer.modulations,
+                            &layer.config,
+                        );
+                    });
+                });
+            }
+
+            ui.add_space(10.);
+
+            if ui
+                .add_enabled(layers.len() < MAX_LAYERS, egui::Button::new("Add layer"))
+                .clicked()
+            {
+                layers.push(EffectLayer::new(
+                    EffectNameList::Twinkle.config_name().default_dispatch(),
+                ));
+                layers_changed = true;
+            }
+        });
+
+        if let Some(idx) = layer_to_remove {
+            layers.remove(idx);
+            layers_changed = true;
+        }
+
+        layers_changed
+    }
+
+    /// Display the GUI for editing the modulations of the numeric fields of the given config and
+    /// return whether any of the modulations have changed.
+    fn display_modulations_gui(
+        ui: &mut egui::Ui,
+        modulations: &mut Vec<Modulation>,
+        config: &EffectConfigDispatchList,
+    ) -> bool {
+        let field_names = config.numeric_field_names();
+        let mut modulations_changed = false;
+        let mut modulation_to_remove = None;
+
+        if field_names.is_empty() {
+            ui.label("This effect has no numeric options to modulate.");
+            return false;
+        }
+
+        for (idx, modulation) in modulations.iter_mut().enumerate() {
+            ui.push_id(idx, |ui| {
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_label("Field")
+                        .selected_text(modulation.field.as_str())
+                        .show_ui(ui, |ui| {
+                            for &name in field_names {
+                                let clicked = ui
+                                    .selectable_label(modulation.field == name, name)
+                                    .clicked();
+
+                                if clicked && modulation.field != name {
+                                    if let Some(new) = Modulation::new(config, name) {
+                                        *modulation = Modulation {
+                                            shape: modulation.shape,
+                                            period_seconds: modulation.period_seconds,
+                                            phase: modulation.phase,
+                                            ..new
+                                        };
+                                    }
+                                    modulations_changed = true;
+                                }
+                            }
+                        });
+
+                    if ui.button("Remove").clicked() {
+                        modulation_to_remove = Some(idx);
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("Shape:");
+                    for shape in ModulationShape::iter() {
+                        modulations_changed |= ui
+                            .selectable_value(&mut modulation.shape, shape, shape.name())
+                            .changed();
+                    }
+                });
+
+                modulations_changed |= ui
+                    .add(
+                        egui::Slider::new(&mut modulation.period_seconds, 0.1..=120.0)
+                            .logarithmic(true)
+                            .suffix("s")
+                            .text("Period"),
+                    )
+                    .changed();
+
+                modulations_changed |= ui
+                    .add(egui::Slider::new(&mut modulation.phase, 0.0..=1.0).text("Phase"))
+                    .changed();
+
+                ui.horizontal(|ui| {
+                    ui.label("Range:");
+                    modulations_changed |= ui
+                        .add(egui::DragValue::new(&mut modulation.min).speed(0.01))
+                        .changed();
+                    ui.label("to");
+                    modulations_changed |= ui
+                        .add(egui::DragValue::new(&mut modulation.max).speed(0.01))
+                        .changed();
+                });
+            });
+        }
+
+        ui.add_space(10.);
+
+        if ui.button("Add modulation").clicked()
+            && let Some(modulation) = Modulation::new(config, field_names[0])
+        {
+            modulations.push(modulation);
+            modulations_changed = true;
+        }
+
+        if let Some(idx) = modulation_to_remove {
+            modulations.remove(idx);
+            modulations_changed = true;
+        }
+
+        modulations_changed
+    }
+
     /// Display the GUI for a protocol mismatch. This method assumes that [`Self::state`] is
     /// [`AppState::ProtocolMismatch`] and will panic if it's not.
     #[instrument(skip_all)]
@@ -0,0 +1,410 @@
+//! This is the build script for `ww-effects`. It finds all the effects so that they don't need to
+//! be registered by hand.
+//!
+//! Every subdirectory of `src/effects` with a `mod.rs` is a category, except `utils`. The modules
+//! of a category are declared as normal with `pub mod` in its `mod.rs`, and a struct in one of
+//! them which derives `BaseEffect` is an effect. The config of the effect must be a struct in the
+//! same file called the name of the effect followed by `Config`, which derives `BaseEffectConfig`.
+//! Every other file in a category must be declared, so that an effect can't be silently left out.
+//!
+//! This script writes these files into `OUT_DIR`, which get included by the crate:
+//! - `effects/<category>.rs` re-exports the effects and configs of the category. It gets included
+//!   by `src/effects/<category>/mod.rs`.
+//! - `effects/effects.rs` and `effects/configs.rs` re-export every effect and every config from
+//!   their categories. They get included by `src/effects/mod.rs`.
+//! - `effect_list.rs` calls `effect_proc_macros::generate_lists_and_impls!` with every effect
+//!   along with its category. It gets included by `src/lib.rs`.
+//! - `effect_metadata.rs` implements `EffectNameList::metadata` from the doc comment and the
+//!   `#[effect(...)]` attribute of every effect. See `src/metadata.rs`. It gets included by
+//!   `src/lib.rs`.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use syn::{Expr, ExprArray, Item, ItemStruct, Lit, LitStr, Meta, Visibility};
+
+/// The subdirectories of `src/effects` which aren't categories of effects.
+const NOT_CATEGORIES: &[&str] = &["utils"];
+
+/// An effect found in a category.
+struct FoundEffect {
+    /// The name of the module that the effect is in.
+    module: String,
+
+    /// The name of the effect.
+    name: String,
+
+    /// The metadata declared on the effect.
+    metadata: DeclaredMetadata,
+}
+
+/// The metadata declared on an effect with its doc comment and `#[effect(...)]` attribute.
+#[derive(Default)]
+struct DeclaredMetadata {
+    /// The first paragraph of the doc comment.
+    description: String,
+
+    /// The author, if it isn't the author of this crate.
+    author: Option<String>,
+
+    /// The tags.
+    tags: Vec<String>,
+
+    /// Whether the effect is good to leave looping on its own.
+    unattended: bool,
+}
+
+/// A category of effects.
+struct Category {
+    /// The name of the category, which is the name of its module.
+    name: String,
+
+    /// The effects in the category.
+    effects: Vec<FoundEffect>,
+}
+
+/// Check whether the struct has a `#[derive(...)]` attribute including the given trait.
+fn derives(item: &ItemStruct, trait_name: &str) -> bool {
+    item.attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("derive"))
+        .any(|attr| {
+            let mut found = false;
+            let _ = attr.parse_nested_meta(|meta| {
+                found |= meta.path.is_ident(trait_name);
+                Ok(())
+            });
+            found
+        })
+}
+
+/// Find all the structs in the items which derive the given trait, including those in inline
+/// modules.
+fn find_structs_deriving<'items>(
+    items: &'items [Item],
+    trait_name: &str,
+) -> Vec<&'items ItemStruct> {
+    items
+        .iter()
+        .flat_map(|item| match item {
+            Item::Struct(item) if derives(item, trait_name) => vec![item],
+            Item::Mod(item) => item.content.as_ref().map_or_else(Vec::new, |(_, items)| {
+                find_structs_deriving(items, trait_name)
+            }),
+            _ => Vec::new(),
+        })
+        .collect()
+}
+
+/// Get the first paragraph of the doc comment of the struct, as a single line.
+fn description(item: &ItemStruct) -> String {
+    item.attrs
+        .iter()
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(meta) if meta.path.is_ident("doc") => match &meta.value {
+                Expr::Lit(expr) => match &expr.lit {
+                    Lit::Str(doc) => Some(doc.value().trim().to_string()),
+                    _ => None,
+                },
+                _ => None,
+            },
+            _ => None,
+        })
+        .take_while(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Read the metadata declared on the effect. See the module docs.
+fn declared_metadata(item: &ItemStruct) -> syn::Result<DeclaredMetadata> {
+    let mut metadata = DeclaredMetadata {
+        description: description(item),
+        ..Default::default()
+    };
+
+    for attr in item
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("effect"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("author") {
+                metadata.author = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("tags") {
+                let array: ExprArray = meta.value()?.parse()?;
+                for tag in array.elems {
+                    match tag {
+                        Expr::Lit(expr) if matches!(expr.lit, Lit::Str(_)) => {
+                            if let Lit::Str(tag) = expr.lit {
+                                metadata.tags.push(tag.value());
+                            }
+                        }
+                        other => {
+                            return Err(syn::Error::new_spanned(other, "Tags must be strings"));
+                        }
+                    }
+                }
+            } else if meta.path.is_ident("unattended") {
+                metadata.unattended = true;
+            } else {
+                return Err(meta.error("Expected `author`, `tags`, or `unattended`"));
+            }
+            Ok(())
+        })?;
+    }
+
+    Ok(metadata)
+}
+
+/// Find the effects in the file of the given module, and check that each one has a config.
+fn find_effects(module: &str, path: &Path) -> Result<Vec<FoundEffect>, String> {
+    let source =
+        fs::read_to_string(path).map_err(|e| format!("Unable to read {}: {e}", path.display()))?;
+    let file =
+        syn::parse_file(&source).map_err(|e| format!("Unable to parse {}: {e}", path.display()))?;
+
+    let configs: Vec<String> = find_structs_deriving(&file.items, "BaseEffectConfig")
+        .into_iter()
+        .map(|item| item.ident.to_string())
+        .collect();
+    find_structs_deriving(&file.items, "BaseEffect")
+        .into_iter()
+        .map(|item| {
+            let name = item.ident.to_string();
+            if configs.contains(&format!("{name}Config")) {
+                Ok(FoundEffect {
+                    module: module.to_string(),
+                    metadata: declared_metadata(item).map_err(|e| {
+                        format!("Bad metadata on {name} in {}: {e}", path.display())
+                    })?,
+                    name,
+                })
+            } else {
+                Err(format!(
+                    "The effect {name} in {} has no {name}Config deriving BaseEffectConfig",
+                    path.display()
+                ))
+            }
+        })
+        .collect()
+}
+
+/// Get the sorted names and paths of the entries in the given directory which pass the filter.
+fn sorted_entries(dir: &Path, filter: impl Fn(&Path) -> bool) -> Result<Vec<PathBuf>, String> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .map_err(|e| format!("Unable to read {}: {e}", dir.display()))?
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| filter(path))
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
+/// Get the file stem of the path as a string.
+fn stem(path: &Path) -> String {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .map_or_else(String::new, ToString::to_string)
+}
+
+/// Get the names of the modules declared with `pub mod` in the given `mod.rs` file.
+fn declared_modules(mod_file: &Path) -> Result<Vec<String>, String> {
+    let source = fs::read_to_string(mod_file)
+        .map_err(|e| format!("Unable to read {}: {e}", mod_file.display()))?;
+    let file = syn::parse_file(&source)
+        .map_err(|e| format!("Unable to parse {}: {e}", mod_file.display()))?;
+
+    Ok(file
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Mod(item)
+                if item.content.is_none() && matches!(item.vis, Visibility::Public(_)) =>
+            {
+                Some(item.ident.to_string())
+            }
+            _ => None,
+        })
+        .collect())
+}
+
+/// Find all the categories in the given directory and the effects in them.
+fn find_categories(effects_dir: &Path) -> Result<Vec<Category>, String> {
+    let category_dirs = sorted_entries(effects_dir, |path| {
+        path.join("mod.rs").is_file() && !NOT_CATEGORIES.contains(&stem(path).as_str())
+    })?;
+
+    let mut categories = Vec::new();
+    for dir in category_dirs {
+        let declared = declared_modules(&dir.join("mod.rs"))?;
+        let mut effects = Vec::new();
+
+        for path in sorted_entries(&dir, |path| {
+            path.extension().is_some_and(|ext| ext == "rs") && stem(path) != "mod"
+        })? {
+            let module = stem(&path);
+            if !declared.contains(&module) {
+                return Err(format!(
+                    "{} isn't declared in {}. Add `pub mod {module};` to it",
+                    path.display(),
+                    dir.join("mod.rs").display()
+                ));
+            }
+            effects.extend(find_effects(&module, &path)?);
+        }
+
+        categories.push(Category {
+            name: stem(&dir),
+            effects,
+        });
+    }
+
+    Ok(categories)
+}
+
+/// Write the file for the given category. See the module docs.
+fn category_file(category: &Category) -> String {
+    category
+        .effects
+        .iter()
+        .map(|FoundEffect { module, name, .. }| {
+            format!(
+                "#[cfg(feature = \"effect-impls\")]\npub use self::{module}::{name};\n\
+                 #[cfg(feature = \"config-impls\")]\npub use self::{module}::{name}Config;\n"
+            )
+        })
+        .collect()
+}
+
+/// Write the file to re-export every effect or every config, with the given suffix on their names.
+fn reexports_file(categories: &[Category], suffix: &str) -> String {
+    categories
+        .iter()
+        .flat_map(|category| {
+            category.effects.iter().map(move |effect| {
+                format!(
+                    "pub use super::{}::{}{suffix};\n",
+                    category.name, effect.name
+                )
+            })
+        })
+        .collect()
+}
+
+/// Write the file which generates the effect lists.
+fn effect_list_file(categories: &[Category]) -> String {
+    let effects: String = categories
+        .iter()
+        .flat_map(|category| {
+            category
+                .effects
+                .iter()
+                .map(move |effect| format!("    {}::{},\n", category.name, effect.name))
+        })
+        .collect();
+
+    format!("effect_proc_macros::generate_lists_and_impls! {{\n{effects}}}\n")
+}
+
+/// Write the file which implements `EffectNameList::metadata`, using the given author for effects
+/// which don't declare one.
+fn effect_metadata_file(categories: &[Category], default_author: &str) -> String {
+    let arms: String = categories
+        .iter()
+        .flat_map(|category| &category.effects)
+        .map(|effect| {
+            let DeclaredMetadata {
+                description,
+                author,
+                tags,
+                unattended,
+            } = &effect.metadata;
+            let author = author.as_deref().unwrap_or(default_author);
+            format!(
+                "            Self::{} => ({description:?}, {author:?}, &{tags:?}, {unattended}),\n",
+                effect.name
+            )
+        })
+        .collect();
+
+    format!(
+        "impl EffectNameList {{
+    /// Get the [metadata](crate::metadata) of the effect, without a thumbnail.
+    pub fn metadata(&self) -> crate::metadata::EffectMetadata {{
+        let (description, author, tags, unattended): (&str, &str, &[&str], bool) = match self {{
+{arms}        }};
+
+        crate::metadata::EffectMetadata {{
+            effect: *self,
+            description: description.to_string(),
+            category: self.category(),
+            author: author.to_string(),
+            tags: tags.iter().map(ToString::to_string).collect(),
+            unattended,
+            thumbnail: None,
+        }}
+    }}
+}}
+"
+    )
+}
+
+/// Write the file into the given directory, only touching it if its contents have changed.
+fn write_if_changed(path: &Path, text: &str) -> Result<(), String> {
+    if fs::read_to_string(path).is_ok_and(|old| old == text) {
+        return Ok(());
+    }
+
+    fs::write(path, text).map_err(|e| format!("Unable to write {}: {e}", path.display()))
+}
+
+fn main() -> Result<(), String> {
+    let manifest_dir =
+        PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").map_err(|e| e.to_string())?);
+    let out_dir = PathBuf::from(std::env::var("OUT_DIR").map_err(|e| e.to_string())?);
+    let effects_dir = manifest_dir.join("src/effects");
+
+    // Cargo checks every file in the directory, so this catches new effects as well as changes
+    println!("cargo:rerun-if-changed={}", effects_dir.display());
+
+    let categories = find_categories(&effects_dir)?;
+
+    let generated_dir = out_dir.join("effects");
+    fs::create_dir_all(&generated_dir)
+        .map_err(|e| format!("Unable to create {}: {e}", generated_dir.display()))?;
+
+    for category in &categories {
+        write_if_changed(
+            &generated_dir.join(format!("{}.rs", category.name)),
+            &category_file(category),
+        )?;
+    }
+    write_if_changed(
+        &generated_dir.join("effects.rs"),
+        &reexports_file(&categories, ""),
+    )?;
+    write_if_changed(
+        &generated_dir.join("configs.rs"),
+        &reexports_file(&categories, "Config"),
+    )?;
+    write_if_changed(
+        &out_dir.join("effect_list.rs"),
+        &effect_list_file(&categories),
+    )?;
+
+    // The authors look like `Name <email>:Other Name <email>`, and we only want the first name
+    let authors = std::env::var("CARGO_PKG_AUTHORS").unwrap_or_default();
+    let default_author = authors
+        .split(':')
+        .next()
+        .and_then(|author| author.split('<').next())
+        .unwrap_or_default()
+        .trim();
+    write_if_changed(
+        &out_dir.join("effect_metadata.rs"),
+        &effect_metadata_file(&categories, default_author),
+    )?;
+
+    Ok(())
+}
@@ -3,7 +3,20 @@
 use proc_macro::TokenStream;
 use proc_macro2::{Ident as Ident2, Literal, TokenStream as TokenStream2};
 use quote::{format_ident, quote};
-use syn::{DeriveInput, parse_macro_input};
+use syn::{
+    Attribute, Data, DataStruct, DeriveInput, Error, Expr, ExprLit, Fields, GenericArgument, Lit,
+    Meta, MetaNameValue, PathArguments, Result, Token, Type, parse::ParseStream, parse_macro_input,
+};
+
+/// The primitive integer types, which are a subset of [`NUMERIC_TYPES`].
+const INTEGER_TYPES: &[&str] = &[
+    "u8", "u16", "u32", "u64", "usize", "i8", "i16", "i32", "i64", "isize",
+];
+
+/// The primitive types which can be addressed as numeric fields.
+const NUMERIC_TYPES: &[&str] = &[
+    "f32", "f64", "u8", "u16", "u32", "u64", "usize", "i8", "i16", "i32", "i64", "isize",
+];
 
 /// Derive the `BaseEffect` trait.
 pub fn derive_base_effect(input: TokenStream) -> TokenStream {
@@ -41,6 +54,11 @@ pub fn derive_base_effect_config(input: TokenStream) -> TokenStream {
         "{} config",
         &struct_name.to_string().replace("Config", "")
     ));
+    let numeric_fields_impl = create_numeric_fields_impl(&input.data);
+    let schema_impl = match create_schema_impl(&struct_name, &input.attrs, &input.data) {
+        Ok(schema_impl) => schema_impl,
+        Err(e) => return e.to_compile_error().into(),
+    };
 
     quote! {
         #sealed_impl
@@ -63,11 +81,383 @@ pub fn derive_base_effect_config(input: TokenStream) -> TokenStream {
 
                 config_changed
             }
+
+            #numeric_fields_impl
+
+            #schema_impl
         }
     }
     .into()
 }
 
+/// A single numeric value somewhere in a config struct.
+struct NumericField {
+    /// The name used to address the field, like `fadeoff` or `colour.0`.
+    name: String,
+
+    /// An expression to access the field from `self`, like `self.colour[0]`.
+    access: TokenStream2,
+
+    /// The primitive type of the field.
+    ty: Ident2,
+}
+
+/// Find all the numeric values in a field of the given type, recursing into arrays.
+fn find_numeric_fields(
+    ty: &Type,
+    name: String,
+    access: TokenStream2,
+    numeric_fields: &mut Vec<NumericField>,
+) {
+    match ty {
+        Type::Path(type_path) => {
+            let Some(ident) = type_path.path.get_ident() else {
+                return;
+            };
+
+            if NUMERIC_TYPES.contains(&ident.to_string().as_str()) {
+                numeric_fields.push(NumericField {
+                    name,
+                    access,
+                    ty: ident.clone(),
+                });
+            } else if ident == "RGBArray" {
+                for i in 0..3_usize {
+                    numeric_fields.push(NumericField {
+                        name: format!("{name}.{i}"),
+                        access: quote! { #access[#i] },
+                        ty: Ident2::new("u8", ident.span()),
+                    });
+                }
+            }
+        }
+        Type::Array(type_array) => {
+            let Expr::Lit(ExprLit {
+                lit: Lit::Int(len), ..
+            }) = &type_array.len
+            else {
+                return;
+            };
+
+            for i in 0..len.base10_parse::<usize>().unwrap_or(0) {
+                find_numeric_fields(
+                    &type_array.elem,
+                    format!("{name}.{i}"),
+                    quote! { #access[#i] },
+                    numeric_fields,
+                );
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Create the methods of `BaseEffectConfig` which read and write numeric fields by name.
+fn create_numeric_fields_impl(data: &Data) -> TokenStream2 {
+    let mut numeric_fields = vec![];
+
+    if let Data::Struct(DataStruct {
+        fields: Fields::Named(fields),
+        ..
+    }) = data
+    {
+        for field in &fields.named {
+            let Some(ident) = &field.ident else {
+                continue;
+            };
+
+            find_numeric_fields(
+                &field.ty,
+                ident.to_string(),
+                quote! { self.#ident },
+                &mut numeric_fields,
+            );
+        }
+    }
+
+    if numeric_fields.is_empty() {
+        return quote! {
+            fn numeric_field_names() -> &'static [&'static str] {
+                &[]
+            }
+
+            fn get_numeric_field(&self, _name: &str) -> Option<f32> {
+                None
+            }
+
+            fn set_numeric_field(&mut self, _name: &str, _value: f32) -> bool {
+                false
+            }
+        };
+    }
+
+    let names: Vec<_> = numeric_fields
+        .iter()
+        .map(|field| Literal::string(&field.name))
+        .collect();
+
+    let get_arms = numeric_fields.iter().zip(&names).map(|(field, name)| {
+        let access = &field.access;
+        if field.ty == "f32" {
+            quote! { #name => Some(#access) }
+        } else {
+            quote! { #name => Some(#access as f32) }
+        }
+    });
+
+    let set_arms = numeric_fields.iter().zip(&names).map(|(field, name)| {
+        let access = &field.access;
+        let ty = &field.ty;
+        let value = if field.ty == "f32" {
+            quote! { value }
+        } else if field.ty == "f64" {
+            quote! { f64::from(value) }
+        } else {
+            quote! { value.round() as #ty }
+        };
+
+        quote! {
+            #name => {
+                #access = #value;
+                true
+            }
+        }
+    });
+
+    quote! {
+        fn numeric_field_names() -> &'static [&'static str] {
+            &[ #( #names ),* ]
+        }
+
+        fn get_numeric_field(&self, name: &str) -> Option<f32> {
+            match name {
+                #( #get_arms, )*
+                _ => None,
+            }
+        }
+
+        fn set_numeric_field(&mut self, name: &str, value: f32) -> bool {
+            match name {
+                #( #set_arms )*
+                _ => false,
+            }
+        }
+    }
+}
+
+/// The hints given to the schema by `#[schema(...)]` attributes on a field.
+#[derive(Default)]
+struct SchemaHints {
+    /// The range of sensible values for a numeric field, from `#[schema(range = start..=end)]`.
+    range: Option<(f64, f64)>,
+
+    /// Whether the range is only a suggestion, from `#[schema(unclamped)]`.
+    unclamped: bool,
+
+    /// Whether an array of 3 `u8`s is a colour, from `#[schema(colour)]`. Fields of type
+    /// `RGBArray` are always colours.
+    colour: bool,
+}
+
+/// Parse all the `#[schema(...)]` attributes on a field.
+fn parse_schema_hints(attrs: &[Attribute]) -> Result<SchemaHints> {
+    let mut hints = SchemaHints::default();
+
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("schema")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("range") {
+                let value = meta.value()?;
+                let start = parse_number(value)?;
+                value.parse::<Token![..=]>()?;
+                let end = parse_number(value)?;
+
+                hints.range = Some((start, end));
+                Ok(())
+            } else if meta.path.is_ident("unclamped") {
+                hints.unclamped = true;
+                Ok(())
+            } else if meta.path.is_ident("colour") {
+                hints.colour = true;
+                Ok(())
+            } else {
+                Err(meta.error("expected `range = start..=end`, `unclamped`, or `colour`"))
+            }
+        })?;
+    }
+
+    Ok(hints)
+}
+
+/// Parse a number literal with an optional minus sign.
+fn parse_number(input: ParseStream) -> Result<f64> {
+    let negative = input.parse::<Option<Token![-]>>()?.is_some();
+    let value = match input.parse::<Lit>()? {
+        Lit::Int(int) => int.base10_parse::<f64>()?,
+        Lit::Float(float) => float.base10_parse::<f64>()?,
+        lit => return Err(Error::new(lit.span(), "expected a number")),
+    };
+
+    Ok(if negative { -value } else { value })
+}
+
+/// Get the doc comment from a list of attributes, with the leading space of each line removed.
+fn doc_comment(attrs: &[Attribute]) -> String {
+    let lines: Vec<String> = attrs
+        .iter()
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(MetaNameValue {
+                path,
+                value:
+                    Expr::Lit(ExprLit {
+                        lit: Lit::Str(text),
+                        ..
+                    }),
+                ..
+            }) if path.is_ident("doc") => {
+                let line = text.value();
+                Some(line.strip_prefix(' ').unwrap_or(&line).to_string())
+            }
+            _ => None,
+        })
+        .collect();
+
+    lines.join("\n").trim().to_string()
+}
+
+/// Create an expression for the `FieldType` of a field with the given type.
+fn field_type_expr(ty: &Type, colour: bool) -> TokenStream2 {
+    let field_type = quote! { crate::schema::FieldType };
+
+    match ty {
+        Type::Path(type_path) => {
+            let Some(segment) = type_path.path.segments.last() else {
+                return quote! { #field_type::Other(::std::string::String::new()) };
+            };
+            let name = segment.ident.to_string();
+
+            if name == "RGBArray" {
+                quote! { #field_type::Colour }
+            } else if name == "f32" || name == "f64" {
+                quote! { #field_type::Float }
+            } else if INTEGER_TYPES.contains(&name.as_str()) {
+                let signed = name.starts_with('i');
+                quote! { #field_type::Integer { signed: #signed } }
+            } else if name == "bool" {
+                quote! { #field_type::Bool }
+            } else if let ("Vec", PathArguments::AngleBracketed(args)) =
+                (name.as_str(), &segment.arguments)
+                && let Some(GenericArgument::Type(element)) = args.args.first()
+            {
+                let element = field_type_expr(element, colour);
+                quote! { #field_type::List(::std::boxed::Box::new(#element)) }
+            } else {
+                let name = quote! { #ty }.to_string().replace(' ', "");
+                quote! { #field_type::Other(::std::string::String::from(#name)) }
+            }
+        }
+        Type::Array(type_array) => {
+            let len = match &type_array.len {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Int(len), ..
+                }) => len.base10_parse::<usize>().unwrap_or(0),
+                _ => 0,
+            };
+
+            let is_u8 = matches!(&*type_array.elem, Type::Path(path) if path.path.is_ident("u8"));
+            if colour && is_u8 && len == 3 {
+                quote! { #field_type::Colour }
+            } else {
+                let element = field_type_expr(&type_array.elem, colour);
+                quote! {
+                    #field_type::Array {
+                        element: ::std::boxed::Box::new(#element),
+                        len: #len,
+                    }
+                }
+            }
+        }
+        _ => {
+            let name = quote! { #ty }.to_string().replace(' ', "");
+            quote! { #field_type::Other(::std::string::String::from(#name)) }
+        }
+    }
+}
+
+/// Create the `schema` method of `BaseEffectConfig`.
+fn create_schema_impl(
+    struct_name: &Ident2,
+    struct_attrs: &[Attribute],
+    data: &Data,
+) -> Result<TokenStream2> {
+    let effect_name = struct_name.to_string().replace("Config", "");
+    let description = doc_comment(struct_attrs);
+    let mut fields = vec![];
+
+    if let Data::Struct(DataStruct {
+        fields: Fields::Named(named),
+        ..
+    }) = data
+    {
+        for field in &named.named {
+            let Some(ident) = &field.ident else {
+                continue;
+            };
+
+            let name = ident.to_string();
+            let field_description = doc_comment(&field.attrs);
+            let hints = parse_schema_hints(&field.attrs)?;
+            let field_type = field_type_expr(&field.ty, hints.colour);
+            let range = match hints.range {
+                Some((start, end)) => {
+                    let start = signed_f64(start);
+                    let end = signed_f64(end);
+                    quote! { ::std::option::Option::Some((#start, #end)) }
+                }
+                None => quote! { ::std::option::Option::None },
+            };
+            let clamped = !hints.unclamped;
+
+            fields.push(quote! {
+                crate::schema::FieldSchema {
+                    name: ::std::string::String::from(#name),
+                    description: ::std::string::String::from(#field_description),
+                    field_type: #field_type,
+                    range: #range,
+                    clamped: #clamped,
+                    default: defaults.get(#name).cloned().unwrap_or_default(),
+                }
+            });
+        }
+    }
+
+    Ok(quote! {
+        fn schema() -> &'static crate::schema::ConfigSchema {
+            static SCHEMA: ::std::sync::OnceLock<crate::schema::ConfigSchema> =
+                ::std::sync::OnceLock::new();
+
+            SCHEMA.get_or_init(|| {
+                let defaults = ::serde_json::to_value(Self::default()).unwrap_or_default();
+
+                crate::schema::ConfigSchema {
+                    effect_name: ::std::string::String::from(#effect_name),
+                    description: ::std::string::String::from(#description),
+                    fields: ::std::vec![ #( #fields ),* ],
+                }
+            })
+        }
+    })
+}
+
+/// Create an `f64` literal which may be negative.
+fn signed_f64(value: f64) -> TokenStream2 {
+    let literal = Literal::f64_suffixed(value.abs());
+    if value < 0. {
+        quote! { -#literal }
+    } else {
+        quote! { #literal }
+    }
+}
+
 /// Create an implementation of `Sealed` for a type with the given name.
 fn create_sealed_impl(name: &Ident2) -> TokenStream2 {
     quote! {
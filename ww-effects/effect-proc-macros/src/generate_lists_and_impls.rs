@@ -1,8 +1,8 @@
 //! Handle the [`generate_lists_and_impls`] macro.
 
-use proc_macro2::{Ident, Span, TokenStream, TokenTree};
+use proc_macro2::{Ident, TokenStream};
 use quote::{format_ident, quote};
-use syn::{Error, Result};
+use syn::{Error, Path, Result, Token, parse::Parser, punctuated::Punctuated};
 
 /// Generate the name and dispatch lists as well as their implementations.
 pub fn generate_lists_and_impls(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
@@ -14,13 +14,14 @@ pub fn generate_lists_and_impls(input: proc_macro::TokenStream) -> proc_macro::T
 
 /// Generate the name and dispatch lists as well as their implementations.
 fn generate_lists_and_impls2(input: TokenStream) -> Result<TokenStream> {
-    let effect_names = get_effect_names(input)?;
+    let (categories, effect_names) = get_categories_and_effect_names(input)?;
     let config_names: Vec<Ident> = effect_names
         .iter()
         .map(|ident| format_ident!("{ident}Config"))
         .collect();
 
     let name_lists = create_name_lists(&effect_names, &config_names);
+    let category_list = create_category_list(&categories, &effect_names);
     let dispatch_lists = create_dispatch_lists(&effect_names, &config_names);
     let impls = impl_lists(&effect_names, &config_names);
     let from_impls = impl_from_lists(&effect_names);
@@ -39,50 +40,121 @@ fn generate_lists_and_impls2(input: TokenStream) -> Result<TokenStream> {
         use crate::effects::effects::*;
 
         #name_lists
+        #category_list
         #dispatch_lists
         #impls
         #from_impls
     })
 }
 
-/// Get a list of the names of the effects from the input.
-fn get_effect_names(input: TokenStream) -> Result<Vec<Ident>> {
-    let mut effect_names: Vec<Ident> = Vec::new();
-    let mut last_elem_was_ident = false;
+/// Get the category and name of each effect from the input, which is a list of comma-separated
+/// paths like `maths::Helix`.
+fn get_categories_and_effect_names(input: TokenStream) -> Result<(Vec<Ident>, Vec<Ident>)> {
+    let paths = Punctuated::<Path, Token![,]>::parse_terminated.parse2(input)?;
 
-    for tt in input {
-        match tt {
-            TokenTree::Ident(ident) => {
-                if last_elem_was_ident {
-                    return Err(Error::new(
-                        ident.span(),
-                        "Effect identifiers must be separated with commas",
-                    ));
+    paths
+        .iter()
+        .map(
+            |path| match path.segments.iter().collect::<Vec<_>>().as_slice() {
+                [category, effect]
+                    if category.arguments.is_none() && effect.arguments.is_none() =>
+                {
+                    Ok((category.ident.clone(), effect.ident.clone()))
                 }
+                _ => Err(Error::new_spanned(
+                    path,
+                    "Effects must be given as `category::EffectName`",
+                )),
+            },
+        )
+        .collect::<Result<Vec<_>>>()
+        .map(|pairs| pairs.into_iter().unzip())
+}
+
+/// Convert the name of a category module like `maths` into a variant name like `Maths`.
+fn category_variant(category: &Ident) -> Ident {
+    let name: String = category
+        .to_string()
+        .split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            chars.next().map_or_else(String::new, |first| {
+                first.to_uppercase().chain(chars).collect::<String>()
+            })
+        })
+        .collect();
+    Ident::new(&name, category.span())
+}
+
+/// Create the `EffectCategory` enum and the method to get the category of an effect.
+fn create_category_list(categories: &[Ident], effect_names: &[Ident]) -> TokenStream {
+    let mut unique_categories: Vec<&Ident> = Vec::new();
+    for category in categories {
+        if !unique_categories.contains(&category) {
+            unique_categories.push(category);
+        }
+    }
+
+    let category_items: Vec<_> = unique_categories
+        .iter()
+        .map(|category| {
+            let variant = category_variant(category);
+            let doc_comment = format!(" The effects in the `{category}` module.");
+            quote! {
+                #[doc = #doc_comment]
+                #variant
+            }
+        })
+        .collect();
 
-                effect_names.push(ident);
-                last_elem_was_ident = true;
+    let category_names: Vec<_> = unique_categories
+        .iter()
+        .map(|category| {
+            let variant = category_variant(category);
+            let string = variant.to_string();
+            quote! {
+                EffectCategory:: #variant => #string
             }
-            TokenTree::Punct(punct) => {
-                if punct.as_char() != ',' {
-                    return Err(Error::new(
-                        punct.span(),
-                        "Effect identifiers must be separated with commas",
-                    ));
-                }
+        })
+        .collect();
 
-                last_elem_was_ident = false;
+    let effect_categories: Vec<_> = categories
+        .iter()
+        .zip(effect_names)
+        .map(|(category, ident)| {
+            let variant = category_variant(category);
+            quote! {
+                EffectNameList:: #ident => EffectCategory:: #variant
+            }
+        })
+        .collect();
+
+    quote! {
+        /// The categories of effects, which are the submodules of `crate::effects` that the
+        /// effects live in. These are used to organise the effects in the UI.
+        #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, ::strum::EnumIter, ::serde::Serialize, ::serde::Deserialize)]
+        pub enum EffectCategory {
+            #( #category_items ),*
+        }
+
+        impl EffectCategory {
+            /// Get the name of the category for the UI.
+            pub fn name(&self) -> &'static str {
+                match self {
+                    #( #category_names ),*
+                }
             }
-            _ => {
-                return Err(syn::Error::new(
-                    Span::call_site(),
-                    "`generate_lists_and_impls!()` takes a list of comma-separated effect identifiers",
-                ));
+        }
+
+        impl EffectNameList {
+            /// Get the category of the effect.
+            pub fn category(&self) -> EffectCategory {
+                match self {
+                    #( #effect_categories ),*
+                }
             }
         }
     }
-
-    Ok(effect_names)
 }
 
 /// Create the `*NameList` enums for the effects and configs.
@@ -258,7 +330,7 @@ fn impl_lists(effect_names: &[Ident], config_names: &[Ident]) -> TokenStream {
                 (
                     EffectDispatchList:: #ident (effect),
                     EffectConfigDispatchList:: #config_ident (config)
-                ) => effect.next_frame(config)
+                ) => effect.next_frame(config, ctx)
             }
         })
         .collect();
@@ -317,6 +389,31 @@ fn impl_lists(effect_names: &[Ident], config_names: &[Ident]) -> TokenStream {
         })
         .collect();
 
+    let config_name_list_schemas: Vec<_> = config_names
+        .iter()
+        .map(|ident| {
+            quote! {
+                EffectConfigNameList:: #ident => #ident ::schema()
+            }
+        })
+        .collect();
+
+    let config_name_list_factory_presets: Vec<_> = config_names
+        .iter()
+        .map(|ident| {
+            quote! {
+                EffectConfigNameList:: #ident => {
+                    ::std::iter::once((crate::presets::DEFAULT_PRESET_NAME, #ident ::default()))
+                        .chain(#ident ::factory_presets())
+                        .map(|(name, config)| {
+                            crate::presets::Preset::new(name, EffectConfigDispatchList:: #ident (config))
+                        })
+                        .collect()
+                }
+            }
+        })
+        .collect();
+
     let config_dispatch_list_render_full_options_guis: Vec<_> = config_names
         .iter()
         .map(|ident| {
@@ -335,6 +432,51 @@ fn impl_lists(effect_names: &[Ident], config_names: &[Ident]) -> TokenStream {
         })
         .collect();
 
+    let config_dispatch_list_numeric_field_names: Vec<_> = config_names
+        .iter()
+        .map(|ident| {
+            quote! {
+                EffectConfigDispatchList:: #ident (_) => #ident ::numeric_field_names()
+            }
+        })
+        .collect();
+
+    let config_dispatch_list_get_numeric_fields: Vec<_> = config_names
+        .iter()
+        .map(|ident| {
+            quote! {
+                EffectConfigDispatchList:: #ident (config) => config.get_numeric_field(name)
+            }
+        })
+        .collect();
+
+    let config_dispatch_list_set_numeric_fields: Vec<_> = config_names
+        .iter()
+        .map(|ident| {
+            quote! {
+                EffectConfigDispatchList:: #ident (config) => config.set_numeric_field(name, value)
+            }
+        })
+        .collect();
+
+    let config_dispatch_list_validates: Vec<_> = config_names
+        .iter()
+        .map(|ident| {
+            quote! {
+                EffectConfigDispatchList:: #ident (config) => config.validate()
+            }
+        })
+        .collect();
+
+    let config_dispatch_list_clamp_to_ranges: Vec<_> = config_names
+        .iter()
+        .map(|ident| {
+            quote! {
+                EffectConfigDispatchList:: #ident (config) => config.clamp_to_ranges()
+            }
+        })
+        .collect();
+
     let config_dispatch_list_effect_names: Vec<_> = effect_names
         .iter()
         .map(|ident| {
@@ -400,6 +542,7 @@ fn impl_lists(effect_names: &[Ident], config_names: &[Ident]) -> TokenStream {
             pub fn next_frame(
                 &mut self,
                 config: &EffectConfigDispatchList,
+                ctx: &crate::timing::FrameContext,
             ) -> Option<(::ww_frame::FrameType, ::std::time::Duration)> {
                 match (self, config) {
                     #( #effect_dispatch_list_next_frame ),*,
@@ -453,6 +596,33 @@ fn impl_lists(effect_names: &[Ident], config_names: &[Ident]) -> TokenStream {
                     #( #config_name_list_default_dispatch ),*
                 }
             }
+
+            /// Get the schema describing the fields of this config. See
+            /// [`BaseEffectConfig::schema`](crate::traits::BaseEffectConfig::schema).
+            #[cfg(feature = "config-impls")]
+            pub fn schema(&self) -> &'static crate::schema::ConfigSchema {
+                use crate::traits::BaseEffectConfig;
+
+                match self {
+                    #( #config_name_list_schemas ),*
+                }
+            }
+
+            /// Get the schema of this config as a [JSON Schema](https://json-schema.org/). See
+            /// [`ConfigSchema::to_json_schema`](crate::schema::ConfigSchema::to_json_schema).
+            #[cfg(feature = "config-impls")]
+            pub fn json_schema(&self) -> ::serde_json::Value {
+                self.schema().to_json_schema()
+            }
+
+            /// Get the built-in presets for this config, starting with the default config. See
+            /// [`EffectConfig::factory_presets`].
+            #[cfg(feature = "config-impls")]
+            pub fn factory_presets(&self) -> Vec<crate::presets::Preset> {
+                match self {
+                    #( #config_name_list_factory_presets ),*
+                }
+            }
         }
 
         #[cfg(feature = "config-impls")]
@@ -466,6 +636,56 @@ fn impl_lists(effect_names: &[Ident], config_names: &[Ident]) -> TokenStream {
                 }
             }
 
+            /// Get the names of the numeric fields in the config. See
+            /// [`BaseEffectConfig::numeric_field_names`](crate::traits::BaseEffectConfig::numeric_field_names).
+            pub fn numeric_field_names(&self) -> &'static [&'static str] {
+                use crate::traits::BaseEffectConfig;
+
+                match self {
+                    #( #config_dispatch_list_numeric_field_names ),*
+                }
+            }
+
+            /// Get the value of a numeric field in the config. See
+            /// [`BaseEffectConfig::get_numeric_field`](crate::traits::BaseEffectConfig::get_numeric_field).
+            pub fn get_numeric_field(&self, name: &str) -> Option<f32> {
+                use crate::traits::BaseEffectConfig;
+
+                match self {
+                    #( #config_dispatch_list_get_numeric_fields ),*
+                }
+            }
+
+            /// Set a numeric field in the config. See
+            /// [`BaseEffectConfig::set_numeric_field`](crate::traits::BaseEffectConfig::set_numeric_field).
+            pub fn set_numeric_field(&mut self, name: &str, value: f32) -> bool {
+                use crate::traits::BaseEffectConfig;
+
+                match self {
+                    #( #config_dispatch_list_set_numeric_fields ),*
+                }
+            }
+
+            /// Check that every numeric field of the config is in range. See
+            /// [`EffectConfig::validate`].
+            ///
+            /// # Errors
+            ///
+            /// This method returns an error for each field which is out of range.
+            pub fn validate(&self) -> Result<(), Vec<crate::schema::RangeError>> {
+                match self {
+                    #( #config_dispatch_list_validates ),*
+                }
+            }
+
+            /// Clamp every numeric field of the config into range. See
+            /// [`EffectConfig::clamp_to_ranges`].
+            pub fn clamp_to_ranges(&mut self) {
+                match self {
+                    #( #config_dispatch_list_clamp_to_ranges ),*
+                }
+            }
+
             /// Save the config to its file.
             pub fn save_to_file(&self, filename: &str) {
                 match self {
@@ -523,6 +743,28 @@ fn impl_from_lists(effect_names: &[Ident]) -> TokenStream {
         })
         .collect();
 
+    let config_dispatch_list_to_effect_name: Vec<_> = effect_names
+        .iter()
+        .map(|ident| {
+            let config_name = format_ident!("{ident}Config");
+            quote! {
+                EffectConfigDispatchList:: #config_name (_) => EffectNameList:: #ident
+            }
+        })
+        .collect();
+
+    let config_dispatch_list_to_effect_dispatch: Vec<_> = effect_names
+        .iter()
+        .map(|ident| {
+            let config_name = format_ident!("{ident}Config");
+            quote! {
+                EffectConfigDispatchList:: #config_name (config) => {
+                    EffectDispatchList:: #ident ( #ident ::from_config(config))
+                }
+            }
+        })
+        .collect();
+
     quote! {
         #[cfg(feature = "config-impls")]
         impl From<EffectConfigNameList> for EffectConfigDispatchList {
@@ -531,6 +773,24 @@ fn impl_from_lists(effect_names: &[Ident]) -> TokenStream {
             }
         }
 
+        #[cfg(feature = "config-impls")]
+        impl From<&EffectConfigDispatchList> for EffectNameList {
+            fn from(value: &EffectConfigDispatchList) -> Self {
+                match value {
+                    #( #config_dispatch_list_to_effect_name ),*
+                }
+            }
+        }
+
+        #[cfg(feature = "effect-impls")]
+        impl From<EffectConfigDispatchList> for EffectDispatchList {
+            fn from(value: EffectConfigDispatchList) -> Self {
+                match value {
+                    #( #config_dispatch_list_to_effect_dispatch ),*
+                }
+            }
+        }
+
         #[cfg(feature = "effect-impls")]
         impl From<EffectNameList> for EffectDispatchList {
             fn from(value: EffectNameList) -> Self {
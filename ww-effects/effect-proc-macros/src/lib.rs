@@ -8,7 +8,6 @@
 use proc_macro::TokenStream;
 
 mod derive;
-mod end_loop;
 mod generate_lists_and_impls;
 
 /// Derive the [`BaseEffect`](../ww_effects/traits/trait.BaseEffect.html) trait for the given type.
@@ -18,7 +17,11 @@ mod generate_lists_and_impls;
 /// and it loads that config from the file and uses `<Self as Default>` for all the other fields.
 ///
 /// The type must also implement [`Effect`](../ww_effects/traits/trait.Effect.html).
-#[proc_macro_derive(BaseEffect)]
+///
+/// The type can have an `#[effect(...)]` attribute to declare its
+/// [metadata](../ww_effects/metadata/index.html), which is read by the build script of
+/// `ww_effects` rather than this macro.
+#[proc_macro_derive(BaseEffect, attributes(effect))]
 pub fn derive_base_effect(input: TokenStream) -> TokenStream {
     derive::derive_base_effect(input)
 }
@@ -27,20 +30,25 @@ pub fn derive_base_effect(input: TokenStream) -> TokenStream {
 /// given type.
 ///
 /// The type must also implement [`EffectConfig`](../ww_effects/traits/trait.EffectConfig.html).
-#[proc_macro_derive(BaseEffectConfig)]
+///
+/// The derived [`schema`](../ww_effects/traits/trait.BaseEffectConfig.html#tymethod.schema) method
+/// is built from the doc comments and types of the fields. Fields can also give hints to the schema
+/// with a `#[schema(...)]` attribute:
+/// - `#[schema(range = 0.0..=1.0)]` gives the range of valid values for a numeric field
+/// - `#[schema(unclamped)]` makes the range only a suggestion, so values outside it are still valid
+/// - `#[schema(colour)]` marks a `[u8; 3]` field as a colour, which is automatic for `RGBArray`
+#[proc_macro_derive(BaseEffectConfig, attributes(schema))]
 pub fn derive_base_effect_config(input: TokenStream) -> TokenStream {
     derive::derive_base_effect_config(input)
 }
 
-/// Given a list of effect names separated by commas, generate the `EffectNameList` and friends for
-/// those effects, as well as all their `impl` blocks.
+/// Given a list of effects separated by commas, generate the `EffectNameList` and friends for
+/// those effects, as well as all their `impl` blocks. Each effect is given along with its category,
+/// like `maths::Helix`, and the categories become the `EffectCategory` enum.
+///
+/// This is called by the code that the build script of `ww_effects` generates, so effects don't
+/// need to be added to it by hand.
 #[proc_macro]
 pub fn generate_lists_and_impls(input: TokenStream) -> TokenStream {
     generate_lists_and_impls::generate_lists_and_impls(input)
 }
-
-/// End a loop after 100 iterations in test or benchmark builds.
-#[proc_macro_attribute]
-pub fn end_loop_in_test_or_bench(_args: TokenStream, input: TokenStream) -> TokenStream {
-    end_loop::end_loop_in_test_or_bench(input.into()).into()
-}
@@ -0,0 +1,275 @@
+//! This module handles the version header of config files and keeping backups of config files
+//! which can't be read or which are about to be saved over with changes.
+//!
+//! Effect config files start with a `// version: N` comment line, where `N` is the
+//! [`VERSION`](crate::traits::EffectConfig::VERSION) of the config when it was saved. RON ignores
+//! comments, so the rest of the file is just the config. Files without the header are from before
+//! configs were versioned and count as version 0.
+
+use std::{
+    fs,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// The start of the first line of a versioned config file.
+const VERSION_PREFIX: &str = "// version: ";
+
+/// Get the version of the config in the given file contents. See the [module docs](self).
+pub fn read_version(text: &str) -> u32 {
+    text.lines()
+        .next()
+        .and_then(|line| line.strip_prefix(VERSION_PREFIX))
+        .and_then(|version| version.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+/// Put the version header at the start of the serialized config. See the [module docs](self).
+pub fn add_version_header(version: u32, text: &str) -> String {
+    format!("{VERSION_PREFIX}{version}\n{text}")
+}
+
+/// Move the file with the given name out of the way so that it doesn't get overwritten, and
+/// return the name of the backup. The backup is named after the original file and the current
+/// time, like `helix.ron.1701455400.bak`, so repeated failures don't overwrite older backups.
+///
+/// Return `None` if the file couldn't be moved, in which case it may be overwritten.
+pub fn back_up_unreadable_file(filename: &str) -> Option<String> {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let backup = format!("{filename}.{seconds}.bak");
+
+    fs::rename(filename, &backup).ok().map(|()| backup)
+}
+
+#[cfg(test)]
+#[cfg(feature = "config-impls")]
+mod tests {
+    use super::*;
+    use crate::{effects::configs::HelixConfig, traits::EffectConfig};
+    use effect_proc_macros::BaseEffectConfig;
+    use serde::{Deserialize, Serialize};
+    use std::path::{Path, PathBuf};
+    use tracing_unwrap::{OptionExt, ResultExt};
+
+    /// Get a fresh path for a config file in a temporary directory.
+    fn temp_config_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ww-config-file-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).expect_or_log("Should be able to create a temporary directory");
+
+        let path = dir.join(name);
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    /// Find all the backups of the given config file.
+    fn backups_of(path: &Path) -> Vec<PathBuf> {
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .expect_or_log("The path should have a name");
+
+        fs::read_dir(path.parent().expect_or_log("The path should have a parent"))
+            .expect_or_log("Should be able to read the temporary directory")
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .filter(|backup| {
+                backup
+                    .file_name()
+                    .and_then(|backup| backup.to_str())
+                    .is_some_and(|backup| {
+                        backup.starts_with(&format!("{name}.")) && backup.ends_with(".bak")
+                    })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn read_version_test() {
+        assert_eq!(read_version("// version: 3\nHelixConfig()"), 3);
+        assert_eq!(read_version(&add_version_header(12, "HelixConfig()")), 12);
+        assert_eq!(
+            read_version("HelixConfig()"),
+            0,
+            "no header means version 0"
+        );
+        assert_eq!(read_version("// something else\nHelixConfig()"), 0);
+    }
+
+    #[test]
+    fn missing_fields_test() {
+        let path = temp_config_path("missing_fields.ron");
+        let filename = path.to_str().expect_or_log("Paths should be valid Unicode");
+
+        // A file from before `band_width` and `clockwise` were added
+        fs::write(filename, "HelixConfig(pitch: 3.5, strands: 4)")
+            .expect_or_log("Should be able to write the config file");
+
+        let config = HelixConfig::from_file(filename);
+        assert_eq!(
+            config,
+            HelixConfig {
+                pitch: 3.5,
+                strands: 4,
+                ..Default::default()
+            }
+        );
+        assert!(
+            backups_of(&path).is_empty(),
+            "readable files aren't backed up"
+        );
+    }
+
+    #[test]
+    fn unreadable_file_test() {
+        let path = temp_config_path("unreadable.ron");
+        let filename = path.to_str().expect_or_log("Paths should be valid Unicode");
+
+        fs::write(filename, "HelixConfig(pitch: \"oops\")")
+            .expect_or_log("Should be able to write the config file");
+
+        assert_eq!(HelixConfig::from_file(filename), HelixConfig::default());
+
+        let backups = backups_of(&path);
+        assert_eq!(backups.len(), 1, "the unreadable file should be backed up");
+        assert_eq!(
+            fs::read_to_string(&backups[0]).ok().as_deref(),
+            Some("HelixConfig(pitch: \"oops\")"),
+            "the backup should be the original file"
+        );
+        assert_eq!(
+            read_version(&fs::read_to_string(filename).unwrap_or_default()),
+            HelixConfig::VERSION,
+            "the default should be saved with a version header"
+        );
+    }
+
+    #[test]
+    fn out_of_range_test() {
+        let path = temp_config_path("out_of_range.ron");
+        let filename = path.to_str().expect_or_log("Paths should be valid Unicode");
+
+        fs::write(filename, "HelixConfig(pitch: 50, strands: 0)")
+            .expect_or_log("Should be able to write the config file");
+
+        let config = HelixConfig::from_file(filename);
+        assert_eq!(config.pitch, 10.);
+        assert_eq!(config.strands, 1);
+        assert!(config.validate().is_ok(), "the config should be clamped");
+
+        let backups = backups_of(&path);
+        assert_eq!(backups.len(), 1, "the original file should be backed up");
+        assert_eq!(
+            fs::read_to_string(&backups[0]).ok().as_deref(),
+            Some("HelixConfig(pitch: 50, strands: 0)")
+        );
+        assert_eq!(
+            HelixConfig::from_file(filename),
+            config,
+            "the clamped config should be saved"
+        );
+    }
+
+    #[test]
+    fn newer_version_test() {
+        let path = temp_config_path("newer_version.ron");
+        let filename = path.to_str().expect_or_log("Paths should be valid Unicode");
+
+        // A file from a newer version with a field that we don't know about
+        let text = add_version_header(
+            HelixConfig::VERSION + 1,
+            "HelixConfig(pitch: 3.5, sparkle: true)",
+        );
+        fs::write(filename, &text).expect_or_log("Should be able to write the config file");
+
+        assert_eq!(
+            HelixConfig::from_file(filename),
+            HelixConfig {
+                pitch: 3.5,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            fs::read_to_string(filename).ok(),
+            Some(text),
+            "files from newer versions shouldn't be saved over"
+        );
+        assert!(backups_of(&path).is_empty());
+    }
+
+    /// A config with a field which was renamed in version 1, to test migrations.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, BaseEffectConfig)]
+    #[serde(default)]
+    struct MigratedConfig {
+        /// The speed, which used to be called `velocity`.
+        speed: f32,
+
+        /// Whether it's on.
+        on: bool,
+    }
+
+    impl Default for MigratedConfig {
+        fn default() -> Self {
+            Self {
+                speed: 1.,
+                on: true,
+            }
+        }
+    }
+
+    impl EffectConfig for MigratedConfig {
+        const VERSION: u32 = 1;
+
+        fn migrate(text: &str, from_version: u32) -> Result<Self, ron::error::SpannedError> {
+            /// Version 0 of [`MigratedConfig`].
+            #[derive(Deserialize)]
+            #[serde(rename = "MigratedConfig")]
+            struct MigratedConfigV0 {
+                /// The speed.
+                velocity: f32,
+            }
+
+            match from_version {
+                0 => ron::from_str(text).map(|old: MigratedConfigV0| Self {
+                    speed: old.velocity,
+                    ..Default::default()
+                }),
+                _ => ron::from_str(text),
+            }
+        }
+    }
+
+    #[test]
+    fn migration_test() {
+        let path = temp_config_path("migrated.ron");
+        let filename = path.to_str().expect_or_log("Paths should be valid Unicode");
+
+        fs::write(filename, "MigratedConfig(velocity: 2.5)")
+            .expect_or_log("Should be able to write the config file");
+
+        let config = MigratedConfig::from_file(filename);
+        assert_eq!(
+            config,
+            MigratedConfig {
+                speed: 2.5,
+                on: true
+            }
+        );
+
+        let text = fs::read_to_string(filename).expect_or_log("The config should be saved");
+        assert_eq!(
+            read_version(&text),
+            1,
+            "the migrated config should be saved"
+        );
+        assert_eq!(
+            MigratedConfig::from_file(filename),
+            config,
+            "the migrated config should load without migrating again"
+        );
+        assert!(
+            backups_of(&path).is_empty(),
+            "migrated files aren't backed up"
+        );
+        assert!(config.on, "new fields should take their default value");
+    }
+}
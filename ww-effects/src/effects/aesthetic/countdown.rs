@@ -0,0 +1,573 @@
+//! This module contains the [`Countdown`] effect.
+
+#[cfg(feature = "config-impls")]
+pub use config::{CountdownConfig, CountdownDisplay, CountdownMode};
+
+#[cfg(feature = "effect-impls")]
+pub use effect::Countdown;
+
+use crate::effects::prelude::*;
+
+/// Contains the config for the [`Countdown`] effect.
+#[cfg(feature = "config-impls")]
+mod config {
+    use super::*;
+
+    /// What time the effect shows.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+    pub enum CountdownMode {
+        /// Show the current time of day.
+        Clock,
+
+        /// Show the time left until the target date.
+        Countdown,
+    }
+
+    /// How the effect shows the time.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+    pub enum CountdownDisplay {
+        /// Write the time in digits which wrap around the tree.
+        Digits,
+
+        /// Fill the tree from the bottom up as time passes. In clock mode, the tree fills up over
+        /// the course of each day.
+        FillLevel,
+    }
+
+    /// The config for the [`Countdown`] effect.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, BaseEffectConfig)]
+    #[serde(default)]
+    pub struct CountdownConfig {
+        /// What time to show.
+        pub mode: CountdownMode,
+
+        /// How to show the time.
+        pub display: CountdownDisplay,
+
+        /// The month of the target date, from 1 to 12. The countdown goes to the next time this
+        /// date comes around, so it works every year.
+        #[schema(range = 1..=12)]
+        pub target_month: u32,
+
+        /// The day of the month of the target date.
+        #[schema(range = 1..=31)]
+        pub target_day: u32,
+
+        /// The hour of the target date, from 0 to 23.
+        #[schema(range = 0..=23)]
+        pub target_hour: u32,
+
+        /// How many days before the target date the fill level starts rising.
+        #[schema(range = 1..=365)]
+        pub fill_days: u32,
+
+        /// How long to celebrate for when the countdown reaches zero.
+        #[schema(range = 0..=600)]
+        pub celebration_seconds: u32,
+
+        /// How tall the digits are, in GIFT coordinate units.
+        #[schema(range = 0.5..=3.0)]
+        pub text_height: f32,
+
+        /// How many times the digits go round the tree every minute.
+        #[schema(range = 0.0..=20.0)]
+        pub rotations_per_minute: f32,
+
+        /// The colour of the digits and the fill level.
+        pub colour: RGBArray,
+
+        /// The colour of everything else.
+        pub background_colour: RGBArray,
+    }
+
+    impl Default for CountdownConfig {
+        fn default() -> Self {
+            Self {
+                mode: CountdownMode::Countdown,
+                display: CountdownDisplay::Digits,
+                target_month: 12,
+                target_day: 25,
+                target_hour: 0,
+                fill_days: 24,
+                celebration_seconds: 60,
+                text_height: 1.5,
+                rotations_per_minute: 3.,
+                colour: [255, 40, 20],
+                background_colour: [0, 12, 0],
+            }
+        }
+    }
+
+    impl CountdownConfig {
+        /// The last day of the target month. February has 29 days, since the countdown can wait
+        /// for a leap year.
+        pub(super) fn days_in_target_month(&self) -> u32 {
+            match self.target_month {
+                2 => 29,
+                4 | 6 | 9 | 11 => 30,
+                _ => 31,
+            }
+        }
+    }
+
+    impl EffectConfig for CountdownConfig {
+        /// Check the schema ranges, and also check that the target day exists in the target month,
+        /// since a date like the 31st of February would never come around.
+        fn validate(&self) -> Result<(), Vec<crate::schema::RangeError>> {
+            let mut errors = crate::schema::validate_schema_ranges(self)
+                .err()
+                .unwrap_or_default();
+
+            let last_day = self.days_in_target_month();
+            if (1..=12).contains(&self.target_month) && self.target_day > last_day {
+                errors.push(crate::schema::RangeError {
+                    field: "target_day".to_string(),
+                    value: self.target_day.into(),
+                    min: 1.,
+                    max: last_day.into(),
+                });
+            }
+
+            if errors.is_empty() {
+                Ok(())
+            } else {
+                Err(errors)
+            }
+        }
+
+        /// Clamp the schema ranges, and then clamp the target day to the end of the target month.
+        fn clamp_to_ranges(&mut self) {
+            crate::schema::clamp_to_schema_ranges(self);
+            self.target_day = self.target_day.min(self.days_in_target_month());
+        }
+
+        fn factory_presets() -> Vec<(&'static str, Self)> {
+            vec![
+                (
+                    "New Year",
+                    Self {
+                        target_month: 1,
+                        target_day: 1,
+                        colour: [255, 200, 40],
+                        background_colour: [0, 0, 12],
+                        ..Self::default()
+                    },
+                ),
+                (
+                    "Advent calendar",
+                    Self {
+                        display: CountdownDisplay::FillLevel,
+                        ..Self::default()
+                    },
+                ),
+                (
+                    "Clock",
+                    Self {
+                        mode: CountdownMode::Clock,
+                        colour: [255, 255, 255],
+                        ..Self::default()
+                    },
+                ),
+            ]
+        }
+
+        fn render_options_gui(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui) -> bool {
+            let schema = Self::schema();
+            let mut config_changed = false;
+
+            ui.horizontal(|ui| {
+                ui.label("Mode:");
+                config_changed |= ui
+                    .selectable_value(&mut self.mode, CountdownMode::Clock, "Clock")
+                    .changed();
+                config_changed |= ui
+                    .selectable_value(&mut self.mode, CountdownMode::Countdown, "Countdown")
+                    .changed();
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Display:");
+                config_changed |= ui
+                    .selectable_value(&mut self.display, CountdownDisplay::Digits, "Digits")
+                    .changed();
+                config_changed |= ui
+                    .selectable_value(&mut self.display, CountdownDisplay::FillLevel, "Fill level")
+                    .changed();
+            });
+
+            if self.mode == CountdownMode::Countdown {
+                ui.add_space(UI_SPACING);
+
+                config_changed |= ui
+                    .add(
+                        schema
+                            .slider("target_month", &mut self.target_month)
+                            .text("Target month"),
+                    )
+                    .changed();
+
+                config_changed |= ui
+                    .add(
+                        schema
+                            .slider("target_day", &mut self.target_day)
+                            .text("Target day"),
+                    )
+                    .changed();
+
+                config_changed |= ui
+                    .add(
+                        schema
+                            .slider("target_hour", &mut self.target_hour)
+                            .text("Target hour"),
+                    )
+                    .changed();
+
+                if self.display == CountdownDisplay::FillLevel {
+                    config_changed |= ui
+                        .add(
+                            schema
+                                .slider("fill_days", &mut self.fill_days)
+                                .suffix(" days")
+                                .text("Fill time"),
+                        )
+                        .changed();
+                }
+
+                config_changed |= ui
+                    .add(
+                        schema
+                            .slider("celebration_seconds", &mut self.celebration_seconds)
+                            .suffix("s")
+                            .text("Celebration time"),
+                    )
+                    .changed();
+            }
+
+            if self.display == CountdownDisplay::Digits {
+                ui.add_space(UI_SPACING);
+
+                config_changed |= ui
+                    .add(
+                        schema
+                            .slider("text_height", &mut self.text_height)
+                            .text("Text height"),
+                    )
+                    .changed();
+
+                config_changed |= ui
+                    .add(
+                        schema
+                            .slider("rotations_per_minute", &mut self.rotations_per_minute)
+                            .suffix("/min")
+                            .text("Rotation speed"),
+                    )
+                    .changed();
+            }
+
+            ui.add_space(UI_SPACING);
+
+            config_changed |= colour_picker(ui, &mut self.colour, "Colour").changed();
+            config_changed |=
+                colour_picker(ui, &mut self.background_colour, "Background colour").changed();
+
+            config_changed
+        }
+    }
+}
+
+/// Contains the [`Countdown`] effect itself.
+#[cfg(feature = "effect-impls")]
+mod effect {
+    use super::*;
+    use crate::effects::utils::clock::Clock;
+    use chrono::{Datelike, NaiveDate, NaiveDateTime, Timelike};
+    use std::f32::consts::TAU;
+    use ww_gift_coords::{COORDS, PointF};
+
+    /// The height of the soft edge at the top of the fill level, in GIFT coordinate units.
+    const FILL_EDGE: f32 = 0.15;
+
+    /// The height of the middle of the digits, as a fraction of the height of the tree. This is
+    /// below halfway because the tree is wider and has more lights at the bottom.
+    const TEXT_CENTER: f32 = 0.35;
+
+    /// The number of rows in each glyph.
+    const GLYPH_ROWS: usize = 5;
+
+    /// The number of columns in each glyph, plus one column of space after it.
+    const GLYPH_COLUMNS: usize = 4;
+
+    /// Get the pixels of a character in a tiny 3x5 font, with one row per element and the
+    /// leftmost column in the highest of the three bits. Unknown characters are blank.
+    fn glyph(c: char) -> [u8; GLYPH_ROWS] {
+        match c {
+            '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+            '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+            '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+            '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+            '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+            '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+            '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+            '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+            '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+            '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+            ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+            'd' => [0b001, 0b001, 0b111, 0b101, 0b111],
+            'h' => [0b100, 0b100, 0b111, 0b101, 0b101],
+            _ => [0; GLYPH_ROWS],
+        }
+    }
+
+    /// Show the current time or a countdown to a date, either in digits or as a rising fill
+    /// level, and celebrate when the countdown reaches zero.
+    #[derive(Clone, Debug, PartialEq, BaseEffect)]
+    #[effect(tags = ["clock", "time", "celebration"], unattended)]
+    pub struct Countdown {
+        /// Where we get the time from.
+        clock: Clock,
+
+        /// The cylindrical coordinates of the lights.
+        cylindrical_coords: Vec<PointF>,
+
+        /// How far the digits have rotated around the tree, in turns.
+        rotation: f32,
+    }
+
+    impl Countdown {
+        /// Create the effect with a specific clock, so that tests can choose what time it is.
+        #[cfg(test)]
+        pub(super) fn with_clock(clock: Clock) -> Self {
+            Self {
+                clock,
+                cylindrical_coords: COORDS.cylindrical_coords(),
+                rotation: 0.,
+            }
+        }
+
+        /// Find the next time that the target date comes around, including the one that we're
+        /// currently celebrating. Return [`None`] if the target date doesn't exist in the next
+        /// few years, like the 31st of February.
+        fn next_target(now: NaiveDateTime, config: &CountdownConfig) -> Option<NaiveDateTime> {
+            let celebration = chrono::Duration::seconds(config.celebration_seconds as i64);
+
+            // Leap days only come around every four years, and sometimes eight
+            (now.year()..=now.year() + 8).find_map(|year| {
+                NaiveDate::from_ymd_opt(year, config.target_month, config.target_day)
+                    .and_then(|date| date.and_hms_opt(config.target_hour, 0, 0))
+                    .filter(|&target| now < target + celebration)
+            })
+        }
+
+        /// Get the text to show for the given number of seconds. Long durations only show days
+        /// and hours so that the text doesn't get too small.
+        pub(super) fn countdown_text(seconds: i64) -> String {
+            let (days, hours) = (seconds / 86_400, seconds / 3600 % 24);
+            let (minutes, seconds) = (seconds / 60 % 60, seconds % 60);
+
+            if days > 0 {
+                format!("{days}d{hours:02}h")
+            } else {
+                format!("{hours:02}:{minutes:02}:{seconds:02}")
+            }
+        }
+
+        /// Write the text around the tree.
+        fn render_text(&self, text: &str, config: &CountdownConfig) -> Vec<RGBArray> {
+            let glyphs: Vec<_> = text.chars().map(glyph).collect();
+            let columns = glyphs.len() * GLYPH_COLUMNS;
+            let row_height = config.text_height / GLYPH_ROWS as f32;
+            let top = TEXT_CENTER.mul_add(COORDS.max_z(), config.text_height / 2.);
+
+            self.cylindrical_coords
+                .iter()
+                .map(|&(_, angle, z)| {
+                    let row = ((top - z) / row_height).floor();
+                    let turns = (angle / TAU - self.rotation).rem_euclid(1.);
+                    let column = (turns * columns as f32) as usize;
+
+                    if !(0. ..GLYPH_ROWS as f32).contains(&row) {
+                        return config.background_colour;
+                    }
+
+                    let (idx, glyph_column) = (column / GLYPH_COLUMNS, column % GLYPH_COLUMNS);
+                    let lit = glyph_column < 3
+                        && glyphs
+                            .get(idx)
+                            .is_some_and(|rows| rows[row as usize] & (0b100 >> glyph_column) != 0);
+
+                    if lit {
+                        config.colour
+                    } else {
+                        config.background_colour
+                    }
+                })
+                .collect()
+        }
+
+        /// Fill the tree up to the given fraction of its height.
+        fn render_fill(&self, fraction: f32, config: &CountdownConfig) -> Vec<RGBArray> {
+            let level = fraction.clamp(0., 1.) * (COORDS.max_z() + FILL_EDGE);
+
+            self.cylindrical_coords
+                .iter()
+                .map(|&(_, _, z)| {
+                    lerp_colour(
+                        config.colour,
+                        config.background_colour,
+                        (z - level) / FILL_EDGE + 1.,
+                    )
+                })
+                .collect()
+        }
+
+        /// Swirl rainbows around the tree to celebrate.
+        fn render_celebration(&self, seconds: f32) -> Vec<RGBArray> {
+            self.cylindrical_coords
+                .iter()
+                .map(|&(_, angle, z)| {
+                    hue_colour(seconds.mul_add(-0.5, angle / TAU + z / COORDS.max_z()))
+                })
+                .collect()
+        }
+    }
+
+    impl Effect for Countdown {
+        fn from_config(_config: CountdownConfig) -> Self {
+            Self {
+                clock: Clock::default(),
+                cylindrical_coords: COORDS.cylindrical_coords(),
+                rotation: 0.,
+            }
+        }
+
+        fn next_frame(
+            &mut self,
+            config: &CountdownConfig,
+            ctx: &FrameContext,
+        ) -> Option<(FrameType, Duration)> {
+            // A simulated clock only moves when told to, so move it by the real time since the
+            // last frame. The system clock ignores this
+            self.clock.advance(ctx.delta);
+            let now = self.clock.now();
+
+            let frame_data = match config.mode {
+                CountdownMode::Clock => {
+                    match config.display {
+                        CountdownDisplay::Digits => self
+                            .render_text(&format!("{:02}:{:02}", now.hour(), now.minute()), config),
+                        CountdownDisplay::FillLevel => self
+                            .render_fill(now.num_seconds_from_midnight() as f32 / 86_400., config),
+                    }
+                }
+                CountdownMode::Countdown => match Self::next_target(now, config) {
+                    Some(target) if now >= target => {
+                        let celebrating_for = (now - target).num_milliseconds() as f32 / 1000.;
+                        self.render_celebration(celebrating_for)
+                    }
+                    Some(target) => {
+                        let seconds_left = (target - now).num_seconds();
+                        match config.display {
+                            CountdownDisplay::Digits => {
+                                self.render_text(&Self::countdown_text(seconds_left), config)
+                            }
+                            CountdownDisplay::FillLevel => {
+                                let fill_seconds = config.fill_days.max(1) as f32 * 86_400.;
+                                self.render_fill(1. - seconds_left as f32 / fill_seconds, config)
+                            }
+                        }
+                    }
+                    None => vec![config.background_colour; COORDS.lights_num()],
+                },
+            };
+
+            self.rotation = (config.rotations_per_minute / 60.)
+                .mul_add(ctx.delta_secs(), self.rotation)
+                .rem_euclid(1.);
+
+            Some((FrameType::RawData(frame_data), ctx.target_frame_time()))
+        }
+
+        #[cfg(any(test, feature = "bench"))]
+        fn loops_to_test() -> Option<NonZeroU16> {
+            NonZeroU16::new(100)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SimulatedClock, effects::utils::clock::Clock, snapshot_effect};
+    use chrono::NaiveDate;
+    use tracing_unwrap::OptionExt;
+
+    #[test]
+    fn countdown_test() {
+        snapshot_effect!(Countdown);
+    }
+
+    #[test]
+    fn countdown_text_test() {
+        assert_eq!(Countdown::countdown_text(0), "00:00:00");
+        assert_eq!(
+            Countdown::countdown_text(3 * 3600 + 25 * 60 + 7),
+            "03:25:07"
+        );
+        assert_eq!(Countdown::countdown_text(86_399), "23:59:59");
+        assert_eq!(Countdown::countdown_text(86_400), "1d00h");
+        assert_eq!(
+            Countdown::countdown_text(23 * 86_400 + 5 * 3600 + 59),
+            "23d05h"
+        );
+    }
+
+    #[test]
+    fn countdown_impossible_date_test() {
+        let mut config = CountdownConfig {
+            target_month: 2,
+            target_day: 31,
+            ..CountdownConfig::default()
+        };
+
+        let errors = config
+            .validate()
+            .expect_err("the 31st of February should be invalid");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "target_day");
+
+        config.clamp_to_ranges();
+        assert_eq!(
+            config.target_day, 29,
+            "the leap day should still be allowed"
+        );
+        assert!(config.validate().is_ok());
+
+        let mut config = CountdownConfig {
+            target_month: 4,
+            target_day: 31,
+            ..CountdownConfig::default()
+        };
+        config.clamp_to_ranges();
+        assert_eq!(config.target_day, 30);
+    }
+
+    #[test]
+    fn countdown_celebration_test() {
+        let config = CountdownConfig::default();
+        let start = NaiveDate::from_ymd_opt(2023, 12, 24)
+            .and_then(|date| date.and_hms_opt(23, 59, 58))
+            .expect_or_log("Christmas Eve should be a valid date");
+        let mut effect = Countdown::with_clock(Clock::Simulated(start));
+        let mut clock = SimulatedClock::default();
+
+        let frames: Vec<_> = (0..200)
+            .filter_map(|_| {
+                let (frame, duration) = effect.next_frame(&config, &clock.tick())?;
+                clock.advance(duration);
+                Some(frame)
+            })
+            .collect();
+
+        insta::assert_ron_snapshot!(frames);
+    }
+}
@@ -0,0 +1,318 @@
+//! This module contains the [`DigitalRain`] effect.
+
+#[cfg(feature = "config-impls")]
+pub use config::DigitalRainConfig;
+
+#[cfg(feature = "effect-impls")]
+pub use effect::DigitalRain;
+
+use crate::effects::prelude::*;
+
+/// Contains the config for the [`DigitalRain`] effect.
+#[cfg(feature = "config-impls")]
+mod config {
+    use super::*;
+
+    /// The config for the [`DigitalRain`] effect.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, BaseEffectConfig)]
+    #[serde(default)]
+    pub struct DigitalRainConfig {
+        /// The number of columns around the tree that the streams fall down.
+        #[schema(range = 1..=64)]
+        pub columns: u8,
+
+        /// The average number of new streams every second.
+        #[schema(range = 0.0..=30.0)]
+        pub streams_per_second: f32,
+
+        /// The speed of the slowest streams, in GIFT coordinate units per second.
+        #[schema(range = 0.1..=5.0)]
+        pub min_speed: f32,
+
+        /// The speed of the fastest streams, in GIFT coordinate units per second.
+        #[schema(range = 0.1..=5.0)]
+        pub max_speed: f32,
+
+        /// The length of the shortest tails, in GIFT coordinate units.
+        #[schema(range = 0.1..=4.0)]
+        pub min_length: f32,
+
+        /// The length of the longest tails, in GIFT coordinate units.
+        #[schema(range = 0.1..=4.0)]
+        pub max_length: f32,
+
+        /// The colour of the head of each stream.
+        pub head_colour: RGBArray,
+
+        /// The colour of the tail of each stream just behind the head. It fades into the
+        /// background further up the tail.
+        pub tail_colour: RGBArray,
+
+        /// The colour of the lights which aren't part of any stream.
+        pub background_colour: RGBArray,
+    }
+
+    impl Default for DigitalRainConfig {
+        fn default() -> Self {
+            Self {
+                columns: 24,
+                streams_per_second: 6.,
+                min_speed: 0.8,
+                max_speed: 2.,
+                min_length: 0.6,
+                max_length: 1.8,
+                head_colour: [210, 255, 210],
+                tail_colour: [0, 255, 40],
+                background_colour: [0, 0, 0],
+            }
+        }
+    }
+
+    impl EffectConfig for DigitalRainConfig {
+        fn factory_presets() -> Vec<(&'static str, Self)> {
+            vec![(
+                "Red alert",
+                Self {
+                    streams_per_second: 12.,
+                    head_colour: [255, 220, 210],
+                    tail_colour: [255, 20, 0],
+                    ..Self::default()
+                },
+            )]
+        }
+
+        fn render_options_gui(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui) -> bool {
+            let schema = Self::schema();
+            let mut config_changed = false;
+
+            config_changed |= ui
+                .add(schema.slider("columns", &mut self.columns).text("Columns"))
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("streams_per_second", &mut self.streams_per_second)
+                        .suffix("/s")
+                        .text("Density"),
+                )
+                .changed();
+
+            ui.add_space(UI_SPACING);
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("min_speed", &mut self.min_speed)
+                        .suffix("units/s")
+                        .text("Min speed"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("max_speed", &mut self.max_speed)
+                        .suffix("units/s")
+                        .text("Max speed"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("min_length", &mut self.min_length)
+                        .text("Min length"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("max_length", &mut self.max_length)
+                        .text("Max length"),
+                )
+                .changed();
+
+            ui.add_space(UI_SPACING);
+
+            config_changed |= colour_picker(ui, &mut self.head_colour, "Head colour").changed();
+            config_changed |= colour_picker(ui, &mut self.tail_colour, "Tail colour").changed();
+            config_changed |=
+                colour_picker(ui, &mut self.background_colour, "Background colour").changed();
+
+            config_changed
+        }
+    }
+}
+
+/// Contains the [`DigitalRain`] effect itself.
+#[cfg(feature = "effect-impls")]
+mod effect {
+    use super::*;
+    use std::f32::consts::TAU;
+    use ww_gift_coords::{COORDS, PointF};
+
+    /// The length of the bright head of each stream, in GIFT coordinate units.
+    const HEAD_LENGTH: f32 = 0.15;
+
+    /// A single stream of light falling down one column.
+    #[derive(Clone, Debug, PartialEq)]
+    struct Stream {
+        /// The index of the column that the stream is falling down.
+        column: usize,
+
+        /// The height of the bottom of the head.
+        head_z: f32,
+
+        /// How fast the stream falls, in GIFT coordinate units per second.
+        speed: f32,
+
+        /// The length of the tail.
+        length: f32,
+    }
+
+    /// Make streams of green light rain down columns around the tree, like the digital rain in
+    /// The Matrix.
+    #[derive(Clone, Debug, PartialEq, BaseEffect)]
+    #[effect(tags = ["matrix", "rain", "green"], unattended)]
+    pub struct DigitalRain {
+        /// The RNG used to choose the columns, speeds, and lengths of new streams.
+        rng: StdRng,
+
+        /// The cylindrical coordinates of the lights.
+        cylindrical_coords: Vec<PointF>,
+
+        /// The streams which are currently falling.
+        streams: Vec<Stream>,
+    }
+
+    impl DigitalRain {
+        /// Work out which column a light is in from its angle around the tree.
+        fn column(angle: f32, columns: usize) -> usize {
+            ((angle / TAU * columns as f32) as usize).min(columns - 1)
+        }
+
+        /// Create a new stream at the top of the tree in a random column.
+        fn new_stream(&mut self, config: &DigitalRainConfig) -> Stream {
+            let (min_speed, max_speed) = (
+                config.min_speed.min(config.max_speed),
+                config.min_speed.max(config.max_speed),
+            );
+            let (min_length, max_length) = (
+                config.min_length.min(config.max_length),
+                config.min_length.max(config.max_length),
+            );
+
+            Stream {
+                column: self.rng.random_range(0..config.columns.max(1) as usize),
+                head_z: COORDS.max_z() + HEAD_LENGTH,
+                speed: self.rng.random_range(min_speed..=max_speed),
+                length: self.rng.random_range(min_length..=max_length),
+            }
+        }
+
+        /// Work out the colour of a light at the given height in a stream, or [`None`] if the
+        /// light isn't in the stream.
+        fn stream_colour(stream: &Stream, z: f32, config: &DigitalRainConfig) -> Option<RGBArray> {
+            let distance_behind_head = z - stream.head_z;
+
+            if (0. ..HEAD_LENGTH).contains(&distance_behind_head) {
+                Some(config.head_colour)
+            } else if (HEAD_LENGTH..HEAD_LENGTH + stream.length).contains(&distance_behind_head) {
+                Some(lerp_colour(
+                    config.tail_colour,
+                    config.background_colour,
+                    (distance_behind_head - HEAD_LENGTH) / stream.length,
+                ))
+            } else {
+                None
+            }
+        }
+    }
+
+    impl Effect for DigitalRain {
+        fn from_config(config: DigitalRainConfig) -> Self {
+            let mut rain = Self {
+                rng: rng!(),
+                cylindrical_coords: COORDS.cylindrical_coords(),
+                streams: vec![],
+            };
+
+            // Start with roughly as many streams as there would be if it had been raining for a
+            // while, spread out over the height of the tree
+            let average_speed = f32::midpoint(config.min_speed, config.max_speed).max(0.1);
+            let initial_streams =
+                config.streams_per_second.max(0.) * COORDS.max_z() / average_speed;
+            for _ in 0..initial_streams as usize {
+                let mut stream = rain.new_stream(&config);
+                stream.head_z = rain.rng.random_range(0.0..=COORDS.max_z());
+                rain.streams.push(stream);
+            }
+
+            rain
+        }
+
+        fn next_frame(
+            &mut self,
+            config: &DigitalRainConfig,
+            ctx: &FrameContext,
+        ) -> Option<(FrameType, Duration)> {
+            let delta = ctx.delta_secs();
+            let columns = config.columns.max(1) as usize;
+
+            // Streams in columns that no longer exist can't be seen
+            self.streams.retain(|stream| stream.column < columns);
+
+            // Start a geometrically distributed number of streams, which averages out to the
+            // right number each second
+            let expected = config.streams_per_second.max(0.) * delta;
+            while self.rng.random_bool((expected / (1. + expected)) as f64) {
+                let stream = self.new_stream(config);
+                self.streams.push(stream);
+            }
+
+            let frame_data = self
+                .cylindrical_coords
+                .iter()
+                .map(|&(_, angle, z)| {
+                    let column = Self::column(angle, columns);
+
+                    // Where streams overlap, the one with the head closest to this light wins
+                    self.streams
+                        .iter()
+                        .filter(|stream| stream.column == column)
+                        .filter_map(|stream| {
+                            Self::stream_colour(stream, z, config).map(|colour| (stream, colour))
+                        })
+                        .max_by(|(a, _), (b, _)| a.head_z.total_cmp(&b.head_z))
+                        .map_or(config.background_colour, |(_, colour)| colour)
+                })
+                .collect();
+
+            for stream in &mut self.streams {
+                stream.head_z = stream.speed.mul_add(-delta, stream.head_z);
+            }
+            self.streams
+                .retain(|stream| stream.head_z + HEAD_LENGTH + stream.length > 0.);
+
+            Some((FrameType::RawData(frame_data), ctx.target_frame_time()))
+        }
+
+        #[cfg(any(test, feature = "bench"))]
+        fn loops_to_test() -> Option<NonZeroU16> {
+            NonZeroU16::new(100)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot_effect;
+
+    #[test]
+    fn digital_rain_test() {
+        snapshot_effect!(DigitalRain);
+    }
+}
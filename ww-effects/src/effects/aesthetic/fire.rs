@@ -0,0 +1,297 @@
+//! This module contains the [`Fire`] effect.
+
+#[cfg(feature = "config-impls")]
+pub use config::FireConfig;
+
+#[cfg(feature = "effect-impls")]
+pub use effect::Fire;
+
+use crate::effects::prelude::*;
+
+/// Contains the config for the [`Fire`] effect.
+#[cfg(feature = "config-impls")]
+mod config {
+    use super::*;
+
+    /// The config for the [`Fire`] effect.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, BaseEffectConfig)]
+    #[serde(default)]
+    pub struct FireConfig {
+        /// How hot the fire is at the base of the tree, between 0 and 1.
+        #[schema(range = 0.0..=1.0)]
+        pub intensity: f32,
+
+        /// Roughly how far up the tree the flames reach, as a proportion of the height of the
+        /// tree.
+        #[schema(range = 0.05..=1.0)]
+        pub flame_height: f32,
+
+        /// How likely the heat is to drift sideways as it rises, between 0 and 1.
+        #[schema(range = 0.0..=1.0)]
+        pub turbulence: f32,
+
+        /// How far the flames lean with the wind. A value of 1 means that the flames move one
+        /// unit sideways for every unit of height.
+        #[schema(range = 0.0..=1.0)]
+        pub wind_strength: f32,
+
+        /// The direction that the wind blows in, measured in degrees anti-clockwise from the
+        /// x-axis.
+        #[schema(range = 0.0..=360.0)]
+        pub wind_direction_degrees: f32,
+
+        /// The colours of the fire from coldest to hottest.
+        pub palette: Vec<RGBArray>,
+    }
+
+    impl Default for FireConfig {
+        fn default() -> Self {
+            Self {
+                intensity: 1.,
+                flame_height: 0.6,
+                turbulence: 0.5,
+                wind_strength: 0.,
+                wind_direction_degrees: 0.,
+                palette: vec![
+                    [0, 0, 0],
+                    [180, 10, 0],
+                    [255, 90, 0],
+                    [255, 200, 20],
+                    [255, 255, 220],
+                ],
+            }
+        }
+    }
+
+    impl EffectConfig for FireConfig {
+        fn factory_presets() -> Vec<(&'static str, Self)> {
+            vec![
+                (
+                    "Blue flame",
+                    Self {
+                        palette: vec![
+                            [0, 0, 0],
+                            [0, 10, 120],
+                            [0, 80, 255],
+                            [100, 200, 255],
+                            [230, 250, 255],
+                        ],
+                        ..Self::default()
+                    },
+                ),
+                (
+                    "Windy",
+                    Self {
+                        turbulence: 0.8,
+                        wind_strength: 0.6,
+                        ..Self::default()
+                    },
+                ),
+            ]
+        }
+
+        fn render_options_gui(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui) -> bool {
+            let schema = Self::schema();
+            let mut config_changed = false;
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("intensity", &mut self.intensity)
+                        .text("Intensity"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("flame_height", &mut self.flame_height)
+                        .text("Flame height"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("turbulence", &mut self.turbulence)
+                        .text("Turbulence"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("wind_strength", &mut self.wind_strength)
+                        .text("Wind strength"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("wind_direction_degrees", &mut self.wind_direction_degrees)
+                        .suffix("°")
+                        .text("Wind direction"),
+                )
+                .changed();
+
+            ui.add_space(UI_SPACING);
+
+            config_changed |= palette_editor(ui, &mut self.palette, "Palette (coldest to hottest)");
+
+            config_changed
+        }
+    }
+}
+
+/// Contains the [`Fire`] effect itself.
+#[cfg(feature = "effect-impls")]
+mod effect {
+    use super::*;
+    use std::f32::consts::TAU;
+    use ww_gift_coords::COORDS;
+
+    /// The number of columns in the heat field, which wrap around the tree.
+    const COLUMNS: usize = 24;
+
+    /// The number of rows in the heat field, from the bottom of the tree to the top.
+    const ROWS: usize = 32;
+
+    /// The time that each step of the heat field simulates, in seconds. The flames rise by one row
+    /// every step, so this sets how fast they move, no matter how often frames are shown.
+    const STEP_SECONDS: f32 = 0.04;
+
+    /// Simulate a rising heat field wrapped around the tree and display it like a fire.
+    #[derive(Clone, Debug, PartialEq, BaseEffect)]
+    #[effect(tags = ["fire", "warm", "simulation"], unattended)]
+    pub struct Fire {
+        /// The RNG used for the turbulence and for seeding heat at the base.
+        rng: StdRng,
+
+        /// The heat of each cell, between 0 and 1. This is stored in row-major order, with the
+        /// bottom row first.
+        heat: Vec<f32>,
+
+        /// The time since the last step of the heat field, in seconds.
+        time_since_step: f32,
+    }
+
+    impl Fire {
+        /// Get the heat at the given row and column, wrapping the column around the tree.
+        fn heat_at(&self, row: usize, column: isize) -> f32 {
+            self.heat[row * COLUMNS + column.rem_euclid(COLUMNS as isize) as usize]
+        }
+
+        /// Sample the heat at a fractional row and column with bilinear interpolation.
+        fn sample(&self, row: f32, column: f32) -> f32 {
+            let row = row.clamp(0., (ROWS - 1) as f32);
+            let (r0, c0) = (row.floor() as usize, column.floor() as isize);
+            let r1 = (r0 + 1).min(ROWS - 1);
+            let (tr, tc) = (row - r0 as f32, column - c0 as f32);
+
+            let bottom =
+                (self.heat_at(r0, c0 + 1) - self.heat_at(r0, c0)).mul_add(tc, self.heat_at(r0, c0));
+            let top =
+                (self.heat_at(r1, c0 + 1) - self.heat_at(r1, c0)).mul_add(tc, self.heat_at(r1, c0));
+
+            (top - bottom).mul_add(tr, bottom)
+        }
+
+        /// Advance the heat field by one step by cooling it, moving the heat upwards, and
+        /// seeding new heat at the base.
+        fn step(&mut self, config: &FireConfig) {
+            let max_cooling = 2. / (config.flame_height.max(0.01) * ROWS as f32);
+            for heat in &mut self.heat {
+                *heat = (*heat - self.rng.random_range(0.0..max_cooling)).max(0.);
+            }
+
+            for row in (1..ROWS).rev() {
+                for column in 0..COLUMNS as isize {
+                    let offset = if self.rng.random_bool(config.turbulence.clamp(0., 1.) as f64) {
+                        if self.rng.random_bool(0.5) { 1 } else { -1 }
+                    } else {
+                        0
+                    };
+
+                    let below = self.heat_at(row - 1, column);
+                    let below_drifted = self.heat_at(row - 1, column + offset);
+                    let two_below = self.heat_at(row.saturating_sub(2), column);
+
+                    self.heat[row * COLUMNS + column as usize] =
+                        2_f32.mul_add(below_drifted, below + two_below) / 4.;
+                }
+            }
+
+            for heat in &mut self.heat[..COLUMNS] {
+                if self.rng.random_bool(0.6) {
+                    let new_heat = self.rng.random_range(0.6..1.0) * config.intensity;
+                    *heat = heat.max(new_heat).min(1.);
+                }
+            }
+        }
+    }
+
+    impl Effect for Fire {
+        fn from_config(_config: FireConfig) -> Self {
+            Self {
+                rng: rng!(),
+                heat: vec![0.; ROWS * COLUMNS],
+                time_since_step: STEP_SECONDS,
+            }
+        }
+
+        fn next_frame(
+            &mut self,
+            config: &FireConfig,
+            ctx: &FrameContext,
+        ) -> Option<(FrameType, Duration)> {
+            // Take as many steps as fit into the time since the last frame, and keep the rest for
+            // next time
+            self.time_since_step += ctx.delta_secs();
+            while self.time_since_step >= STEP_SECONDS {
+                self.step(config);
+                self.time_since_step -= STEP_SECONDS;
+            }
+
+            let wind = {
+                let angle = config.wind_direction_degrees.to_radians();
+                Vec3::new(angle.cos(), angle.sin(), 0.) * config.wind_strength
+            };
+
+            let frame_data = COORDS
+                .coords()
+                .iter()
+                .map(|&point| {
+                    let point = Vec3::from(point);
+
+                    // Lean the flames downwind by sampling the heat from upwind
+                    let leaned = point - wind * point.z;
+                    let angle = leaned.y.atan2(leaned.x).rem_euclid(TAU);
+
+                    let row = point.z / COORDS.max_z() * (ROWS - 1) as f32;
+                    let column = angle / TAU * COLUMNS as f32;
+
+                    sample_gradient(&config.palette, self.sample(row, column))
+                })
+                .collect();
+
+            Some((FrameType::RawData(frame_data), ctx.target_frame_time()))
+        }
+
+        #[cfg(any(test, feature = "bench"))]
+        fn loops_to_test() -> Option<NonZeroU16> {
+            NonZeroU16::new(100)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot_effect;
+
+    #[test]
+    fn fire_test() {
+        snapshot_effect!(Fire);
+    }
+}
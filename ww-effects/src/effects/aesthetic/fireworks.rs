@@ -0,0 +1,416 @@
+//! This module contains the [`Fireworks`] effect.
+
+#[cfg(feature = "config-impls")]
+pub use config::FireworksConfig;
+
+#[cfg(feature = "effect-impls")]
+pub use effect::Fireworks;
+
+use crate::effects::prelude::*;
+
+/// Contains the config for the [`Fireworks`] effect.
+#[cfg(feature = "config-impls")]
+mod config {
+    use super::*;
+
+    /// The config for the [`Fireworks`] effect.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, BaseEffectConfig)]
+    #[serde(default)]
+    pub struct FireworksConfig {
+        /// The average number of rockets launched every second.
+        #[schema(range = 0.0..=5.0)]
+        pub launches_per_second: f32,
+
+        /// The speed at which rockets climb, in GIFT coordinate units per second.
+        #[schema(range = 0.1..=5.0)]
+        pub rocket_speed: f32,
+
+        /// The number of sparks created when a rocket bursts.
+        #[schema(range = 1..=50)]
+        pub sparks_per_burst: u8,
+
+        /// The speed at which sparks fly away from the centre of a burst, in GIFT coordinate units
+        /// per second.
+        #[schema(range = 0.0..=3.0)]
+        pub burst_speed: f32,
+
+        /// The downwards acceleration applied to sparks, in GIFT coordinate units per second
+        /// squared.
+        #[schema(range = 0.0..=3.0)]
+        pub gravity: f32,
+
+        /// The number of seconds that a spark takes to fade out completely.
+        #[schema(range = 0.1..=5.0)]
+        pub spark_lifetime_seconds: f32,
+
+        /// The chance that any given spark will burst again into a smaller secondary burst.
+        #[schema(range = 0.0..=1.0)]
+        pub secondary_burst_chance: f32,
+
+        /// The radius of each spark and rocket.
+        #[schema(range = 0.0..=0.5)]
+        pub spark_radius: f32,
+
+        /// The maximum distance where colour drops to zero.
+        ///
+        /// See [`ww_frame::FrameObject::fadeoff`].
+        #[schema(range = 0.0..=0.5)]
+        pub fadeoff: f32,
+
+        /// The colour of the rockets as they climb.
+        pub rocket_colour: RGBArray,
+
+        /// The colour that sparks fade towards as they die and fall as embers.
+        pub ember_colour: RGBArray,
+
+        /// The colours that bursts can have. Each burst picks one of these at random.
+        pub palette: Vec<RGBArray>,
+    }
+
+    impl Default for FireworksConfig {
+        fn default() -> Self {
+            Self {
+                launches_per_second: 0.8,
+                rocket_speed: 2.,
+                sparks_per_burst: 12,
+                burst_speed: 0.8,
+                gravity: 0.6,
+                spark_lifetime_seconds: 1.5,
+                secondary_burst_chance: 0.1,
+                spark_radius: 0.08,
+                fadeoff: 0.1,
+                rocket_colour: [255, 220, 150],
+                ember_colour: [120, 30, 0],
+                palette: vec![
+                    [255, 40, 40],
+                    [40, 255, 60],
+                    [60, 90, 255],
+                    [255, 215, 0],
+                    [230, 60, 255],
+                ],
+            }
+        }
+    }
+
+    impl EffectConfig for FireworksConfig {
+        fn factory_presets() -> Vec<(&'static str, Self)> {
+            vec![(
+                "Finale",
+                Self {
+                    launches_per_second: 3.5,
+                    sparks_per_burst: 25,
+                    secondary_burst_chance: 0.4,
+                    ..Self::default()
+                },
+            )]
+        }
+
+        fn render_options_gui(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui) -> bool {
+            let schema = Self::schema();
+            let mut config_changed = false;
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("launches_per_second", &mut self.launches_per_second)
+                        .suffix("/s")
+                        .text("Launch rate"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("rocket_speed", &mut self.rocket_speed)
+                        .suffix("units/s")
+                        .text("Rocket speed"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("sparks_per_burst", &mut self.sparks_per_burst)
+                        .text("Sparks per burst"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("burst_speed", &mut self.burst_speed)
+                        .suffix("units/s")
+                        .text("Burst speed"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("gravity", &mut self.gravity)
+                        .suffix("units/s²")
+                        .text("Gravity"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("spark_lifetime_seconds", &mut self.spark_lifetime_seconds)
+                        .suffix("s")
+                        .text("Spark lifetime"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("secondary_burst_chance", &mut self.secondary_burst_chance)
+                        .text("Secondary burst chance"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("spark_radius", &mut self.spark_radius)
+                        .text("Spark radius"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(schema.slider("fadeoff", &mut self.fadeoff).text("Fadeoff"))
+                .changed();
+
+            ui.add_space(UI_SPACING);
+
+            config_changed |= colour_picker(ui, &mut self.rocket_colour, "Rocket colour").changed();
+            config_changed |= colour_picker(ui, &mut self.ember_colour, "Ember colour").changed();
+
+            ui.add_space(UI_SPACING);
+
+            config_changed |= palette_editor(ui, &mut self.palette, "Burst colours");
+
+            config_changed
+        }
+    }
+}
+
+/// Contains the [`Fireworks`] effect itself.
+#[cfg(feature = "effect-impls")]
+mod effect {
+    use super::*;
+    use ww_gift_coords::COORDS;
+
+    /// The proportion of velocity that a spark keeps every second, to simulate air resistance.
+    const SPARK_DRAG_PER_SECOND: f32 = 0.364;
+
+    /// A rocket climbing up the tree before it bursts.
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct Rocket {
+        /// The current position of the rocket.
+        position: Vec3,
+
+        /// The direction that the rocket is flying in. This is always normalized.
+        direction: Vec3,
+
+        /// The height at which the rocket will burst.
+        burst_height: f32,
+    }
+
+    /// A single spark from a burst.
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct Spark {
+        /// The current position of the spark.
+        position: Vec3,
+
+        /// The current velocity of the spark, in GIFT coordinate units per second.
+        velocity: Vec3,
+
+        /// The colour of the spark when it's at full brightness.
+        colour: RGBArray,
+
+        /// How much life the spark has left, from 1 when it's created down to 0 when it dies.
+        life: f32,
+
+        /// Will this spark burst again into a secondary burst?
+        will_burst: bool,
+    }
+
+    /// Launch rockets from the bottom of the tree and burst them into colourful spheres of sparks
+    /// that fall under gravity.
+    #[derive(Clone, Debug, PartialEq, BaseEffect)]
+    #[effect(tags = ["fireworks", "celebration", "particles"], unattended)]
+    pub struct Fireworks {
+        /// The RNG used to launch and burst the rockets.
+        rng: StdRng,
+
+        /// The rockets that are currently climbing.
+        rockets: Vec<Rocket>,
+
+        /// The sparks that are currently alive.
+        sparks: Vec<Spark>,
+    }
+
+    impl Fireworks {
+        /// Launch a new rocket from a random point near the bottom of the tree.
+        fn launch_rocket(&mut self) {
+            let position = Vec3::new(
+                self.rng.random_range(-0.5..0.5),
+                self.rng.random_range(-0.5..0.5),
+                0.,
+            );
+
+            // Mostly upwards, but with a slight lean towards the middle of the tree so that the
+            // bursts stay over the lights
+            let direction = (Vec3::Z * 4. - position
+                + 0.3 * random_vector(&mut self.rng) * Vec3::new(1., 1., 0.))
+            .normalize();
+
+            let burst_height = COORDS.max_z() * self.rng.random_range(0.4..0.9);
+
+            self.rockets.push(Rocket {
+                position,
+                direction,
+                burst_height,
+            });
+        }
+
+        /// Burst into a sphere of sparks at the given position, all with a random colour from
+        /// the palette. If `is_secondary` is true, then none of the new sparks will burst again.
+        fn burst(
+            &mut self,
+            position: Vec3,
+            spark_count: u8,
+            is_secondary: bool,
+            config: &FireworksConfig,
+        ) {
+            let colour = random_palette_colour(&mut self.rng, &config.palette);
+
+            for _ in 0..spark_count {
+                let velocity = random_vector(&mut self.rng)
+                    * config.burst_speed
+                    * self.rng.random_range(0.6..1.0);
+
+                self.sparks.push(Spark {
+                    position,
+                    velocity,
+                    colour,
+                    life: 1.,
+                    will_burst: !is_secondary
+                        && self
+                            .rng
+                            .random_bool(config.secondary_burst_chance.clamp(0., 1.) as f64),
+                });
+            }
+        }
+
+        /// Create the frame objects for all the rockets and sparks.
+        fn frame_objects(&self, config: &FireworksConfig) -> Vec<FrameObject> {
+            let rockets = self.rockets.iter().map(|rocket| FrameObject {
+                object: Object::Sphere {
+                    center: rocket.position,
+                    radius: config.spark_radius,
+                },
+                colour: config.rocket_colour,
+                fadeoff: config.fadeoff,
+            });
+
+            let sparks = self.sparks.iter().map(|spark| FrameObject {
+                object: Object::Sphere {
+                    center: spark.position,
+                    radius: config.spark_radius,
+                },
+                colour: scale_colour(
+                    lerp_colour(config.ember_colour, spark.colour, spark.life),
+                    spark.life.sqrt(),
+                ),
+                fadeoff: config.fadeoff,
+            });
+
+            rockets.chain(sparks).collect()
+        }
+    }
+
+    impl Effect for Fireworks {
+        fn from_config(_config: FireworksConfig) -> Self {
+            Self {
+                rng: rng!(),
+                rockets: vec![],
+                sparks: vec![],
+            }
+        }
+
+        fn next_frame(
+            &mut self,
+            config: &FireworksConfig,
+            ctx: &FrameContext,
+        ) -> Option<(FrameType, Duration)> {
+            let delta = ctx.delta_secs();
+            let frame = FrameType::Frame3D(Frame3D::new(self.frame_objects(config), false));
+
+            if self
+                .rng
+                .random_bool((config.launches_per_second * delta).clamp(0., 1.) as f64)
+            {
+                self.launch_rocket();
+            }
+
+            // Move the rockets and burst any that have reached their burst height
+            let mut burst_positions = vec![];
+            self.rockets.retain_mut(|rocket| {
+                rocket.position += rocket.direction * config.rocket_speed * delta;
+
+                if rocket.position.z >= rocket.burst_height {
+                    burst_positions.push(rocket.position);
+                    false
+                } else {
+                    true
+                }
+            });
+
+            for position in burst_positions {
+                self.burst(position, config.sparks_per_burst, false, config);
+            }
+
+            // Move the sparks and kill any that have died or hit the ground
+            let mut secondary_burst_positions = vec![];
+            let drag = SPARK_DRAG_PER_SECOND.powf(delta);
+            self.sparks.retain_mut(|spark| {
+                spark.velocity.z = config.gravity.mul_add(-delta, spark.velocity.z);
+                spark.velocity *= drag;
+                spark.position += spark.velocity * delta;
+                spark.life -= delta / config.spark_lifetime_seconds.max(f32::EPSILON);
+
+                if spark.will_burst && spark.life < 0.6 {
+                    secondary_burst_positions.push(spark.position);
+                    false
+                } else {
+                    spark.life > 0. && spark.position.z >= 0.
+                }
+            });
+
+            for position in secondary_burst_positions {
+                self.burst(position, (config.sparks_per_burst / 3).max(1), true, config);
+            }
+
+            Some((frame, ctx.target_frame_time()))
+        }
+
+        #[cfg(any(test, feature = "bench"))]
+        fn loops_to_test() -> Option<NonZeroU16> {
+            NonZeroU16::new(100)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot_effect;
+
+    #[test]
+    fn fireworks_test() {
+        snapshot_effect!(Fireworks);
+    }
+}
@@ -15,16 +15,20 @@ mod config {
 
     /// The config for the [`LavaLamp`] effect.
     #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, BaseEffectConfig)]
+    #[serde(default)]
     pub struct LavaLampConfig {
         /// The base colour of the spheres.
+        #[schema(colour)]
         pub base_colour: [u8; 3],
 
         /// The maximum RBG colour variation from the base.
+        #[schema(range = 1..=255)]
         pub variation: u8,
 
         /// The maximum distance where colour drops to zero.
         ///
         /// See [`ww_frame::FrameObject::fadeoff`].
+        #[schema(range = 0.0..=1.5)]
         pub fadeoff: f32,
     }
 
@@ -39,16 +43,39 @@ mod config {
     }
 
     impl EffectConfig for LavaLampConfig {
+        fn factory_presets() -> Vec<(&'static str, Self)> {
+            vec![
+                (
+                    "Icy",
+                    Self {
+                        base_colour: [90, 200, 255],
+                        variation: 40,
+                        fadeoff: 0.4,
+                    },
+                ),
+                (
+                    "Ember",
+                    Self {
+                        base_colour: [255, 80, 10],
+                        variation: 30,
+                        fadeoff: 0.25,
+                    },
+                ),
+            ]
+        }
+
         fn render_options_gui(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui) -> bool {
+            let schema = Self::schema();
             let mut config_changed = false;
 
             config_changed |= ui
-                .add(egui::Slider::new(&mut self.fadeoff, 0.0..=1.5).text("Fadeoff"))
+                .add(schema.slider("fadeoff", &mut self.fadeoff).text("Fadeoff"))
                 .changed();
 
             config_changed |= ui
                 .add(
-                    egui::Slider::new(&mut self.variation, 1..=255)
+                    schema
+                        .slider("variation", &mut self.variation)
                         .text("Colour variation (requires restart)"),
                 )
                 .changed();
@@ -69,6 +96,15 @@ mod effect {
     use glam::IVec3;
     use ww_gift_coords::COORDS;
 
+    /// How long to show each frame for.
+    const FRAME_TIME: Duration = Duration::from_millis(100);
+
+    /// The speed of the spheres, in GIFT coordinate units per second.
+    const SPEED: f32 = 0.5;
+
+    /// How much the spheres wander off course each second.
+    const WANDER_PER_SECOND: f32 = 0.1;
+
     /// A simple sphere used to keep track of the spheres in the lava lamp.
     #[derive(Clone, Copy, Debug, PartialEq)]
     struct Sphere {
@@ -111,6 +147,7 @@ mod effect {
 
     /// Display a lava lamp-like effect on the tree.
     #[derive(Clone, Debug, PartialEq, BaseEffect)]
+    #[effect(tags = ["lava", "blobs", "calm"], unattended)]
     pub struct LavaLamp {
         /// The RNG used to move the spheres randomly.
         rng: StdRng,
@@ -147,7 +184,12 @@ mod effect {
             Self { rng, spheres }
         }
 
-        fn next_frame(&mut self, config: &LavaLampConfig) -> Option<(FrameType, Duration)> {
+        fn next_frame(
+            &mut self,
+            config: &LavaLampConfig,
+            ctx: &FrameContext,
+        ) -> Option<(FrameType, Duration)> {
+            let delta = ctx.delta_secs();
             let sphere_frame_objects = self
                 .spheres
                 .iter()
@@ -157,21 +199,22 @@ mod effect {
             let frame = FrameType::Frame3D(Frame3D::new(sphere_frame_objects, true));
 
             for sphere in &mut self.spheres {
-                sphere.centre += 0.05 * sphere.movement_direction;
-                sphere.movement_direction =
-                    (sphere.movement_direction + 0.01 * random_vector(&mut self.rng)).normalize();
+                sphere.centre += SPEED * delta * sphere.movement_direction;
+                sphere.movement_direction = (sphere.movement_direction
+                    + WANDER_PER_SECOND * delta * random_vector(&mut self.rng))
+                .normalize();
 
                 while !COORDS
                     .is_within_bounding_box((sphere.centre + sphere.movement_direction).into())
                 {
-                    // We're not multiplying the random vector by 0.01 here because if we did, then
-                    // this loop would take ages to bring the spheres back into the bounding box
+                    // We're not scaling the random vector down here because if we did, then this
+                    // loop would take ages to bring the spheres back into the bounding box
                     sphere.movement_direction =
                         (sphere.movement_direction + random_vector(&mut self.rng)).normalize();
                 }
             }
 
-            Some((frame, Duration::from_millis(100)))
+            Some((frame, FRAME_TIME))
         }
 
         #[cfg(any(test, feature = "bench"))]
@@ -0,0 +1,472 @@
+//! This module contains the [`Lightning`] effect.
+
+#[cfg(feature = "config-impls")]
+pub use config::LightningConfig;
+
+#[cfg(feature = "effect-impls")]
+pub use effect::Lightning;
+
+use crate::effects::prelude::*;
+
+/// Contains the config for the [`Lightning`] effect.
+#[cfg(feature = "config-impls")]
+mod config {
+    use super::*;
+
+    /// The config for the [`Lightning`] effect.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, BaseEffectConfig)]
+    #[serde(default)]
+    pub struct LightningConfig {
+        /// The average number of strikes every minute.
+        #[schema(range = 1.0..=60.0)]
+        pub strikes_per_minute: f32,
+
+        /// The chance that the bolt forks at each of its kinks.
+        #[schema(range = 0.0..=1.0)]
+        pub branching_factor: f32,
+
+        /// The thickness of the main bolt. Branches are a bit thinner.
+        #[schema(range = 0.0..=0.3)]
+        pub thickness: f32,
+
+        /// The maximum distance where colour drops to zero.
+        ///
+        /// See [`ww_frame::FrameObject::fadeoff`].
+        #[schema(range = 0.0..=0.5)]
+        pub fadeoff: f32,
+
+        /// Should we show a dim rain in the background?
+        pub rain: bool,
+
+        /// The colour of the main bolt.
+        pub bolt_colour: RGBArray,
+
+        /// The colour of the branches of the bolt.
+        pub branch_colour: RGBArray,
+
+        /// The colour of the rain in the background.
+        pub rain_colour: RGBArray,
+    }
+
+    impl Default for LightningConfig {
+        fn default() -> Self {
+            Self {
+                strikes_per_minute: 12.,
+                branching_factor: 0.35,
+                thickness: 0.12,
+                fadeoff: 0.15,
+                rain: true,
+                bolt_colour: [230, 230, 255],
+                branch_colour: [140, 110, 255],
+                rain_colour: [0, 0, 20],
+            }
+        }
+    }
+
+    impl EffectConfig for LightningConfig {
+        fn factory_presets() -> Vec<(&'static str, Self)> {
+            vec![
+                (
+                    "Dry storm",
+                    Self {
+                        strikes_per_minute: 24.,
+                        branching_factor: 0.6,
+                        rain: false,
+                        ..Self::default()
+                    },
+                ),
+                (
+                    "Distant",
+                    Self {
+                        strikes_per_minute: 4.,
+                        thickness: 0.06,
+                        bolt_colour: [120, 120, 160],
+                        branch_colour: [60, 40, 120],
+                        ..Self::default()
+                    },
+                ),
+            ]
+        }
+
+        fn render_options_gui(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui) -> bool {
+            let schema = Self::schema();
+            let mut config_changed = false;
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("strikes_per_minute", &mut self.strikes_per_minute)
+                        .suffix("/min")
+                        .text("Strike frequency"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("branching_factor", &mut self.branching_factor)
+                        .text("Branching factor"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("thickness", &mut self.thickness)
+                        .text("Thickness"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(schema.slider("fadeoff", &mut self.fadeoff).text("Fadeoff"))
+                .changed();
+
+            config_changed |= ui.checkbox(&mut self.rain, "Rain?").changed();
+
+            ui.add_space(UI_SPACING);
+
+            config_changed |= colour_picker(ui, &mut self.bolt_colour, "Bolt colour").changed();
+            config_changed |= colour_picker(ui, &mut self.branch_colour, "Branch colour").changed();
+            config_changed |= colour_picker(ui, &mut self.rain_colour, "Rain colour").changed();
+
+            config_changed
+        }
+    }
+}
+
+/// Contains the [`Lightning`] effect itself.
+#[cfg(feature = "effect-impls")]
+mod effect {
+    use super::*;
+    use ww_gift_coords::COORDS;
+
+    /// How long each step of the brightness envelope of a strike lasts, in seconds.
+    const ENVELOPE_STEP_SECONDS: f32 = 0.02;
+
+    /// How far down each kink of the bolt goes, as a range in GIFT coordinate units.
+    const KINK_HEIGHT: std::ops::Range<f32> = 0.15..0.35;
+
+    /// How far around the tree each kink of the bolt can go, in radians.
+    const KINK_ANGLE: f32 = 0.6;
+
+    /// The radius of the bottom of the tree, where most of the lights are.
+    const BASE_RADIUS: f32 = 0.85;
+
+    /// How many times a branch can fork into smaller branches.
+    const MAX_BRANCH_DEPTH: u8 = 2;
+
+    /// How many raindrops there are when the rain is turned on.
+    const RAINDROPS: usize = 40;
+
+    /// How fast the rain falls, in GIFT coordinate units per second.
+    const RAIN_SPEED: f32 = 2.5;
+
+    /// The distance from a raindrop within which lights are lit by it.
+    const RAINDROP_RADIUS: f32 = 0.12;
+
+    /// One zigzag line of a bolt, which is either the main bolt or a branch.
+    #[derive(Clone, Debug, PartialEq)]
+    struct Fork {
+        /// The points along the fork, from top to bottom.
+        points: Box<[Vec3]>,
+
+        /// How many times we've branched to get here. The main bolt has a depth of 0.
+        depth: u8,
+    }
+
+    /// What the storm is currently doing.
+    #[derive(Clone, Debug, PartialEq)]
+    enum Phase {
+        /// Waiting for the next strike.
+        Waiting {
+            /// How many more seconds to wait.
+            seconds_left: f32,
+        },
+
+        /// Showing a strike.
+        Striking {
+            /// The main bolt and all its branches.
+            forks: Vec<Fork>,
+
+            /// The brightness of the bolt in each step of the strike, which flashes, flickers and
+            /// then fades out. Each step lasts for [`ENVELOPE_STEP_SECONDS`].
+            brightness: Vec<f32>,
+
+            /// How long the strike has been showing, in seconds.
+            seconds: f32,
+        },
+    }
+
+    /// Strike the tree with branching bolts of lightning in a rainy storm.
+    #[derive(Clone, Debug, PartialEq, BaseEffect)]
+    #[effect(tags = ["storm", "rain", "flashing"], unattended)]
+    pub struct Lightning {
+        /// The RNG used to shape the bolts and time the strikes.
+        rng: StdRng,
+
+        /// What the storm is currently doing.
+        phase: Phase,
+
+        /// The positions of the raindrops.
+        raindrops: Vec<Vec3>,
+    }
+
+    impl Lightning {
+        /// Get a random point somewhere in the bounding box to put a raindrop.
+        fn random_raindrop(&mut self) -> Vec3 {
+            Vec3::new(
+                self.rng.random_range(-1.0..=1.0),
+                self.rng.random_range(-1.0..=1.0),
+                self.rng.random_range(0.0..=COORDS.max_z()),
+            )
+        }
+
+        /// Choose how many seconds to wait until the next strike.
+        fn random_gap(&mut self, config: &LightningConfig) -> f32 {
+            let average_seconds = 60. / config.strikes_per_minute.max(0.1);
+            average_seconds * self.rng.random_range(0.25..1.75)
+        }
+
+        /// Walk down and around the surface of the tree from the start point in random zigzags,
+        /// since that's where the lights are. The main bolt keeps going until it reaches the
+        /// ground, but branches only go a few steps and swing further sideways.
+        fn random_walk(&mut self, start: Vec3, is_branch: bool) -> Vec<Vec3> {
+            let mut points = vec![start];
+            let mut angle = start.y.atan2(start.x);
+            let mut z = start.z;
+            let (steps, max_angle) = if is_branch {
+                (self.rng.random_range(2..=5), KINK_ANGLE * 1.5)
+            } else {
+                (usize::MAX, KINK_ANGLE)
+            };
+
+            for _ in 0..steps {
+                angle += self.rng.random_range(-max_angle..=max_angle);
+                z = (z - self.rng.random_range(KINK_HEIGHT)).max(0.);
+
+                let radius =
+                    BASE_RADIUS * (1. - z / COORDS.max_z()) * self.rng.random_range(0.85..=1.05);
+                points.push(Vec3::new(radius * angle.cos(), radius * angle.sin(), z));
+
+                if z <= 0. {
+                    break;
+                }
+            }
+
+            points
+        }
+
+        /// Create a new bolt from the top of the tree to the bottom, with random branches.
+        fn new_bolt(&mut self, config: &LightningConfig) -> Vec<Fork> {
+            let top = Vec3::new(0., 0., COORDS.max_z())
+                + random_vector(&mut self.rng) * Vec3::new(0.1, 0.1, 0.);
+            let mut forks = vec![Fork {
+                points: self.random_walk(top, false).into_boxed_slice(),
+                depth: 0,
+            }];
+
+            // Go through the forks breadth-first so that new branches get a chance to branch too
+            let mut idx = 0;
+            while idx < forks.len() {
+                let depth = forks[idx].depth;
+                if depth < MAX_BRANCH_DEPTH {
+                    let chance = config.branching_factor.clamp(0., 1.) / (depth + 1) as f32;
+                    let kinks = forks[idx].points.len().saturating_sub(1);
+
+                    for kink in 1..kinks {
+                        if self.rng.random_bool(chance as f64) {
+                            let start = forks[idx].points[kink];
+                            forks.push(Fork {
+                                points: self.random_walk(start, true).into_boxed_slice(),
+                                depth: depth + 1,
+                            });
+                        }
+                    }
+                }
+                idx += 1;
+            }
+
+            forks
+        }
+
+        /// Choose the brightness of the bolt in each step of a strike. The bolt flashes, then
+        /// flickers a few times as the return strokes go through it, and then fades out.
+        fn new_brightness_envelope(&mut self) -> Vec<f32> {
+            let mut brightness = vec![1.; 3];
+
+            let flickers = self.rng.random_range(1..=3);
+            let mut peak = 0.9;
+            for _ in 0..flickers {
+                let dark_steps = self.rng.random_range(2..=5);
+                let bright_steps = self.rng.random_range(2..=3);
+                brightness.extend(std::iter::repeat_n(0.15, dark_steps));
+                brightness.extend(std::iter::repeat_n(peak, bright_steps));
+                peak *= 0.8;
+            }
+
+            let fade_steps = self.rng.random_range(10..=20);
+            brightness
+                .extend((1..=fade_steps).map(|step| peak * (1. - step as f32 / fade_steps as f32)));
+
+            brightness
+        }
+
+        /// Move the rain by the given time in seconds and render it, or render a black background
+        /// if there's no rain.
+        fn background(&mut self, config: &LightningConfig, delta: f32) -> Vec<RGBArray> {
+            if !config.rain {
+                return vec![[0; 3]; COORDS.lights_num()];
+            }
+
+            for idx in 0..self.raindrops.len() {
+                self.raindrops[idx].z = RAIN_SPEED.mul_add(-delta, self.raindrops[idx].z);
+                if self.raindrops[idx].z < 0. {
+                    let mut drop = self.random_raindrop();
+                    drop.z = COORDS.max_z();
+                    self.raindrops[idx] = drop;
+                }
+            }
+
+            let drop_colour = scale_colour(config.rain_colour, 3.);
+            COORDS
+                .coords()
+                .iter()
+                .map(|&point| {
+                    let point = Vec3::from(point);
+                    if self
+                        .raindrops
+                        .iter()
+                        .any(|drop| drop.distance(point) < RAINDROP_RADIUS)
+                    {
+                        drop_colour
+                    } else {
+                        config.rain_colour
+                    }
+                })
+                .collect()
+        }
+
+        /// Create the frame objects for the forks of a bolt at the given brightness.
+        fn bolt_objects(
+            forks: &[Fork],
+            brightness: f32,
+            config: &LightningConfig,
+        ) -> Vec<FrameObject> {
+            forks
+                .iter()
+                .map(|fork| {
+                    let (threshold, start_colour, end_colour) = if fork.depth == 0 {
+                        let colour = scale_colour(config.bolt_colour, brightness);
+                        (config.thickness, colour, colour)
+                    } else {
+                        // Branches get thinner and dimmer towards their tips
+                        let colour =
+                            scale_colour(config.branch_colour, brightness / fork.depth as f32);
+                        (config.thickness * 0.6, colour, scale_colour(colour, 0.3))
+                    };
+
+                    FrameObject {
+                        object: Object::CatmullRomSpline {
+                            points: fork.points.clone(),
+                            threshold,
+                            start_colour,
+                            end_colour,
+                        },
+                        colour: start_colour,
+                        fadeoff: config.fadeoff,
+                    }
+                })
+                .collect()
+        }
+    }
+
+    impl Effect for Lightning {
+        fn from_config(_config: LightningConfig) -> Self {
+            let mut lightning = Self {
+                rng: rng!(),
+                phase: Phase::Waiting { seconds_left: 0. },
+                raindrops: vec![],
+            };
+
+            lightning.raindrops = (0..RAINDROPS)
+                .map(|_| lightning.random_raindrop())
+                .collect();
+            lightning.phase = Phase::Waiting {
+                seconds_left: lightning.rng.random_range(0.2..1.0),
+            };
+            lightning
+        }
+
+        fn next_frame(
+            &mut self,
+            config: &LightningConfig,
+            ctx: &FrameContext,
+        ) -> Option<(FrameType, Duration)> {
+            let delta = ctx.delta_secs();
+            let mut frame_data = self.background(config, delta);
+
+            let next_phase = match &mut self.phase {
+                Phase::Waiting { seconds_left } if *seconds_left <= 0. => Some(Phase::Striking {
+                    forks: self.new_bolt(config),
+                    brightness: self.new_brightness_envelope(),
+                    seconds: 0.,
+                }),
+                Phase::Waiting { seconds_left } => {
+                    *seconds_left -= delta;
+                    None
+                }
+                Phase::Striking {
+                    forks,
+                    brightness,
+                    seconds,
+                } => {
+                    let step = (*seconds / ENVELOPE_STEP_SECONDS) as usize;
+                    if let Some(&brightness) = brightness.get(step) {
+                        // The brightest flashes light up the whole sky a little bit
+                        let sky = scale_colour(config.bolt_colour, 0.1 * brightness);
+                        let bolt =
+                            Frame3D::new(Self::bolt_objects(forks, brightness, config), false)
+                                .to_raw_data();
+
+                        for (light, bolt_light) in frame_data.iter_mut().zip(bolt) {
+                            *light = if bolt_light == [0; 3] {
+                                std::array::from_fn(|channel| light[channel].max(sky[channel]))
+                            } else {
+                                bolt_light
+                            };
+                        }
+
+                        *seconds += delta;
+                        None
+                    } else {
+                        Some(Phase::Waiting {
+                            seconds_left: self.random_gap(config),
+                        })
+                    }
+                }
+            };
+
+            if let Some(phase) = next_phase {
+                self.phase = phase;
+            }
+
+            Some((FrameType::RawData(frame_data), ctx.target_frame_time()))
+        }
+
+        #[cfg(any(test, feature = "bench"))]
+        fn loops_to_test() -> Option<NonZeroU16> {
+            NonZeroU16::new(100)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot_effect;
+
+    #[test]
+    fn lightning_test() {
+        snapshot_effect!(Lightning);
+    }
+}
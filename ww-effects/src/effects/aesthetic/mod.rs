@@ -1,9 +1,16 @@
-//! This module contains purely mathematical effects.
+//! This module contains effects which are just nice to look at.
 
+pub mod countdown;
+pub mod digital_rain;
+pub mod fire;
+pub mod fireworks;
 pub mod lava_lamp;
+pub mod lightning;
+pub mod plasma;
+pub mod ripple;
+pub mod snowfall;
+pub mod twinkle;
 
-#[cfg(feature = "effect-impls")]
-pub use self::lava_lamp::LavaLamp;
-
-#[cfg(feature = "config-impls")]
-pub use self::lava_lamp::LavaLampConfig;
+// The effects in these modules are found by the build script, which re-exports them along with
+// their configs
+include!(concat!(env!("OUT_DIR"), "/effects/aesthetic.rs"));
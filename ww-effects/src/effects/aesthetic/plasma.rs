@@ -0,0 +1,177 @@
+//! This module contains the [`Plasma`] effect.
+
+#[cfg(feature = "config-impls")]
+pub use config::PlasmaConfig;
+
+#[cfg(feature = "effect-impls")]
+pub use effect::Plasma;
+
+use crate::effects::prelude::*;
+
+/// Contains the config for the [`Plasma`] effect.
+#[cfg(feature = "config-impls")]
+mod config {
+    use super::*;
+
+    /// The config for the [`Plasma`] effect.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, BaseEffectConfig)]
+    #[serde(default)]
+    pub struct PlasmaConfig {
+        /// How many noise cycles fit into one GIFT coordinate unit. Larger values give smaller
+        /// blobs of colour.
+        #[schema(range = 0.05..=5.0)]
+        pub scale: f32,
+
+        /// How quickly the noise changes over time.
+        #[schema(range = 0.0..=3.0)]
+        pub speed: f32,
+
+        /// The number of layers of noise to add together. More octaves give finer detail.
+        #[schema(range = 1..=6)]
+        pub octaves: u8,
+
+        /// How strongly to push the noise towards the ends of the palette. A value of 1 leaves
+        /// the noise alone.
+        #[schema(range = 0.1..=5.0)]
+        pub contrast: f32,
+
+        /// The colours that the noise is mapped through, from lowest to highest.
+        pub palette: Vec<RGBArray>,
+    }
+
+    impl Default for PlasmaConfig {
+        fn default() -> Self {
+            Self {
+                scale: 0.8,
+                speed: 0.3,
+                octaves: 2,
+                contrast: 1.5,
+                palette: vec![
+                    [10, 0, 80],
+                    [120, 0, 200],
+                    [255, 0, 120],
+                    [255, 140, 0],
+                    [255, 240, 60],
+                ],
+            }
+        }
+    }
+
+    impl EffectConfig for PlasmaConfig {
+        fn factory_presets() -> Vec<(&'static str, Self)> {
+            vec![(
+                "Ocean",
+                Self {
+                    scale: 1.2,
+                    speed: 0.15,
+                    palette: vec![
+                        [0, 10, 40],
+                        [0, 60, 120],
+                        [0, 150, 180],
+                        [80, 220, 200],
+                        [220, 255, 250],
+                    ],
+                    ..Self::default()
+                },
+            )]
+        }
+
+        fn render_options_gui(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui) -> bool {
+            let schema = Self::schema();
+            let mut config_changed = false;
+
+            config_changed |= ui
+                .add(schema.slider("scale", &mut self.scale).text("Scale"))
+                .changed();
+
+            config_changed |= ui
+                .add(schema.slider("speed", &mut self.speed).text("Speed"))
+                .changed();
+
+            config_changed |= ui
+                .add(schema.slider("octaves", &mut self.octaves).text("Octaves"))
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("contrast", &mut self.contrast)
+                        .text("Contrast"),
+                )
+                .changed();
+
+            ui.add_space(UI_SPACING);
+
+            config_changed |= palette_editor(ui, &mut self.palette, "Palette");
+
+            config_changed
+        }
+    }
+}
+
+/// Contains the [`Plasma`] effect itself.
+#[cfg(feature = "effect-impls")]
+mod effect {
+    use super::*;
+    use ww_gift_coords::COORDS;
+
+    /// Colour every light by sampling 4D simplex noise at its position and the current time, and
+    /// mapping the result through a palette.
+    #[derive(Clone, Debug, PartialEq, BaseEffect)]
+    #[effect(tags = ["noise", "colourful", "calm"], unattended)]
+    pub struct Plasma {
+        /// The noise that we sample.
+        noise: SimplexNoise,
+
+        /// The position of the current frame along the time axis of the noise.
+        time: f32,
+    }
+
+    impl Effect for Plasma {
+        fn from_config(_config: PlasmaConfig) -> Self {
+            Self {
+                noise: SimplexNoise::new(&mut rng!()),
+                time: 0.,
+            }
+        }
+
+        fn next_frame(
+            &mut self,
+            config: &PlasmaConfig,
+            ctx: &FrameContext,
+        ) -> Option<(FrameType, Duration)> {
+            self.time = config.speed.mul_add(ctx.delta_secs(), self.time);
+
+            let frame_data = COORDS
+                .coords()
+                .iter()
+                .map(|&point| {
+                    let point = Vec3::from(point) * config.scale;
+                    let value = self
+                        .noise
+                        .fractal_noise4(point.extend(self.time), config.octaves);
+
+                    sample_gradient(&config.palette, (value * config.contrast).mul_add(0.5, 0.5))
+                })
+                .collect();
+
+            Some((FrameType::RawData(frame_data), ctx.target_frame_time()))
+        }
+
+        #[cfg(any(test, feature = "bench"))]
+        fn loops_to_test() -> Option<NonZeroU16> {
+            NonZeroU16::new(100)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot_effect;
+
+    #[test]
+    fn plasma_test() {
+        snapshot_effect!(Plasma);
+    }
+}
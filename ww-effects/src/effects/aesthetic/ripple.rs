@@ -0,0 +1,252 @@
+//! This module contains the [`Ripple`] effect.
+
+#[cfg(feature = "config-impls")]
+pub use config::RippleConfig;
+
+#[cfg(feature = "effect-impls")]
+pub use effect::Ripple;
+
+use crate::effects::prelude::*;
+
+/// Contains the config for the [`Ripple`] effect.
+#[cfg(feature = "config-impls")]
+mod config {
+    use super::*;
+
+    /// The config for the [`Ripple`] effect.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, BaseEffectConfig)]
+    #[serde(default)]
+    pub struct RippleConfig {
+        /// The average number of drops that land on the tree every second.
+        #[schema(range = 0.0..=10.0)]
+        pub drops_per_second: f32,
+
+        /// How fast the ripples spread out, in GIFT coordinate units per second.
+        #[schema(range = 0.1..=5.0)]
+        pub wave_speed: f32,
+
+        /// The thickness of each ripple.
+        #[schema(range = 0.0..=1.0)]
+        pub thickness: f32,
+
+        /// The maximum distance where colour drops to zero.
+        ///
+        /// See [`ww_frame::FrameObject::fadeoff`].
+        #[schema(range = 0.0..=0.5)]
+        pub fadeoff: f32,
+
+        /// How far a ripple spreads before it fades away completely.
+        #[schema(range = 0.5..=5.0)]
+        pub max_radius: f32,
+
+        /// The colours of the ripples. Each drop picks a random colour from this list.
+        pub palette: Vec<RGBArray>,
+    }
+
+    impl Default for RippleConfig {
+        fn default() -> Self {
+            Self {
+                drops_per_second: 1.5,
+                wave_speed: 1.2,
+                thickness: 0.2,
+                fadeoff: 0.1,
+                max_radius: 2.5,
+                palette: vec![[0, 80, 255], [0, 200, 200], [120, 0, 255], [255, 255, 255]],
+            }
+        }
+    }
+
+    impl EffectConfig for RippleConfig {
+        fn factory_presets() -> Vec<(&'static str, Self)> {
+            vec![(
+                "Rain",
+                Self {
+                    drops_per_second: 6.,
+                    wave_speed: 2.,
+                    thickness: 0.1,
+                    max_radius: 1.,
+                    ..Self::default()
+                },
+            )]
+        }
+
+        fn render_options_gui(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui) -> bool {
+            let schema = Self::schema();
+            let mut config_changed = false;
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("drops_per_second", &mut self.drops_per_second)
+                        .suffix("/s")
+                        .text("Drop rate"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("wave_speed", &mut self.wave_speed)
+                        .suffix("units/s")
+                        .text("Wave speed"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("thickness", &mut self.thickness)
+                        .text("Thickness"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(schema.slider("fadeoff", &mut self.fadeoff).text("Fadeoff"))
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("max_radius", &mut self.max_radius)
+                        .text("Max radius"),
+                )
+                .changed();
+
+            ui.add_space(UI_SPACING);
+
+            config_changed |= palette_editor(ui, &mut self.palette, "Palette");
+
+            config_changed
+        }
+    }
+}
+
+/// Contains the [`Ripple`] effect itself.
+#[cfg(feature = "effect-impls")]
+mod effect {
+    use super::*;
+    use ww_gift_coords::COORDS;
+
+    /// A single ripple spreading out from where a drop landed.
+    #[derive(Clone, Debug, PartialEq)]
+    struct Wave {
+        /// Where the drop landed.
+        center: Vec3,
+
+        /// The current radius of the middle of the shell.
+        radius: f32,
+
+        /// The colour of the ripple at full brightness.
+        colour: RGBArray,
+    }
+
+    /// Drop raindrops onto random lights and send out spherical ripples which fade as they grow
+    /// and add together where they overlap.
+    #[derive(Clone, Debug, PartialEq, BaseEffect)]
+    #[effect(tags = ["rain", "water", "waves"], unattended)]
+    pub struct Ripple {
+        /// The RNG used to choose where drops land and what colour they are.
+        rng: StdRng,
+
+        /// The ripples which are currently spreading out.
+        waves: Vec<Wave>,
+    }
+
+    impl Ripple {
+        /// Render a single ripple as a hollow shell, which is a coloured sphere with a black
+        /// sphere inside it. [`Frame3D`] can't add colours together, so each ripple is rendered
+        /// on its own and then added to the others.
+        fn render_wave(wave: &Wave, config: &RippleConfig) -> Vec<RGBArray> {
+            let brightness = 1. - wave.radius / config.max_radius;
+            let half_thickness = config.thickness / 2.;
+
+            let mut objects = vec![FrameObject {
+                object: Object::Sphere {
+                    center: wave.center,
+                    radius: wave.radius + half_thickness,
+                },
+                colour: scale_colour(wave.colour, brightness),
+                fadeoff: config.fadeoff,
+            }];
+
+            if wave.radius > half_thickness {
+                objects.push(FrameObject {
+                    object: Object::Sphere {
+                        center: wave.center,
+                        radius: wave.radius - half_thickness,
+                    },
+                    colour: [0; 3],
+                    fadeoff: 0.,
+                });
+            }
+
+            Frame3D::new(objects, false).to_raw_data()
+        }
+    }
+
+    impl Effect for Ripple {
+        fn from_config(_config: RippleConfig) -> Self {
+            Self {
+                rng: rng!(),
+                waves: vec![],
+            }
+        }
+
+        fn next_frame(
+            &mut self,
+            config: &RippleConfig,
+            ctx: &FrameContext,
+        ) -> Option<(FrameType, Duration)> {
+            let delta = ctx.delta_secs();
+
+            // Land a geometrically distributed number of drops, which averages out to the right
+            // number each second
+            let expected = config.drops_per_second.max(0.) * delta;
+            while self.rng.random_bool((expected / (1. + expected)) as f64) {
+                let lights = COORDS.coords();
+                let colour = random_palette_colour(&mut self.rng, &config.palette);
+
+                self.waves.push(Wave {
+                    center: Vec3::from(lights[self.rng.random_range(0..lights.len())]),
+                    radius: 0.,
+                    colour,
+                });
+            }
+
+            let frame_data = self.waves.iter().fold(
+                vec![[0; 3]; COORDS.lights_num()],
+                |mut frame_data, wave| {
+                    for (light, colour) in
+                        frame_data.iter_mut().zip(Self::render_wave(wave, config))
+                    {
+                        *light = add_colours(*light, colour);
+                    }
+                    frame_data
+                },
+            );
+
+            for wave in &mut self.waves {
+                wave.radius = config.wave_speed.mul_add(delta, wave.radius);
+            }
+            self.waves.retain(|wave| wave.radius < config.max_radius);
+
+            Some((FrameType::RawData(frame_data), ctx.target_frame_time()))
+        }
+
+        #[cfg(any(test, feature = "bench"))]
+        fn loops_to_test() -> Option<NonZeroU16> {
+            NonZeroU16::new(100)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot_effect;
+
+    #[test]
+    fn ripple_test() {
+        snapshot_effect!(Ripple);
+    }
+}
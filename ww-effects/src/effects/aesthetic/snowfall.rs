@@ -0,0 +1,503 @@
+//! This module contains the [`Snowfall`] effect.
+
+#[cfg(feature = "config-impls")]
+pub use config::{FlakeStyle, SnowfallConfig};
+
+#[cfg(feature = "effect-impls")]
+pub use effect::Snowfall;
+
+use crate::effects::prelude::*;
+
+/// Contains the config for the [`Snowfall`] effect.
+#[cfg(feature = "config-impls")]
+mod config {
+    use super::*;
+
+    /// How to draw the falling snowflakes.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+    pub enum FlakeStyle {
+        /// Draw each flake as a small sphere which may light up several lights.
+        Sphere,
+
+        /// Light up only the single light nearest to each flake.
+        Splat,
+    }
+
+    /// The config for the [`Snowfall`] effect.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, BaseEffectConfig)]
+    #[serde(default)]
+    pub struct SnowfallConfig {
+        /// The average number of new snowflakes every second.
+        #[schema(range = 0.0..=50.0)]
+        pub flakes_per_second: f32,
+
+        /// The speed at which the flakes fall, in GIFT coordinate units per second.
+        #[schema(range = 0.05..=3.0)]
+        pub fall_speed: f32,
+
+        /// The speed of the wind, in GIFT coordinate units per second.
+        #[schema(range = 0.0..=1.0)]
+        pub wind_strength: f32,
+
+        /// The direction that the wind blows in, measured in degrees anti-clockwise from the
+        /// x-axis.
+        #[schema(range = 0.0..=360.0)]
+        pub wind_direction_degrees: f32,
+
+        /// How much each flake wanders about as it falls.
+        #[schema(range = 0.0..=2.0)]
+        pub jitter: f32,
+
+        /// How to draw the flakes.
+        pub flake_style: FlakeStyle,
+
+        /// The radius of each flake when drawn as a sphere.
+        #[schema(range = 0.0..=0.3)]
+        pub flake_radius: f32,
+
+        /// The maximum distance where colour drops to zero when flakes are drawn as spheres.
+        ///
+        /// See [`ww_frame::FrameObject::fadeoff`].
+        #[schema(range = 0.0..=0.3)]
+        pub fadeoff: f32,
+
+        /// How much the pile rises under each flake that settles, in GIFT coordinate units.
+        #[schema(range = 0.0..=0.1)]
+        pub settling_rate: f32,
+
+        /// How high the pile can get before it thaws, in GIFT coordinate units.
+        #[schema(range = 0.1..=2.0)]
+        pub max_pile_height: f32,
+
+        /// The number of seconds that it takes for the pile to thaw completely.
+        #[schema(range = 0.5..=30.0)]
+        pub thaw_seconds: f32,
+
+        /// The colour of the falling flakes.
+        pub flake_colour: RGBArray,
+
+        /// The colour of the lights buried in the pile of snow.
+        pub pile_colour: RGBArray,
+
+        /// The colour of every other light.
+        pub background_colour: RGBArray,
+    }
+
+    impl Default for SnowfallConfig {
+        fn default() -> Self {
+            Self {
+                flakes_per_second: 15.,
+                fall_speed: 0.5,
+                wind_strength: 0.1,
+                wind_direction_degrees: 0.,
+                jitter: 0.3,
+                flake_style: FlakeStyle::Sphere,
+                flake_radius: 0.05,
+                fadeoff: 0.08,
+                settling_rate: 0.02,
+                max_pile_height: 0.6,
+                thaw_seconds: 5.,
+                flake_colour: [255, 255, 255],
+                pile_colour: [160, 190, 255],
+                background_colour: [0, 0, 8],
+            }
+        }
+    }
+
+    impl EffectConfig for SnowfallConfig {
+        fn factory_presets() -> Vec<(&'static str, Self)> {
+            vec![(
+                "Blizzard",
+                Self {
+                    flakes_per_second: 45.,
+                    fall_speed: 1.2,
+                    wind_strength: 0.7,
+                    jitter: 0.8,
+                    ..Self::default()
+                },
+            )]
+        }
+
+        fn render_options_gui(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui) -> bool {
+            let schema = Self::schema();
+            let mut config_changed = false;
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("flakes_per_second", &mut self.flakes_per_second)
+                        .suffix("/s")
+                        .text("Snowfall density"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("fall_speed", &mut self.fall_speed)
+                        .suffix("units/s")
+                        .text("Fall speed"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("wind_strength", &mut self.wind_strength)
+                        .suffix("units/s")
+                        .text("Wind strength"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("wind_direction_degrees", &mut self.wind_direction_degrees)
+                        .suffix("°")
+                        .text("Wind direction"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(schema.slider("jitter", &mut self.jitter).text("Jitter"))
+                .changed();
+
+            ui.add_space(UI_SPACING);
+
+            ui.horizontal(|ui| {
+                ui.label("Flake style:");
+                config_changed |= ui
+                    .selectable_value(&mut self.flake_style, FlakeStyle::Sphere, "Sphere")
+                    .changed();
+                config_changed |= ui
+                    .selectable_value(&mut self.flake_style, FlakeStyle::Splat, "Nearest light")
+                    .changed();
+            });
+
+            if self.flake_style == FlakeStyle::Sphere {
+                config_changed |= ui
+                    .add(
+                        schema
+                            .slider("flake_radius", &mut self.flake_radius)
+                            .text("Flake radius"),
+                    )
+                    .changed();
+
+                config_changed |= ui
+                    .add(schema.slider("fadeoff", &mut self.fadeoff).text("Fadeoff"))
+                    .changed();
+            }
+
+            ui.add_space(UI_SPACING);
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("settling_rate", &mut self.settling_rate)
+                        .suffix("units/flake")
+                        .text("Settling rate"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("max_pile_height", &mut self.max_pile_height)
+                        .suffix("units")
+                        .text("Max pile height"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("thaw_seconds", &mut self.thaw_seconds)
+                        .suffix("s")
+                        .text("Thaw time"),
+                )
+                .changed();
+
+            ui.add_space(UI_SPACING);
+
+            config_changed |= colour_picker(ui, &mut self.flake_colour, "Flake colour").changed();
+            config_changed |= colour_picker(ui, &mut self.pile_colour, "Pile colour").changed();
+            config_changed |=
+                colour_picker(ui, &mut self.background_colour, "Background colour").changed();
+
+            config_changed
+        }
+    }
+}
+
+/// Contains the [`Snowfall`] effect itself.
+#[cfg(feature = "effect-impls")]
+mod effect {
+    use super::*;
+    use ww_gift_coords::COORDS;
+
+    /// The number of cells along each side of the grid that records the height of the pile.
+    const GRID_SIZE: usize = 12;
+
+    /// The proportion of its wandering velocity that a flake keeps every second.
+    const JITTER_DAMPING_PER_SECOND: f32 = 0.077;
+
+    /// The furthest that a flake can be from a light and still light it up as a splat.
+    const MAX_SPLAT_DISTANCE: f32 = 0.2;
+
+    /// A single snowflake.
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct Flake {
+        /// The current position of the flake.
+        position: Vec3,
+
+        /// The horizontal velocity of the flake on top of the wind, which wanders randomly.
+        drift: Vec3,
+    }
+
+    /// Drift snowflakes down the tree and pile them up at the bottom until the pile thaws.
+    #[derive(Clone, Debug, PartialEq, BaseEffect)]
+    #[effect(tags = ["snow", "winter", "calm"], unattended)]
+    pub struct Snowfall {
+        /// The RNG used to create flakes and make them wander.
+        rng: StdRng,
+
+        /// The flakes that are currently falling.
+        flakes: Vec<Flake>,
+
+        /// Half the width of the square that the flakes fall in, so that they cover every light.
+        extent: f32,
+
+        /// The height of the pile in each cell of a grid covering the bottom of the tree, stored
+        /// in row-major order.
+        pile: Vec<f32>,
+
+        /// Is the pile currently thawing?
+        thawing: bool,
+    }
+
+    impl Snowfall {
+        /// Get the index of the pile cell under the given point.
+        fn pile_cell(&self, point: Vec3) -> (usize, usize) {
+            let to_cell = |coord: f32| {
+                let t = (coord + self.extent) / (2. * self.extent);
+                ((t * GRID_SIZE as f32) as usize).min(GRID_SIZE - 1)
+            };
+
+            (to_cell(point.x), to_cell(point.y))
+        }
+
+        /// Get the height of the pile under the given point.
+        fn pile_height(&self, point: Vec3) -> f32 {
+            let (column, row) = self.pile_cell(point);
+            self.pile[row * GRID_SIZE + column]
+        }
+
+        /// Settle a flake onto the pile at the given point, spreading some of it onto the
+        /// neighbouring cells so that the pile stays smooth.
+        fn settle(&mut self, point: Vec3, settling_rate: f32) {
+            let (column, row) = self.pile_cell(point);
+
+            for (dc, dr, share) in [
+                (0, 0, 1.),
+                (-1, 0, 0.5),
+                (1, 0, 0.5),
+                (0, -1, 0.5),
+                (0, 1, 0.5),
+            ] {
+                let (Some(c), Some(r)) =
+                    (column.checked_add_signed(dc), row.checked_add_signed(dr))
+                else {
+                    continue;
+                };
+
+                if c < GRID_SIZE && r < GRID_SIZE {
+                    let height = &mut self.pile[r * GRID_SIZE + c];
+                    *height = settling_rate.mul_add(share, *height);
+                }
+            }
+        }
+
+        /// Create a new flake at a random position just above the top of the tree.
+        fn spawn_flake(&mut self) {
+            self.flakes.push(Flake {
+                position: Vec3::new(
+                    self.rng.random_range(-self.extent..=self.extent),
+                    self.rng.random_range(-self.extent..=self.extent),
+                    COORDS.max_z() + 0.1,
+                ),
+                drift: Vec3::ZERO,
+            });
+        }
+
+        /// Move all the flakes by the given time in seconds and settle any that have landed on
+        /// the pile.
+        fn update_flakes(&mut self, config: &SnowfallConfig, delta: f32) {
+            let wind = {
+                let angle = config.wind_direction_degrees.to_radians();
+                Vec3::new(angle.cos(), angle.sin(), 0.) * config.wind_strength
+            };
+            let width = 2. * self.extent;
+            let damping = JITTER_DAMPING_PER_SECOND.powf(delta);
+
+            for flake in &mut self.flakes {
+                flake.drift +=
+                    random_vector(&mut self.rng) * Vec3::new(1., 1., 0.) * config.jitter * delta;
+                flake.drift *= damping;
+
+                flake.position += (wind + flake.drift) * delta;
+                flake.position.z = config.fall_speed.mul_add(-delta, flake.position.z);
+
+                // Wrap the flakes around horizontally so that the wind doesn't blow them all away
+                flake.position.x = (flake.position.x + self.extent).rem_euclid(width) - self.extent;
+                flake.position.y = (flake.position.y + self.extent).rem_euclid(width) - self.extent;
+            }
+
+            let (settled, falling): (Vec<Flake>, Vec<Flake>) = self
+                .flakes
+                .iter()
+                .partition(|flake| flake.position.z <= self.pile_height(flake.position));
+            self.flakes = falling;
+
+            if !self.thawing {
+                for flake in settled {
+                    self.settle(flake.position, config.settling_rate);
+                }
+            }
+        }
+
+        /// Grow the pile, or thaw it by the given time in seconds, as appropriate.
+        fn update_pile(&mut self, config: &SnowfallConfig, delta: f32) {
+            if self.thawing {
+                let thaw = config.max_pile_height * delta / config.thaw_seconds.max(f32::EPSILON);
+                for height in &mut self.pile {
+                    *height = (*height - thaw).max(0.);
+                }
+
+                if self.pile.iter().all(|&height| height == 0.) {
+                    self.thawing = false;
+                    debug!("Snow pile has thawed");
+                }
+            } else if self
+                .pile
+                .iter()
+                .any(|&height| height >= config.max_pile_height)
+            {
+                self.thawing = true;
+                debug!("Snow pile has started thawing");
+            }
+        }
+
+        /// Work out how brightly each light is lit by the falling flakes, between 0 and 1.
+        fn flake_brightness(&self, config: &SnowfallConfig) -> Vec<f32> {
+            let coords = COORDS.coords();
+            let mut brightness = vec![0.; coords.len()];
+
+            match config.flake_style {
+                FlakeStyle::Sphere => {
+                    for (light, &point) in brightness.iter_mut().zip(coords) {
+                        let point = Vec3::from(point);
+                        for flake in &self.flakes {
+                            let dist = point.distance(flake.position) - config.flake_radius;
+                            let value = if dist <= 0. {
+                                1.
+                            } else if dist < config.fadeoff {
+                                1. - dist / config.fadeoff
+                            } else {
+                                0.
+                            };
+                            *light = f32::max(*light, value);
+                        }
+                    }
+                }
+                FlakeStyle::Splat => {
+                    for flake in &self.flakes {
+                        let nearest = coords
+                            .iter()
+                            .map(|&point| flake.position.distance(Vec3::from(point)))
+                            .enumerate()
+                            .min_by(|(_, a), (_, b)| a.total_cmp(b));
+
+                        if let Some((idx, dist)) = nearest
+                            && dist <= MAX_SPLAT_DISTANCE
+                        {
+                            brightness[idx] = 1.;
+                        }
+                    }
+                }
+            }
+
+            brightness
+        }
+    }
+
+    impl Effect for Snowfall {
+        fn from_config(_config: SnowfallConfig) -> Self {
+            let extent = COORDS
+                .coords()
+                .iter()
+                .fold(1_f32, |acc, &(x, y, _)| acc.max(x.abs()).max(y.abs()));
+
+            Self {
+                rng: rng!(),
+                flakes: vec![],
+                extent,
+                pile: vec![0.; GRID_SIZE * GRID_SIZE],
+                thawing: false,
+            }
+        }
+
+        fn next_frame(
+            &mut self,
+            config: &SnowfallConfig,
+            ctx: &FrameContext,
+        ) -> Option<(FrameType, Duration)> {
+            let delta = ctx.delta_secs();
+            let flake_brightness = self.flake_brightness(config);
+            let frame_data = COORDS
+                .coords()
+                .iter()
+                .zip(flake_brightness)
+                .map(|(&point, brightness)| {
+                    let point = Vec3::from(point);
+                    let base = if point.z < self.pile_height(point) {
+                        config.pile_colour
+                    } else {
+                        config.background_colour
+                    };
+
+                    lerp_colour(base, config.flake_colour, brightness)
+                })
+                .collect();
+
+            // Spawn new flakes, allowing for more than one per frame at high densities
+            let mut expected_flakes = config.flakes_per_second.max(0.) * delta;
+            while expected_flakes > 0. {
+                if self.rng.random_bool(expected_flakes.min(1.) as f64) {
+                    self.spawn_flake();
+                }
+                expected_flakes -= 1.;
+            }
+
+            self.update_flakes(config, delta);
+            self.update_pile(config, delta);
+
+            Some((FrameType::RawData(frame_data), ctx.target_frame_time()))
+        }
+
+        #[cfg(any(test, feature = "bench"))]
+        fn loops_to_test() -> Option<NonZeroU16> {
+            NonZeroU16::new(100)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot_effect;
+
+    #[test]
+    fn snowfall_test() {
+        snapshot_effect!(Snowfall);
+    }
+}
@@ -0,0 +1,308 @@
+//! This module contains the [`Twinkle`] effect.
+
+#[cfg(feature = "config-impls")]
+pub use config::{TwinkleColours, TwinkleConfig};
+
+#[cfg(feature = "effect-impls")]
+pub use effect::Twinkle;
+
+use crate::effects::prelude::*;
+
+/// Contains the config for the [`Twinkle`] effect.
+#[cfg(feature = "config-impls")]
+mod config {
+    use super::*;
+
+    /// Where the colours of the twinkles come from.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+    pub enum TwinkleColours {
+        /// Every twinkle is white with the configured colour temperature.
+        WarmWhite,
+
+        /// Every twinkle is a random colour from the palette.
+        Palette,
+    }
+
+    /// The config for the [`Twinkle`] effect.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, BaseEffectConfig)]
+    #[serde(default)]
+    pub struct TwinkleConfig {
+        /// The average fraction of the lights that are twinkling at any one time.
+        #[schema(range = 0.0..=0.9)]
+        pub density: f32,
+
+        /// How long a twinkle takes to reach full brightness.
+        #[schema(range = 0..=2000)]
+        pub attack_milliseconds: u32,
+
+        /// How long a twinkle takes to decay back to the base colour.
+        #[schema(range = 0..=5000)]
+        pub release_milliseconds: u32,
+
+        /// How much the attack and release times of each twinkle vary, as a fraction of the
+        /// configured times.
+        #[schema(range = 0.0..=1.0)]
+        pub timing_randomness: f32,
+
+        /// Where the colours of the twinkles come from.
+        pub colours: TwinkleColours,
+
+        /// The colour temperature of warm white twinkles, in Kelvin.
+        #[schema(range = 1500.0..=6500.0)]
+        pub colour_temperature: f32,
+
+        /// The colours that twinkles can be when using the palette.
+        pub palette: Vec<RGBArray>,
+
+        /// The dim colour of the lights when they're not twinkling.
+        pub base_colour: RGBArray,
+    }
+
+    impl Default for TwinkleConfig {
+        fn default() -> Self {
+            Self {
+                density: 0.15,
+                attack_milliseconds: 120,
+                release_milliseconds: 1200,
+                timing_randomness: 0.5,
+                colours: TwinkleColours::WarmWhite,
+                colour_temperature: 2700.,
+                palette: vec![
+                    [255, 40, 40],
+                    [40, 255, 60],
+                    [60, 80, 255],
+                    [255, 200, 40],
+                    [220, 60, 255],
+                ],
+                base_colour: [12, 7, 2],
+            }
+        }
+    }
+
+    impl EffectConfig for TwinkleConfig {
+        fn factory_presets() -> Vec<(&'static str, Self)> {
+            vec![(
+                "Multicolour",
+                Self {
+                    density: 0.25,
+                    colours: TwinkleColours::Palette,
+                    base_colour: [0, 0, 0],
+                    ..Self::default()
+                },
+            )]
+        }
+
+        fn render_options_gui(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui) -> bool {
+            let schema = Self::schema();
+            let mut config_changed = false;
+
+            config_changed |= ui
+                .add(schema.slider("density", &mut self.density).text("Density"))
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("attack_milliseconds", &mut self.attack_milliseconds)
+                        .suffix("ms")
+                        .text("Attack time"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("release_milliseconds", &mut self.release_milliseconds)
+                        .suffix("ms")
+                        .text("Release time"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("timing_randomness", &mut self.timing_randomness)
+                        .text("Timing randomness"),
+                )
+                .changed();
+
+            ui.add_space(UI_SPACING);
+
+            ui.horizontal(|ui| {
+                ui.label("Twinkle colours:");
+                config_changed |= ui
+                    .selectable_value(&mut self.colours, TwinkleColours::WarmWhite, "Warm white")
+                    .changed();
+                config_changed |= ui
+                    .selectable_value(&mut self.colours, TwinkleColours::Palette, "Palette")
+                    .changed();
+            });
+
+            match self.colours {
+                TwinkleColours::WarmWhite => {
+                    config_changed |= ui
+                        .add(
+                            schema
+                                .slider("colour_temperature", &mut self.colour_temperature)
+                                .suffix("K")
+                                .text("Colour temperature"),
+                        )
+                        .changed();
+                }
+                TwinkleColours::Palette => {
+                    config_changed |= palette_editor(ui, &mut self.palette, "Palette");
+                }
+            }
+
+            config_changed |= colour_picker(ui, &mut self.base_colour, "Base colour").changed();
+
+            config_changed
+        }
+    }
+}
+
+/// Contains the [`Twinkle`] effect itself.
+#[cfg(feature = "effect-impls")]
+mod effect {
+    use super::*;
+    use ww_gift_coords::COORDS;
+
+    /// A single light flaring up and decaying.
+    #[derive(Clone, Debug, PartialEq)]
+    struct Sparkle {
+        /// The colour of the light at full brightness.
+        colour: RGBArray,
+
+        /// How long the sparkle has been going, in seconds.
+        age: f32,
+
+        /// How long the sparkle takes to reach full brightness, in seconds.
+        attack: f32,
+
+        /// How long the sparkle takes to decay after reaching full brightness, in seconds.
+        release: f32,
+    }
+
+    impl Sparkle {
+        /// The brightness of the sparkle at its current age, between 0 and 1, or [`None`] if it
+        /// has finished.
+        fn brightness(&self) -> Option<f32> {
+            if self.age < self.attack {
+                Some(self.age / self.attack)
+            } else if self.age < self.attack + self.release {
+                // Real bulbs fade quickly at first and then linger, so decay quadratically
+                let remaining = 1. - (self.age - self.attack) / self.release;
+                Some(remaining * remaining)
+            } else {
+                None
+            }
+        }
+    }
+
+    /// Make random lights flare up and decay like twinkling fairy lights.
+    #[derive(Clone, Debug, PartialEq, BaseEffect)]
+    #[effect(tags = ["sparkle", "fairy lights", "calm"], unattended)]
+    pub struct Twinkle {
+        /// The RNG used to start sparkles and choose their colours and timings.
+        rng: StdRng,
+
+        /// The sparkle on each light, if it has one.
+        sparkles: Vec<Option<Sparkle>>,
+    }
+
+    impl Twinkle {
+        /// Create a new sparkle with a random colour and timing. Its attack and release each last
+        /// for at least the given frame time in seconds, so that they can be seen.
+        fn new_sparkle(&mut self, config: &TwinkleConfig, frame_time: f32) -> Sparkle {
+            let colour = match config.colours {
+                TwinkleColours::Palette if !config.palette.is_empty() => {
+                    random_palette_colour(&mut self.rng, &config.palette)
+                }
+                _ => colour_temperature(config.colour_temperature),
+            };
+
+            let randomness = config.timing_randomness.clamp(0., 1.);
+            let mut vary = |milliseconds: u32| {
+                let factor = self.rng.random_range(1. - randomness..=1. + randomness);
+                (milliseconds as f32 / 1000. * factor).max(frame_time)
+            };
+
+            Sparkle {
+                colour,
+                age: 0.,
+                attack: vary(config.attack_milliseconds),
+                release: vary(config.release_milliseconds),
+            }
+        }
+    }
+
+    impl Effect for Twinkle {
+        fn from_config(_config: TwinkleConfig) -> Self {
+            Self {
+                rng: rng!(),
+                sparkles: vec![None; COORDS.lights_num()],
+            }
+        }
+
+        fn next_frame(
+            &mut self,
+            config: &TwinkleConfig,
+            ctx: &FrameContext,
+        ) -> Option<(FrameType, Duration)> {
+            let delta = ctx.delta_secs();
+            let frame_time = ctx.target_frame_time().as_secs_f32();
+
+            // For the average fraction of twinkling lights to be `density`, each idle light needs
+            // to start twinkling at a rate of `density / ((1 - density) * average_duration)`
+            let density = config.density.clamp(0., 0.99);
+            let average_duration =
+                ((config.attack_milliseconds + config.release_milliseconds) as f32 / 1000.)
+                    .max(frame_time);
+            let start_probability =
+                (density / ((1. - density) * average_duration) * delta).clamp(0., 1.);
+
+            for idx in 0..self.sparkles.len() {
+                if self.sparkles[idx].is_none() && self.rng.random_bool(start_probability as f64) {
+                    self.sparkles[idx] = Some(self.new_sparkle(config, frame_time));
+                }
+            }
+
+            let frame_data = self
+                .sparkles
+                .iter_mut()
+                .map(|slot| {
+                    let Some(sparkle) = slot else {
+                        return config.base_colour;
+                    };
+
+                    if let Some(brightness) = sparkle.brightness() {
+                        let colour = lerp_colour(config.base_colour, sparkle.colour, brightness);
+                        sparkle.age += delta;
+                        colour
+                    } else {
+                        *slot = None;
+                        config.base_colour
+                    }
+                })
+                .collect();
+
+            Some((FrameType::RawData(frame_data), ctx.target_frame_time()))
+        }
+
+        #[cfg(any(test, feature = "bench"))]
+        fn loops_to_test() -> Option<NonZeroU16> {
+            NonZeroU16::new(100)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot_effect;
+
+    #[test]
+    fn twinkle_test() {
+        snapshot_effect!(Twinkle);
+    }
+}
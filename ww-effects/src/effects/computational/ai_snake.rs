@@ -15,17 +15,22 @@ mod config {
 
     /// The config for the [`AiSnake`] effect.
     #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, BaseEffectConfig)]
+    #[serde(default)]
     pub struct AiSnakeConfig {
         /// How many milliseconds we wait between rendering steps.
+        #[schema(range = 0..=5000)]
         pub milliseconds_per_step: u64,
 
         /// The number of evenly spaced lattice points across the diameter of the bottom of the tree.
+        #[schema(range = 0..=10)]
         pub lattice_points_across_diameter: u8,
 
         /// The thickness of the snake and the apple.
+        #[schema(range = 0.0..=0.5)]
         pub thickness: f32,
 
         /// The fadeoff of the objects in the frame.
+        #[schema(range = 0.0..=0.5)]
         pub fadeoff: f32,
 
         /// Should we allow the snake to move diagonally?
@@ -57,12 +62,34 @@ mod config {
     }
 
     impl EffectConfig for AiSnakeConfig {
+        fn factory_presets() -> Vec<(&'static str, Self)> {
+            vec![
+                (
+                    "Speedy",
+                    Self {
+                        milliseconds_per_step: 80,
+                        ..Self::default()
+                    },
+                ),
+                (
+                    "Classic",
+                    Self {
+                        lattice_points_across_diameter: 6,
+                        allow_diagonal_movement: false,
+                        ..Self::default()
+                    },
+                ),
+            ]
+        }
+
         fn render_options_gui(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui) -> bool {
+            let schema = Self::schema();
             let mut config_changed = false;
 
             config_changed |= ui
                 .add(
-                    egui::Slider::new(&mut self.milliseconds_per_step, 0..=5000)
+                    schema
+                        .slider("milliseconds_per_step", &mut self.milliseconds_per_step)
                         .text("Milliseconds per step")
                         .suffix("ms"),
                 )
@@ -70,17 +97,25 @@ mod config {
 
             config_changed |= ui
                 .add(
-                    egui::Slider::new(&mut self.lattice_points_across_diameter, 0..=10)
+                    schema
+                        .slider(
+                            "lattice_points_across_diameter",
+                            &mut self.lattice_points_across_diameter,
+                        )
                         .text("Lattice points across diameter"),
                 )
                 .changed();
 
             config_changed |= ui
-                .add(egui::Slider::new(&mut self.thickness, 0.0..=0.5).text("Thickness"))
+                .add(
+                    schema
+                        .slider("thickness", &mut self.thickness)
+                        .text("Thickness"),
+                )
                 .changed();
 
             config_changed |= ui
-                .add(egui::Slider::new(&mut self.fadeoff, 0.0..=0.5).text("Fadeoff"))
+                .add(schema.slider("fadeoff", &mut self.fadeoff).text("Fadeoff"))
                 .changed();
 
             config_changed |= ui
@@ -105,56 +140,9 @@ mod config {
 #[cfg(feature = "effect-impls")]
 mod effect {
     use super::*;
-    use ordered_float::NotNan;
-    use rand::Rng;
+    use crate::effects::utils::lattice::{Coord, Lattice};
     use std::{collections::VecDeque, fmt, iter};
-    use ww_gift_coords::{COORDS, GIFTCoords};
-
-    /// A coordinate in snake space.
-    #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
-    struct Coord(i8, i8, i8);
-
-    impl Coord {
-        /// Check if this coordinate is orthogonally adjacent to another one.
-        fn is_orthogonal(self, other: Self) -> bool {
-            let diffs = (
-                self.0.abs_diff(other.0),
-                self.1.abs_diff(other.1),
-                self.2.abs_diff(other.2),
-            );
-            diffs == (1, 0, 0) || diffs == (0, 1, 0) || diffs == (0, 0, 1)
-        }
-
-        /// Check if this coordinate is diagonally adjacent to another one.
-        fn is_diagonal(self, other: Self) -> bool {
-            let diffs = (
-                self.0.abs_diff(other.0),
-                self.1.abs_diff(other.1),
-                self.2.abs_diff(other.2),
-            );
-            diffs == (1, 1, 0) || diffs == (1, 0, 1) || diffs == (0, 1, 1) || diffs == (1, 1, 1)
-        }
-
-        /// Convert the coordinate to a GIFT coordinate.
-        fn to_gift(self, cell_width: f32) -> (f32, f32, f32) {
-            (
-                self.0 as f32 * cell_width,
-                self.1 as f32 * cell_width,
-                self.2 as f32 * cell_width,
-            )
-        }
-
-        /// Calculate the Euclidean distance between this coordinate and another one.
-        fn distance(self, other: Self) -> NotNan<f32> {
-            let (dx, dy, dz) = (
-                self.0 as f32 - other.0 as f32,
-                self.1 as f32 - other.1 as f32,
-                self.2 as f32 - other.2 as f32,
-            );
-            NotNan::new(f32::sqrt(dx.mul_add(dx, dy.mul_add(dy, dz * dz))))
-                .expect_or_log("Euclidean distance of snake points should never be NaN")
-        }
-    }
+    use ww_gift_coords::COORDS;
 
     /// A snake that can move through snake space.
     #[derive(Clone, PartialEq)]
@@ -304,152 +292,48 @@ mod effect {
         }
     }
 
-    /// A collection of valid lattice points.
-    #[derive(Clone, Debug, PartialEq)]
-    struct Lattice {
-        /// The points in the lattice.
-        points: Box<[Coord]>,
-
-        /// The width of each cell in GIFT coord units.
-        cell_width: f32,
-    }
-
-    impl Lattice {
-        /// Create a new lattice from the given coordinates and settings.
-        #[instrument(skip_all)]
-        fn new(coords: &GIFTCoords, config: &AiSnakeConfig) -> Self {
-            let bound = (config.lattice_points_across_diameter / 2) as i8;
-            let range = -bound..=bound;
-            let square: Box<[_]> = range
-                .clone()
-                .flat_map(|x| range.clone().map(move |y| (x, y)))
-                .collect();
-
-            let max_vertical: i8 = (coords.max_z()
-                / (2.0 / (config.lattice_points_across_diameter as f32)))
-                .floor() as i8;
-            let z_range = 0..=max_vertical;
-
-            let cell_width = 2.0 / config.lattice_points_across_diameter as f32;
-
-            let points: Box<[Coord]> = z_range
-                .into_iter()
-                .flat_map(|z| square.iter().map(move |(x, y)| Coord(*x, *y, z)))
-                .filter(|coord| {
-                    let (px, py, pz) = coord.to_gift(cell_width);
-                    coords.coords().iter().any(|&(lx, ly, lz)| {
-                        let dx = px - lx;
-                        let dy = py - ly;
-                        let dz = pz - lz;
-
-                        let dist = f32::sqrt(dx.mul_add(dx, dy.mul_add(dy, dz * dz)));
-
-                        dist <= cell_width / 2.0
-                    })
-                })
-                .collect();
-
-            info!(num_points = points.len(), "Created lattice for AI snake");
-
-            Self {
-                points,
-                cell_width: 2.0 / config.lattice_points_across_diameter as f32,
-            }
-        }
-
-        /// Get a random point from the lattice.
-        fn random_point(&self, rng: &mut StdRng) -> Option<Coord> {
-            let idx = rng.random_range(0..self.points.len());
-            self.points.get(idx).copied()
-        }
-
-        /// Find the shortest path between two points on the lattice
-        fn shortest_path(
-            &self,
-            start: Coord,
-            end: Coord,
-            avoid: &[Coord],
-            allow_diagonals: bool,
-        ) -> Option<Vec<Coord>> {
-            debug_assert!(
-                self.points.contains(&start),
-                "Start point must be on lattice"
-            );
-            debug_assert!(self.points.contains(&end), "End point must be on lattice");
-
-            let path = pathfinding::directed::dijkstra::dijkstra(
-                &start,
-                |&Coord(x, y, z)| {
-                    [
-                        Coord(x + 1, y, z),
-                        Coord(x - 1, y, z),
-                        Coord(x, y + 1, z),
-                        Coord(x, y - 1, z),
-                        Coord(x, y, z + 1),
-                        Coord(x, y, z - 1),
-                    ]
-                    .into_iter()
-                    .chain(if allow_diagonals {
-                        vec![
-                            Coord(x + 1, y + 1, z),
-                            Coord(x + 1, y, z + 1),
-                            Coord(x, y + 1, z + 1),
-                            Coord(x + 1, y - 1, z),
-                            Coord(x + 1, y, z - 1),
-                            Coord(x, y + 1, z - 1),
-                            Coord(x - 1, y + 1, z),
-                            Coord(x - 1, y, z + 1),
-                            Coord(x, y - 1, z + 1),
-                            Coord(x - 1, y - 1, z),
-                            Coord(x - 1, y, z - 1),
-                            Coord(x, y - 1, z - 1),
-                            Coord(x + 1, y + 1, z + 1),
-                            Coord(x + 1, y + 1, z - 1),
-                            Coord(x + 1, y - 1, z + 1),
-                            Coord(x + 1, y - 1, z - 1),
-                            Coord(x - 1, y + 1, z + 1),
-                            Coord(x - 1, y + 1, z - 1),
-                            Coord(x - 1, y - 1, z + 1),
-                            Coord(x - 1, y - 1, z - 1),
-                        ]
-                        .into_iter()
-                    } else {
-                        vec![].into_iter()
-                    })
-                    .filter(|coord| self.points.contains(coord) && !avoid.contains(coord))
-                    .map(move |coord| (coord, Coord(x, y, z).distance(coord)))
-                },
-                |pos| pos == &end,
-            )
-            .map(|(path, _dist)| path);
-            trace!(?start, ?end, ?path);
-            path
-        }
-    }
-
     /// Create an AI snake that moves through the tree to collect the apple.
     #[derive(Clone, Debug, PartialEq, BaseEffect)]
+    #[effect(tags = ["game", "pathfinding", "snake"], unattended)]
     pub struct AiSnake {
         /// The RNG to use for randomness.
         rng: StdRng,
 
         /// The snake itself.
         snake: Snake,
+
+        /// Keeps the snake moving at the right speed when frames are late.
+        schedule: StepSchedule,
     }
 
     impl Effect for AiSnake {
         fn from_config(config: AiSnakeConfig) -> Self {
             let mut rng = rng!();
-            let snake = Snake::new(Lattice::new(&COORDS, &config), &mut rng);
+            let snake = Snake::new(
+                Lattice::new(&COORDS, config.lattice_points_across_diameter),
+                &mut rng,
+            );
 
-            Self { rng, snake }
+            Self {
+                rng,
+                snake,
+                schedule: StepSchedule::default(),
+            }
         }
 
-        fn next_frame(&mut self, config: &AiSnakeConfig) -> Option<(FrameType, Duration)> {
-            match self
-                .snake
-                .advance(&mut self.rng, config.allow_diagonal_movement)
-            {
+        fn next_frame(
+            &mut self,
+            config: &AiSnakeConfig,
+            ctx: &FrameContext,
+        ) -> Option<(FrameType, Duration)> {
+            let step = Duration::from_millis(config.milliseconds_per_step);
+            self.schedule.update(ctx);
+            let steps = 1 + self.schedule.catch_up_steps(step);
+
+            match (0..steps).try_for_each(|_| {
+                self.snake
+                    .advance(&mut self.rng, config.allow_diagonal_movement)
+            }) {
                 Ok(()) => Some((
                     FrameType::Frame3D(Frame3D::new(
                         vec![
@@ -478,7 +362,7 @@ mod effect {
                         ],
                         true,
                     )),
-                    Duration::from_millis(config.milliseconds_per_step),
+                    self.schedule.request(step),
                 )),
                 Err(error @ (SnakeError::PathfindingFail | SnakeError::PlaceAppleFail)) => {
                     trace!(?error, "Failure in Snake::advance");
@@ -0,0 +1,442 @@
+//! This module provides the [`Boids`] effect.
+
+#[cfg(feature = "config-impls")]
+pub use config::BoidsConfig;
+
+#[cfg(feature = "effect-impls")]
+pub use effect::Boids;
+
+use crate::effects::prelude::*;
+
+/// Contains the config for the [`Boids`] effect.
+#[cfg(feature = "config-impls")]
+mod config {
+    use super::*;
+
+    /// The config for the [`Boids`] effect.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, BaseEffectConfig)]
+    #[serde(default)]
+    pub struct BoidsConfig {
+        /// The number of boids in the flock.
+        #[schema(range = 1..=50)]
+        pub boid_count: u8,
+
+        /// The slowest that a boid can fly, in GIFT coordinate units per second.
+        #[schema(range = 0.0..=5.0)]
+        pub min_speed: f32,
+
+        /// The fastest that a boid can fly, in GIFT coordinate units per second.
+        #[schema(range = 0.1..=5.0)]
+        pub max_speed: f32,
+
+        /// How far away a boid can see the rest of the flock.
+        #[schema(range = 0.0..=3.0)]
+        pub perception_radius: f32,
+
+        /// How close another boid has to be before this boid tries to move away from it.
+        #[schema(range = 0.0..=3.0)]
+        pub separation_radius: f32,
+
+        /// How strongly each boid steers away from boids that are too close.
+        #[schema(range = 0.0..=5.0)]
+        pub separation_weight: f32,
+
+        /// How strongly each boid steers to match the heading of its neighbours.
+        #[schema(range = 0.0..=5.0)]
+        pub alignment_weight: f32,
+
+        /// How strongly each boid steers towards the centre of its neighbours.
+        #[schema(range = 0.0..=5.0)]
+        pub cohesion_weight: f32,
+
+        /// How strongly each boid steers away from the edges of the bounding box.
+        #[schema(range = 0.0..=20.0)]
+        pub edge_avoidance_weight: f32,
+
+        /// The number of past positions in each boid's trail. If this is less than 2, then the
+        /// boids are drawn as spheres instead.
+        #[schema(range = 0..=20)]
+        pub trail_length: u8,
+
+        /// The radius of each boid and the thickness of its trail.
+        #[schema(range = 0.0..=0.5)]
+        pub radius: f32,
+
+        /// The maximum distance where colour drops to zero.
+        ///
+        /// See [`ww_frame::FrameObject::fadeoff`].
+        #[schema(range = 0.0..=0.5)]
+        pub fadeoff: f32,
+
+        /// The colours of the boids. Each boid takes the next colour in the list.
+        pub palette: Vec<RGBArray>,
+
+        /// The colour at the very end of each trail.
+        pub tail_colour: RGBArray,
+    }
+
+    impl Default for BoidsConfig {
+        fn default() -> Self {
+            Self {
+                boid_count: 12,
+                min_speed: 0.5,
+                max_speed: 1.2,
+                perception_radius: 0.8,
+                separation_radius: 0.3,
+                separation_weight: 1.5,
+                alignment_weight: 1.,
+                cohesion_weight: 1.,
+                edge_avoidance_weight: 8.,
+                trail_length: 6,
+                radius: 0.15,
+                fadeoff: 0.15,
+                palette: vec![[0, 200, 255], [120, 80, 255], [0, 255, 160]],
+                tail_colour: [0, 10, 30],
+            }
+        }
+    }
+
+    impl EffectConfig for BoidsConfig {
+        fn factory_presets() -> Vec<(&'static str, Self)> {
+            vec![
+                (
+                    "Swarm",
+                    Self {
+                        boid_count: 40,
+                        trail_length: 3,
+                        radius: 0.08,
+                        ..Self::default()
+                    },
+                ),
+                (
+                    "Goldfish",
+                    Self {
+                        boid_count: 8,
+                        max_speed: 0.8,
+                        alignment_weight: 3.,
+                        cohesion_weight: 2.,
+                        palette: vec![[255, 120, 0], [255, 200, 40], [255, 60, 20]],
+                        tail_colour: [30, 8, 0],
+                        ..Self::default()
+                    },
+                ),
+            ]
+        }
+
+        fn render_options_gui(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui) -> bool {
+            let schema = Self::schema();
+            let mut config_changed = false;
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("boid_count", &mut self.boid_count)
+                        .text("Number of boids (requires restart)"),
+                )
+                .changed();
+
+            // The speed sliders limit each other, so their ranges can't come from the schema
+            config_changed |= ui
+                .add(
+                    egui::Slider::new(&mut self.min_speed, 0.0..=self.max_speed)
+                        .suffix("units/s")
+                        .text("Min speed"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    egui::Slider::new(&mut self.max_speed, self.min_speed..=5.0)
+                        .suffix("units/s")
+                        .text("Max speed"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("perception_radius", &mut self.perception_radius)
+                        .text("Perception radius"),
+                )
+                .changed();
+
+            // The separation radius is limited by the perception radius rather than the schema
+            config_changed |= ui
+                .add(
+                    egui::Slider::new(&mut self.separation_radius, 0.0..=self.perception_radius)
+                        .text("Separation radius"),
+                )
+                .changed();
+
+            ui.add_space(UI_SPACING);
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("separation_weight", &mut self.separation_weight)
+                        .text("Separation weight"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("alignment_weight", &mut self.alignment_weight)
+                        .text("Alignment weight"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("cohesion_weight", &mut self.cohesion_weight)
+                        .text("Cohesion weight"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("edge_avoidance_weight", &mut self.edge_avoidance_weight)
+                        .text("Edge avoidance weight"),
+                )
+                .changed();
+
+            ui.add_space(UI_SPACING);
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("trail_length", &mut self.trail_length)
+                        .text("Trail length"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(schema.slider("radius", &mut self.radius).text("Radius"))
+                .changed();
+
+            config_changed |= ui
+                .add(schema.slider("fadeoff", &mut self.fadeoff).text("Fadeoff"))
+                .changed();
+
+            ui.add_space(UI_SPACING);
+
+            config_changed |= colour_picker(ui, &mut self.tail_colour, "Tail colour").changed();
+            config_changed |= palette_editor(ui, &mut self.palette, "Boid colours");
+
+            config_changed
+        }
+    }
+}
+
+/// Contains the [`Boids`] effect itself.
+#[cfg(feature = "effect-impls")]
+mod effect {
+    use super::*;
+    use std::collections::VecDeque;
+    use ww_gift_coords::COORDS;
+
+    /// The time between each point recorded in a boid's trail, in seconds.
+    const SECONDS_PER_TRAIL_POINT: f32 = 0.1;
+
+    /// How many seconds ahead a boid looks when checking if it's about to leave the bounding box.
+    const LOOKAHEAD_SECONDS: f32 = 0.5;
+
+    /// How much narrower the space that the boids fly in is at the top of the tree than at the
+    /// bottom, as a proportion of the width of the bounding box.
+    const TAPER: f32 = 0.7;
+
+    /// A single member of the flock.
+    #[derive(Clone, Debug, PartialEq)]
+    struct Boid {
+        /// The current position of the boid.
+        position: Vec3,
+
+        /// The current velocity of the boid, in GIFT coordinate units per second.
+        velocity: Vec3,
+
+        /// The previous positions of the boid, oldest first.
+        trail: VecDeque<Vec3>,
+    }
+
+    /// Simulate a flock of boids which swirl around inside the bounding box by following the
+    /// classic separation, alignment, and cohesion rules.
+    #[derive(Clone, Debug, PartialEq, BaseEffect)]
+    #[effect(tags = ["flocking", "simulation", "particles"], unattended)]
+    pub struct Boids {
+        /// The members of the flock.
+        boids: Vec<Boid>,
+
+        /// The time since we last added a point to the trails, in seconds.
+        time_since_trail_point: f32,
+    }
+
+    impl Boids {
+        /// Work out the acceleration of every boid from the rules.
+        fn accelerations(&self, config: &BoidsConfig) -> Vec<Vec3> {
+            let center = Vec3::from(COORDS.center());
+
+            self.boids
+                .iter()
+                .enumerate()
+                .map(|(idx, boid)| {
+                    let mut separation = Vec3::ZERO;
+                    let mut total_velocity = Vec3::ZERO;
+                    let mut total_position = Vec3::ZERO;
+                    let mut neighbours = 0;
+
+                    for (other_idx, other) in self.boids.iter().enumerate() {
+                        let dist = boid.position.distance(other.position);
+                        if other_idx == idx || dist > config.perception_radius {
+                            continue;
+                        }
+
+                        if dist < config.separation_radius && dist > 0. {
+                            // Closer boids push harder
+                            let away = boid.position - other.position;
+                            separation += away / dist.powi(2);
+                        }
+                        total_velocity += other.velocity;
+                        total_position += other.position;
+                        neighbours += 1;
+                    }
+
+                    let (alignment, cohesion) = if neighbours > 0 {
+                        let neighbours = neighbours as f32;
+                        (
+                            total_velocity / neighbours - boid.velocity,
+                            total_position / neighbours - boid.position,
+                        )
+                    } else {
+                        (Vec3::ZERO, Vec3::ZERO)
+                    };
+
+                    // Steer back towards the middle harder the further out we're about to go. We
+                    // stretch the point sideways as it gets higher so that the space the boids
+                    // fly in narrows like the tree does
+                    let lookahead = boid.position + boid.velocity * LOOKAHEAD_SECONDS;
+                    let taper = TAPER.mul_add(-(lookahead.z / COORDS.max_z()).clamp(0., 1.), 1.);
+                    let stretched = lookahead * Vec3::new(1. / taper, 1. / taper, 1.);
+                    let edge_avoidance = (center - boid.position).normalize_or_zero()
+                        * COORDS.distance_from_bounding_box(stretched.into());
+
+                    separation * config.separation_weight
+                        + alignment * config.alignment_weight
+                        + cohesion * config.cohesion_weight
+                        + edge_avoidance * config.edge_avoidance_weight
+                })
+                .collect()
+        }
+
+        /// Create the frame objects for all the boids.
+        fn frame_objects(&self, config: &BoidsConfig) -> Vec<FrameObject> {
+            self.boids
+                .iter()
+                .enumerate()
+                .map(|(idx, boid)| {
+                    let colour = palette_colour(&config.palette, idx);
+
+                    if config.trail_length < 2 || boid.trail.is_empty() {
+                        FrameObject {
+                            object: Object::Sphere {
+                                center: boid.position,
+                                radius: config.radius,
+                            },
+                            colour,
+                            fadeoff: config.fadeoff,
+                        }
+                    } else {
+                        FrameObject {
+                            object: Object::CatmullRomSpline {
+                                points: boid.trail.iter().copied().chain([boid.position]).collect(),
+                                threshold: config.radius,
+                                start_colour: config.tail_colour,
+                                end_colour: colour,
+                            },
+                            colour,
+                            fadeoff: config.fadeoff,
+                        }
+                    }
+                })
+                .collect()
+        }
+    }
+
+    impl Effect for Boids {
+        fn from_config(config: BoidsConfig) -> Self {
+            let mut rng = rng!();
+            let center = Vec3::from(COORDS.center());
+
+            let boids = (0..config.boid_count)
+                .map(|_| {
+                    let position = center
+                        + random_vector(&mut rng)
+                            * Vec3::new(0.5, 0.5, COORDS.max_z() / 4.)
+                            * rng.random_range(0.0..1.0);
+                    let velocity = random_vector(&mut rng) * config.min_speed.max(0.1);
+
+                    Boid {
+                        position,
+                        velocity,
+                        trail: VecDeque::new(),
+                    }
+                })
+                .collect();
+
+            Self {
+                boids,
+                time_since_trail_point: 0.,
+            }
+        }
+
+        fn next_frame(
+            &mut self,
+            config: &BoidsConfig,
+            ctx: &FrameContext,
+        ) -> Option<(FrameType, Duration)> {
+            let delta = ctx.delta_secs();
+            let frame = FrameType::Frame3D(Frame3D::new(self.frame_objects(config), false));
+
+            let accelerations = self.accelerations(config);
+            self.time_since_trail_point += delta;
+            let record_trail = self.time_since_trail_point >= SECONDS_PER_TRAIL_POINT;
+            if record_trail {
+                self.time_since_trail_point %= SECONDS_PER_TRAIL_POINT;
+            }
+
+            for (boid, acceleration) in self.boids.iter_mut().zip(accelerations) {
+                if record_trail {
+                    boid.trail.push_back(boid.position);
+                }
+                while boid.trail.len() > (config.trail_length as usize).saturating_sub(1) {
+                    boid.trail.pop_front();
+                }
+
+                boid.velocity += acceleration * delta;
+                boid.velocity = boid
+                    .velocity
+                    .clamp_length(config.min_speed.min(config.max_speed), config.max_speed);
+                boid.position += boid.velocity * delta;
+            }
+
+            Some((frame, ctx.target_frame_time()))
+        }
+
+        #[cfg(any(test, feature = "bench"))]
+        fn loops_to_test() -> Option<NonZeroU16> {
+            NonZeroU16::new(100)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot_effect;
+
+    #[test]
+    fn boids_test() {
+        snapshot_effect!(Boids);
+    }
+}
@@ -0,0 +1,374 @@
+//! This module contains the [`BouncingBalls`] effect.
+
+#[cfg(feature = "config-impls")]
+pub use config::BouncingBallsConfig;
+
+#[cfg(feature = "effect-impls")]
+pub use effect::BouncingBalls;
+
+use crate::effects::prelude::*;
+
+/// Contains the config for the [`BouncingBalls`] effect.
+#[cfg(feature = "config-impls")]
+mod config {
+    use super::*;
+
+    /// The config for the [`BouncingBalls`] effect.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, BaseEffectConfig)]
+    #[serde(default)]
+    pub struct BouncingBallsConfig {
+        /// The number of balls.
+        #[schema(range = 1..=15)]
+        pub ball_count: u8,
+
+        /// The radius of each ball.
+        #[schema(range = 0.05..=0.5)]
+        pub radius: f32,
+
+        /// The maximum distance where colour drops to zero.
+        ///
+        /// See [`ww_frame::FrameObject::fadeoff`].
+        #[schema(range = 0.0..=0.5)]
+        pub fadeoff: f32,
+
+        /// The acceleration due to gravity, in GIFT coordinate units per second squared.
+        #[schema(range = 0.0..=10.0)]
+        pub gravity: f32,
+
+        /// The fraction of a ball's speed that it keeps after bouncing off the walls or the floor.
+        #[schema(range = 0.0..=1.0)]
+        pub restitution: f32,
+
+        /// The colours of the balls. A ball changes to a random colour from this list whenever
+        /// it hits something.
+        pub palette: Vec<RGBArray>,
+    }
+
+    impl Default for BouncingBallsConfig {
+        fn default() -> Self {
+            Self {
+                ball_count: 5,
+                radius: 0.25,
+                fadeoff: 0.1,
+                gravity: 3.,
+                restitution: 0.85,
+                palette: vec![
+                    [255, 0, 0],
+                    [255, 160, 0],
+                    [255, 255, 0],
+                    [0, 255, 0],
+                    [0, 160, 255],
+                    [160, 0, 255],
+                ],
+            }
+        }
+    }
+
+    impl EffectConfig for BouncingBallsConfig {
+        fn factory_presets() -> Vec<(&'static str, Self)> {
+            vec![
+                (
+                    "Moon",
+                    Self {
+                        gravity: 0.6,
+                        restitution: 0.95,
+                        ..Self::default()
+                    },
+                ),
+                (
+                    "Bowling ball",
+                    Self {
+                        ball_count: 1,
+                        radius: 0.45,
+                        restitution: 0.5,
+                        palette: vec![[40, 40, 255]],
+                        ..Self::default()
+                    },
+                ),
+            ]
+        }
+
+        fn render_options_gui(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui) -> bool {
+            let schema = Self::schema();
+            let mut config_changed = false;
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("ball_count", &mut self.ball_count)
+                        .text("Number of balls"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(schema.slider("radius", &mut self.radius).text("Radius"))
+                .changed();
+
+            config_changed |= ui
+                .add(schema.slider("fadeoff", &mut self.fadeoff).text("Fadeoff"))
+                .changed();
+
+            ui.add_space(UI_SPACING);
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("gravity", &mut self.gravity)
+                        .suffix("units/s²")
+                        .text("Gravity"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("restitution", &mut self.restitution)
+                        .text("Restitution"),
+                )
+                .changed();
+
+            ui.add_space(UI_SPACING);
+
+            config_changed |= palette_editor(ui, &mut self.palette, "Ball colours");
+
+            config_changed
+        }
+    }
+}
+
+/// Contains the [`BouncingBalls`] effect itself.
+#[cfg(feature = "effect-impls")]
+mod effect {
+    use super::*;
+    use ww_gift_coords::COORDS;
+
+    /// The longest time that a single physics step can simulate, in seconds. Each frame is split
+    /// into steps no longer than this, to stop fast balls from tunnelling through each other.
+    const MAX_STEP_SECONDS: f32 = 0.005;
+
+    /// The radius of the base of the cone that the balls bounce around in.
+    const BASE_RADIUS: f32 = 1.;
+
+    /// The slowest that a ball can hit something and still change colour, in GIFT coordinate
+    /// units per second. This stops balls that are rolling along the floor from flickering.
+    const MIN_IMPACT_SPEED: f32 = 0.3;
+
+    /// A ball touching the floor which doesn't have enough energy to bounce higher than this is
+    /// thrown back up, so that the balls don't end up in a jiggling heap on the floor.
+    const MIN_BOUNCE_HEIGHT: f32 = 0.3;
+
+    /// A single ball.
+    #[derive(Clone, Debug, PartialEq)]
+    struct Ball {
+        /// The position of the center of the ball.
+        position: Vec3,
+
+        /// The velocity of the ball, in GIFT coordinate units per second.
+        velocity: Vec3,
+
+        /// The index of the ball's current colour in the palette.
+        colour_idx: usize,
+    }
+
+    /// Drop some balls into a cone the shape of the tree and let them bounce off the walls, the
+    /// floor, and each other.
+    #[derive(Clone, Debug, PartialEq, BaseEffect)]
+    #[effect(tags = ["physics", "simulation", "balls"], unattended)]
+    pub struct BouncingBalls {
+        /// The RNG used to place the balls and choose their colours.
+        rng: StdRng,
+
+        /// The balls themselves.
+        balls: Vec<Ball>,
+    }
+
+    impl BouncingBalls {
+        /// Create a new ball somewhere in the top half of the cone, moving sideways.
+        fn new_ball(&mut self, config: &BouncingBallsConfig) -> Ball {
+            let height = COORDS.max_z();
+            let z = self.rng.random_range(0.3..0.6) * height;
+            let max_radius = BASE_RADIUS.mul_add(1. - z / height, -config.radius).max(0.);
+            let horizontal = random_vector(&mut self.rng) * Vec3::new(1., 1., 0.);
+
+            Ball {
+                position: Vec3::new(0., 0., z)
+                    + horizontal.normalize_or_zero() * self.rng.random_range(0.0..=max_radius),
+                velocity: horizontal * self.rng.random_range(0.5..1.5),
+                colour_idx: self.rng.random_range(0..config.palette.len().max(1)),
+            }
+        }
+
+        /// Change a ball to a different random colour.
+        fn change_colour(rng: &mut StdRng, ball: &mut Ball, palette_len: usize) {
+            if palette_len > 1 {
+                // Skip over the current colour so that the change is always visible
+                ball.colour_idx =
+                    (ball.colour_idx + rng.random_range(1..palette_len)) % palette_len;
+            }
+        }
+
+        /// Bounce a ball off the floor and the sloping wall of the cone. Return true if it hit
+        /// either of them hard enough to count as an impact.
+        fn collide_with_hull(ball: &mut Ball, config: &BouncingBallsConfig) -> bool {
+            let height = COORDS.max_z();
+            let mut impact = false;
+
+            if ball.position.z < config.radius {
+                ball.position.z = config.radius;
+                if ball.velocity.z < 0. {
+                    impact |= -ball.velocity.z > MIN_IMPACT_SPEED;
+                    ball.velocity.z *= -config.restitution;
+                }
+            }
+
+            // In the plane through the axis of the cone and the ball, the wall is the line from
+            // (BASE_RADIUS, 0) to (0, height), so its outward normal is (height, BASE_RADIUS)
+            let outwards = (ball.position * Vec3::new(1., 1., 0.)).normalize_or(Vec3::X);
+            let normal = (outwards * height + Vec3::Z * BASE_RADIUS).normalize();
+            let radial = ball.position.truncate().length();
+            let distance_outside = BASE_RADIUS.mul_add(ball.position.z - height, radial * height)
+                / height.hypot(BASE_RADIUS);
+
+            let overlap = distance_outside + config.radius;
+            if overlap > 0. {
+                ball.position -= normal * overlap;
+                let normal_speed = ball.velocity.dot(normal);
+                if normal_speed > 0. {
+                    impact |= normal_speed > MIN_IMPACT_SPEED;
+                    ball.velocity -= (1. + config.restitution) * normal_speed * normal;
+                }
+            }
+
+            impact
+        }
+
+        /// Find all the pairs of overlapping balls, push them apart, and swap their velocities
+        /// along the line between them, like equal masses colliding elastically.
+        fn collide_balls(&mut self, config: &BouncingBallsConfig) {
+            let palette_len = config.palette.len();
+
+            for i in 0..self.balls.len() {
+                for j in (i + 1)..self.balls.len() {
+                    let offset = self.balls[j].position - self.balls[i].position;
+                    let distance = offset.length();
+                    if distance >= 2. * config.radius {
+                        continue;
+                    }
+
+                    let normal = offset.try_normalize().unwrap_or(Vec3::Z);
+                    let correction = normal * 2_f32.mul_add(config.radius, -distance) / 2.;
+                    self.balls[i].position -= correction;
+                    self.balls[j].position += correction;
+
+                    let approach_speed =
+                        (self.balls[i].velocity - self.balls[j].velocity).dot(normal);
+                    if approach_speed > 0. {
+                        self.balls[i].velocity -= approach_speed * normal;
+                        self.balls[j].velocity += approach_speed * normal;
+
+                        if approach_speed > MIN_IMPACT_SPEED {
+                            Self::change_colour(&mut self.rng, &mut self.balls[i], palette_len);
+                            Self::change_colour(&mut self.rng, &mut self.balls[j], palette_len);
+                        }
+                    }
+                }
+            }
+        }
+
+        /// Advance the simulation by one physics step of the given time in seconds.
+        fn step(&mut self, config: &BouncingBallsConfig, delta: f32) {
+            let palette_len = config.palette.len();
+
+            for ball in &mut self.balls {
+                ball.velocity.z = config.gravity.mul_add(-delta, ball.velocity.z);
+                ball.position += ball.velocity * delta;
+
+                if Self::collide_with_hull(ball, config) {
+                    Self::change_colour(&mut self.rng, ball, palette_len);
+                }
+
+                // Throw balls that have run out of energy back up so that the effect keeps going
+                let bounce_height = (ball.position.z - config.radius)
+                    + ball.velocity.length_squared() / (2. * config.gravity);
+                if config.gravity > 0.
+                    && ball.position.z <= config.radius
+                    && bounce_height < MIN_BOUNCE_HEIGHT
+                {
+                    let target_height = self.rng.random_range(0.3..0.7) * COORDS.max_z();
+                    ball.velocity = Vec3::new(
+                        self.rng.random_range(-0.5..0.5),
+                        self.rng.random_range(-0.5..0.5),
+                        f32::sqrt(2. * config.gravity * target_height),
+                    );
+                }
+            }
+
+            self.collide_balls(config);
+        }
+    }
+
+    impl Effect for BouncingBalls {
+        fn from_config(config: BouncingBallsConfig) -> Self {
+            let mut effect = Self {
+                rng: rng!(),
+                balls: vec![],
+            };
+            effect.balls = (0..config.ball_count)
+                .map(|_| effect.new_ball(&config))
+                .collect();
+            effect
+        }
+
+        fn next_frame(
+            &mut self,
+            config: &BouncingBallsConfig,
+            ctx: &FrameContext,
+        ) -> Option<(FrameType, Duration)> {
+            let ball_count = config.ball_count as usize;
+            self.balls.truncate(ball_count);
+            while self.balls.len() < ball_count {
+                let ball = self.new_ball(config);
+                self.balls.push(ball);
+            }
+
+            let objects = self
+                .balls
+                .iter()
+                .map(|ball| FrameObject {
+                    object: Object::Sphere {
+                        center: ball.position,
+                        radius: config.radius,
+                    },
+                    colour: palette_colour(&config.palette, ball.colour_idx),
+                    fadeoff: config.fadeoff,
+                })
+                .collect();
+
+            let steps = (ctx.delta_secs() / MAX_STEP_SECONDS).ceil() as u32;
+            for _ in 0..steps {
+                self.step(config, ctx.delta_secs() / steps as f32);
+            }
+
+            Some((
+                FrameType::Frame3D(Frame3D::new(objects, false)),
+                ctx.target_frame_time(),
+            ))
+        }
+
+        #[cfg(any(test, feature = "bench"))]
+        fn loops_to_test() -> Option<NonZeroU16> {
+            NonZeroU16::new(100)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot_effect;
+
+    #[test]
+    fn bouncing_balls_test() {
+        snapshot_effect!(BouncingBalls);
+    }
+}
@@ -0,0 +1,350 @@
+//! This module provides the [`CellularAutomaton`] effect.
+
+#[cfg(feature = "config-impls")]
+pub use config::CellularAutomatonConfig;
+
+#[cfg(feature = "effect-impls")]
+pub use effect::CellularAutomaton;
+
+use crate::effects::prelude::*;
+
+/// Contains the config for the [`CellularAutomaton`] effect.
+#[cfg(feature = "config-impls")]
+mod config {
+    use super::*;
+
+    /// The config for the [`CellularAutomaton`] effect.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, BaseEffectConfig)]
+    #[serde(default)]
+    pub struct CellularAutomatonConfig {
+        /// How many milliseconds we wait between generations.
+        #[schema(range = 20..=2000)]
+        pub milliseconds_per_generation: u64,
+
+        /// The number of nearest lights that count as the neighbours of each light.
+        #[schema(range = 1..=20)]
+        pub neighbours: u8,
+
+        /// The numbers of live neighbours that cause a dead cell to come alive.
+        pub birth: Vec<u8>,
+
+        /// The numbers of live neighbours that let a live cell survive to the next generation.
+        pub survival: Vec<u8>,
+
+        /// The proportion of cells that are alive when the automaton is seeded.
+        #[schema(range = 0.0..=1.0)]
+        pub initial_density: f32,
+
+        /// How many previous generations to remember when checking if the pattern has stagnated
+        /// or started oscillating. If the new generation matches any of them, then we reseed.
+        #[schema(range = 1..=50)]
+        pub generations_to_remember: u8,
+
+        /// The colour of cells that have just been born.
+        pub young_colour: RGBArray,
+
+        /// The colour of cells that have been alive for a long time.
+        pub old_colour: RGBArray,
+
+        /// The age (in generations) at which a cell reaches [`Self::old_colour`].
+        #[schema(range = 1..=100)]
+        pub old_age: u16,
+    }
+
+    impl Default for CellularAutomatonConfig {
+        fn default() -> Self {
+            Self {
+                milliseconds_per_generation: 300,
+                neighbours: 8,
+                birth: vec![3, 4],
+                survival: vec![2, 3, 4],
+                initial_density: 0.3,
+                generations_to_remember: 12,
+                young_colour: [255, 255, 120],
+                old_colour: [200, 0, 255],
+                old_age: 20,
+            }
+        }
+    }
+
+    /// Display a row of checkboxes, one for each possible number of live neighbours, and return
+    /// whether the rule changed.
+    fn rule_checkboxes(ui: &mut egui::Ui, rule: &mut Vec<u8>, neighbours: u8, label: &str) -> bool {
+        let mut changed = false;
+
+        ui.horizontal_wrapped(|ui| {
+            ui.label(label);
+
+            for count in 0..=neighbours {
+                let mut checked = rule.contains(&count);
+                if ui.checkbox(&mut checked, count.to_string()).changed() {
+                    if checked {
+                        rule.push(count);
+                        rule.sort_unstable();
+                    } else {
+                        rule.retain(|&c| c != count);
+                    }
+                    changed = true;
+                }
+            }
+        });
+
+        changed
+    }
+
+    impl EffectConfig for CellularAutomatonConfig {
+        fn factory_presets() -> Vec<(&'static str, Self)> {
+            vec![
+                (
+                    "Conway",
+                    Self {
+                        birth: vec![3],
+                        survival: vec![2, 3],
+                        ..Self::default()
+                    },
+                ),
+                (
+                    "Slow burn",
+                    Self {
+                        milliseconds_per_generation: 800,
+                        young_colour: [255, 200, 0],
+                        old_colour: [120, 0, 0],
+                        old_age: 50,
+                        ..Self::default()
+                    },
+                ),
+            ]
+        }
+
+        fn render_options_gui(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui) -> bool {
+            let schema = Self::schema();
+            let mut config_changed = false;
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider(
+                            "milliseconds_per_generation",
+                            &mut self.milliseconds_per_generation,
+                        )
+                        .suffix("ms")
+                        .text("Milliseconds per generation"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("neighbours", &mut self.neighbours)
+                        .text("Neighbours per light (requires restart)"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("initial_density", &mut self.initial_density)
+                        .text("Initial density"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("generations_to_remember", &mut self.generations_to_remember)
+                        .text("Generations to check for stagnation"),
+                )
+                .changed();
+
+            ui.add_space(UI_SPACING);
+
+            config_changed |= rule_checkboxes(ui, &mut self.birth, self.neighbours, "Birth:");
+            config_changed |= rule_checkboxes(ui, &mut self.survival, self.neighbours, "Survival:");
+
+            ui.add_space(UI_SPACING);
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("old_age", &mut self.old_age)
+                        .text("Old age (generations)"),
+                )
+                .changed();
+
+            config_changed |= colour_picker(ui, &mut self.young_colour, "Young colour").changed();
+            config_changed |= colour_picker(ui, &mut self.old_colour, "Old colour").changed();
+
+            config_changed
+        }
+    }
+}
+
+/// Contains the [`CellularAutomaton`] effect itself.
+#[cfg(feature = "effect-impls")]
+mod effect {
+    use super::*;
+    use bitvec::vec::BitVec;
+    use std::collections::VecDeque;
+    use ww_gift_coords::COORDS;
+
+    /// Find the indices of the `k` nearest lights to every light.
+    #[instrument]
+    fn nearest_neighbours(k: usize) -> Box<[Box<[usize]>]> {
+        let coords = COORDS.coords();
+
+        coords
+            .iter()
+            .enumerate()
+            .map(|(idx, &point)| {
+                let point = Vec3::from(point);
+                let mut others: Vec<(usize, f32)> = coords
+                    .iter()
+                    .enumerate()
+                    .filter(|&(other_idx, _)| other_idx != idx)
+                    .map(|(other_idx, &other)| (other_idx, point.distance(Vec3::from(other))))
+                    .collect();
+                others.sort_unstable_by(|(_, a), (_, b)| a.total_cmp(b));
+
+                others.into_iter().take(k).map(|(idx, _)| idx).collect()
+            })
+            .collect()
+    }
+
+    /// Run a Life-like cellular automaton on a graph connecting every light to its nearest
+    /// neighbours, and colour each living light by its age.
+    #[derive(Clone, Debug, PartialEq, Eq, BaseEffect)]
+    #[effect(tags = ["life", "simulation", "grid"], unattended)]
+    pub struct CellularAutomaton {
+        /// The RNG used to seed the automaton.
+        rng: StdRng,
+
+        /// The indices of the neighbours of each light.
+        neighbours: Box<[Box<[usize]>]>,
+
+        /// The age of each cell in generations. A dead cell has an age of 0.
+        ages: Vec<u16>,
+
+        /// The most recent generations, used to detect stagnation and oscillation.
+        history: VecDeque<BitVec>,
+
+        /// Keeps the generations coming at the right speed when frames are late.
+        schedule: StepSchedule,
+    }
+
+    impl CellularAutomaton {
+        /// Randomly seed every cell and forget the history.
+        fn reseed(&mut self, initial_density: f32) {
+            let density = initial_density.clamp(0., 1.) as f64;
+            for age in &mut self.ages {
+                *age = u16::from(self.rng.random_bool(density));
+            }
+            self.history.clear();
+            debug!("Reseeded cellular automaton");
+        }
+
+        /// Get the current generation as a bit for each cell saying whether it's alive.
+        fn alive(&self) -> BitVec {
+            self.ages.iter().map(|&age| age > 0).collect()
+        }
+
+        /// Advance the automaton by one generation, reseeding it if it has died, stagnated, or
+        /// started oscillating.
+        fn step(&mut self, config: &CellularAutomatonConfig) {
+            let alive = self.alive();
+
+            self.ages = self
+                .ages
+                .iter()
+                .zip(&self.neighbours)
+                .map(|(&age, neighbours)| {
+                    let live_neighbours = neighbours.iter().filter(|&&idx| alive[idx]).count();
+                    let live_neighbours = u8::try_from(live_neighbours).unwrap_or(u8::MAX);
+
+                    if age > 0 {
+                        if config.survival.contains(&live_neighbours) {
+                            age.saturating_add(1)
+                        } else {
+                            0
+                        }
+                    } else {
+                        u16::from(config.birth.contains(&live_neighbours))
+                    }
+                })
+                .collect();
+
+            let new_alive = self.alive();
+            if new_alive.not_any() || self.history.contains(&new_alive) {
+                self.reseed(config.initial_density);
+                return;
+            }
+
+            self.history.push_back(new_alive);
+            while self.history.len() > config.generations_to_remember as usize {
+                self.history.pop_front();
+            }
+        }
+    }
+
+    impl Effect for CellularAutomaton {
+        fn from_config(config: CellularAutomatonConfig) -> Self {
+            let mut effect = Self {
+                rng: rng!(),
+                neighbours: nearest_neighbours(config.neighbours as usize),
+                ages: vec![0; COORDS.lights_num()],
+                history: VecDeque::new(),
+                schedule: StepSchedule::default(),
+            };
+            effect.reseed(config.initial_density);
+            effect
+        }
+
+        fn next_frame(
+            &mut self,
+            config: &CellularAutomatonConfig,
+            ctx: &FrameContext,
+        ) -> Option<(FrameType, Duration)> {
+            let step = Duration::from_millis(config.milliseconds_per_generation);
+            self.schedule.update(ctx);
+            for _ in 0..self.schedule.catch_up_steps(step) {
+                self.step(config);
+            }
+
+            let old_age = config.old_age.max(1) as f32;
+            let frame_data = self
+                .ages
+                .iter()
+                .map(|&age| {
+                    if age == 0 {
+                        [0; 3]
+                    } else {
+                        lerp_colour(
+                            config.young_colour,
+                            config.old_colour,
+                            (age - 1) as f32 / old_age,
+                        )
+                    }
+                })
+                .collect();
+
+            self.step(config);
+
+            Some((FrameType::RawData(frame_data), self.schedule.request(step)))
+        }
+
+        #[cfg(any(test, feature = "bench"))]
+        fn loops_to_test() -> Option<NonZeroU16> {
+            NonZeroU16::new(100)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot_effect;
+
+    #[test]
+    fn cellular_automaton_test() {
+        snapshot_effect!(CellularAutomaton);
+    }
+}
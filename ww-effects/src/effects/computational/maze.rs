@@ -0,0 +1,458 @@
+//! This module provides the [`Maze`] effect.
+
+#[cfg(feature = "config-impls")]
+pub use config::MazeConfig;
+
+#[cfg(feature = "effect-impls")]
+pub use effect::Maze;
+
+use crate::effects::prelude::*;
+
+/// Contains the config for the [`Maze`] effect.
+#[cfg(feature = "config-impls")]
+mod config {
+    use super::*;
+
+    /// The config for the [`Maze`] effect.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, BaseEffectConfig)]
+    #[serde(default)]
+    pub struct MazeConfig {
+        /// How many milliseconds we wait between steps of carving or solving the maze.
+        #[schema(range = 0..=1000)]
+        pub milliseconds_per_step: u64,
+
+        /// How many milliseconds we show the solved maze for before carving a new one.
+        #[schema(range = 0..=30_000)]
+        pub milliseconds_to_show_solution: u64,
+
+        /// The number of evenly spaced lattice points across the diameter of the bottom of the tree.
+        #[schema(range = 2..=10)]
+        pub lattice_points_across_diameter: u8,
+
+        /// The radius of each cell of the maze.
+        #[schema(range = 0.0..=0.5)]
+        pub thickness: f32,
+
+        /// The fadeoff of the objects in the frame.
+        #[schema(range = 0.0..=0.5)]
+        pub fadeoff: f32,
+
+        /// The colour of the cells that have been carved out of the maze.
+        pub passage_colour: RGBArray,
+
+        /// The colour of the cell that's currently being carved.
+        pub carver_colour: RGBArray,
+
+        /// The colour of the cells that the solver has explored.
+        pub frontier_colour: RGBArray,
+
+        /// The colour of the path from the bottom of the maze to the top.
+        pub path_colour: RGBArray,
+    }
+
+    impl Default for MazeConfig {
+        fn default() -> Self {
+            Self {
+                milliseconds_per_step: 60,
+                milliseconds_to_show_solution: 5000,
+                lattice_points_across_diameter: 6,
+                thickness: 0.2,
+                fadeoff: 0.1,
+                passage_colour: [20, 20, 60],
+                carver_colour: [255, 255, 255],
+                frontier_colour: [200, 60, 0],
+                path_colour: [0, 255, 80],
+            }
+        }
+    }
+
+    impl EffectConfig for MazeConfig {
+        fn factory_presets() -> Vec<(&'static str, Self)> {
+            vec![
+                (
+                    "Quick",
+                    Self {
+                        milliseconds_per_step: 15,
+                        milliseconds_to_show_solution: 2000,
+                        ..Self::default()
+                    },
+                ),
+                (
+                    "Labyrinth",
+                    Self {
+                        lattice_points_across_diameter: 9,
+                        thickness: 0.12,
+                        fadeoff: 0.05,
+                        ..Self::default()
+                    },
+                ),
+            ]
+        }
+
+        fn render_options_gui(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui) -> bool {
+            let schema = Self::schema();
+            let mut config_changed = false;
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("milliseconds_per_step", &mut self.milliseconds_per_step)
+                        .text("Milliseconds per step")
+                        .suffix("ms"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider(
+                            "milliseconds_to_show_solution",
+                            &mut self.milliseconds_to_show_solution,
+                        )
+                        .text("Time to show solution")
+                        .suffix("ms"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider(
+                            "lattice_points_across_diameter",
+                            &mut self.lattice_points_across_diameter,
+                        )
+                        .text("Lattice points across diameter (requires restart)"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("thickness", &mut self.thickness)
+                        .text("Thickness"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(schema.slider("fadeoff", &mut self.fadeoff).text("Fadeoff"))
+                .changed();
+
+            ui.add_space(UI_SPACING);
+
+            config_changed |=
+                colour_picker(ui, &mut self.passage_colour, "Passage colour").changed();
+            config_changed |= colour_picker(ui, &mut self.carver_colour, "Carver colour").changed();
+            config_changed |=
+                colour_picker(ui, &mut self.frontier_colour, "Frontier colour").changed();
+            config_changed |= colour_picker(ui, &mut self.path_colour, "Path colour").changed();
+
+            config_changed
+        }
+    }
+}
+
+/// Contains the [`Maze`] effect itself.
+#[cfg(feature = "effect-impls")]
+mod effect {
+    use super::*;
+    use crate::effects::utils::lattice::{Coord, Lattice};
+    use pathfinding::directed::bfs::{bfs, bfs_reach};
+    use std::collections::{HashMap, HashSet};
+    use ww_gift_coords::COORDS;
+
+    /// What the effect is currently doing.
+    #[derive(Clone, Debug, PartialEq)]
+    enum Phase {
+        /// Carving the maze with a randomised depth-first search.
+        Carving {
+            /// The cells on the current branch of the search, with the current cell last.
+            stack: Vec<Coord>,
+        },
+
+        /// Revealing the cells explored by a breadth-first search from the bottom of the maze to
+        /// the top.
+        Solving {
+            /// The cells explored by the search, in the order that they were explored.
+            explored: Vec<Coord>,
+
+            /// How many of the explored cells have been revealed so far.
+            revealed: usize,
+
+            /// The path from the bottom of the maze to the top.
+            path: Vec<Coord>,
+        },
+
+        /// Showing the path through the maze.
+        Solved {
+            /// The path from the bottom of the maze to the top.
+            path: Vec<Coord>,
+        },
+    }
+
+    /// Carve a random maze through a lattice that follows the shape of the tree, and then solve
+    /// it with a breadth-first search from the bottom to the top.
+    #[derive(Clone, Debug, PartialEq, BaseEffect)]
+    #[effect(tags = ["maze", "pathfinding", "search"], unattended)]
+    pub struct Maze {
+        /// The RNG used to carve the maze.
+        rng: StdRng,
+
+        /// The lattice that the maze is carved through.
+        lattice: Lattice,
+
+        /// The cell at the bottom of the maze, where carving and solving both start.
+        start: Coord,
+
+        /// The cells that have been carved so far, in the order that they were carved.
+        carved: Vec<Coord>,
+
+        /// The passages between carved cells, stored as the neighbours of each cell.
+        passages: HashMap<Coord, Vec<Coord>>,
+
+        /// What the effect is currently doing.
+        phase: Phase,
+
+        /// Keeps the maze moving at the right speed when frames are late.
+        schedule: StepSchedule,
+    }
+
+    impl Maze {
+        /// Get the orthogonal neighbours of a cell that are in the lattice.
+        fn lattice_neighbours(lattice: &Lattice, coord: Coord) -> Vec<Coord> {
+            coord
+                .orthogonal_neighbours()
+                .into_iter()
+                .filter(|neighbour| lattice.points.contains(neighbour))
+                .collect()
+        }
+
+        /// Find the lowest cell in the largest connected part of the lattice, so that the maze
+        /// can cover as much of the tree as possible.
+        fn find_start(lattice: &Lattice) -> Coord {
+            let mut seen = HashSet::new();
+            let mut largest_component = vec![];
+
+            for &point in &lattice.points {
+                if seen.contains(&point) {
+                    continue;
+                }
+
+                let component: Vec<Coord> =
+                    bfs_reach(point, |&coord| Self::lattice_neighbours(lattice, coord)).collect();
+                seen.extend(component.iter().copied());
+
+                if component.len() > largest_component.len() {
+                    largest_component = component;
+                }
+            }
+
+            largest_component
+                .into_iter()
+                .min_by_key(|&Coord(x, y, z)| (z, x.abs() + y.abs()))
+                .expect_or_log("Should have at least one point in lattice")
+        }
+
+        /// Forget the current maze and start carving a new one.
+        fn restart(&mut self) {
+            self.carved = vec![self.start];
+            self.passages = HashMap::from([(self.start, vec![])]);
+            self.phase = Phase::Carving {
+                stack: vec![self.start],
+            };
+        }
+
+        /// Carve one more cell of the maze, or backtrack if the current cell has no uncarved
+        /// neighbours. Return the new phase once the maze is finished.
+        fn carve_step(&mut self, stack: &mut Vec<Coord>) -> Option<Phase> {
+            let Some(&current) = stack.last() else {
+                return Some(self.solve());
+            };
+
+            let uncarved: Vec<Coord> = Self::lattice_neighbours(&self.lattice, current)
+                .into_iter()
+                .filter(|coord| !self.passages.contains_key(coord))
+                .collect();
+
+            if uncarved.is_empty() {
+                stack.pop();
+            } else {
+                let next = uncarved[self.rng.random_range(0..uncarved.len())];
+
+                self.passages.entry(current).or_default().push(next);
+                self.passages.insert(next, vec![current]);
+                self.carved.push(next);
+                stack.push(next);
+            }
+
+            None
+        }
+
+        /// Find the path from the bottom of the maze to the highest carved cell, remembering the
+        /// order that the cells were explored in.
+        fn solve(&self) -> Phase {
+            let start = self.start;
+            let goal = self
+                .carved
+                .iter()
+                .copied()
+                .max_by_key(|&Coord(x, y, z)| (z, -(x.abs() + y.abs())))
+                .unwrap_or(start);
+
+            let successors = |coord: &Coord| self.passages[coord].clone();
+
+            let mut explored = vec![];
+            for coord in bfs_reach(start, successors) {
+                explored.push(coord);
+                if coord == goal {
+                    break;
+                }
+            }
+
+            let path = bfs(&start, successors, |&coord| coord == goal)
+                .expect_or_log("Every carved cell should be reachable from the start");
+
+            debug!(
+                cells = self.carved.len(),
+                explored = explored.len(),
+                path_length = path.len(),
+                "Solved maze"
+            );
+
+            Phase::Solving {
+                explored,
+                revealed: 0,
+                path,
+            }
+        }
+
+        /// Create a sphere for a single cell.
+        fn cell_object(&self, coord: Coord, colour: RGBArray, config: &MazeConfig) -> FrameObject {
+            FrameObject {
+                object: Object::Sphere {
+                    center: coord.to_gift(self.lattice.cell_width).into(),
+                    radius: config.thickness,
+                },
+                colour,
+                fadeoff: config.fadeoff,
+            }
+        }
+
+        /// Carve or solve one more step of the maze. This does nothing once the maze is solved.
+        fn advance(&mut self) {
+            match &mut self.phase {
+                Phase::Carving { stack } => {
+                    let mut stack = std::mem::take(stack);
+                    self.phase = self
+                        .carve_step(&mut stack)
+                        .unwrap_or(Phase::Carving { stack });
+                }
+                Phase::Solving {
+                    explored,
+                    revealed,
+                    path,
+                } => {
+                    if *revealed < explored.len() {
+                        *revealed += 1;
+                    } else {
+                        self.phase = Phase::Solved {
+                            path: std::mem::take(path),
+                        };
+                    }
+                }
+                Phase::Solved { .. } => {}
+            }
+        }
+
+        /// Create the frame objects for the current phase.
+        fn frame_objects(&self, config: &MazeConfig) -> Vec<FrameObject> {
+            let passages = self
+                .carved
+                .iter()
+                .map(|&coord| self.cell_object(coord, config.passage_colour, config));
+
+            let highlighted: Vec<FrameObject> = match &self.phase {
+                Phase::Carving { stack } => stack
+                    .last()
+                    .map(|&coord| self.cell_object(coord, config.carver_colour, config))
+                    .into_iter()
+                    .collect(),
+                Phase::Solving {
+                    explored, revealed, ..
+                } => explored[..*revealed]
+                    .iter()
+                    .map(|&coord| self.cell_object(coord, config.frontier_colour, config))
+                    .collect(),
+                Phase::Solved { path } => path
+                    .iter()
+                    .map(|&coord| self.cell_object(coord, config.path_colour, config))
+                    .collect(),
+            };
+
+            passages.chain(highlighted).collect()
+        }
+    }
+
+    impl Effect for Maze {
+        fn from_config(config: MazeConfig) -> Self {
+            let lattice = Lattice::new(&COORDS, config.lattice_points_across_diameter);
+            let start = Self::find_start(&lattice);
+
+            let mut maze = Self {
+                rng: rng!(),
+                lattice,
+                start,
+                carved: vec![],
+                passages: HashMap::new(),
+                phase: Phase::Carving { stack: vec![] },
+                schedule: StepSchedule::default(),
+            };
+            maze.restart();
+            maze
+        }
+
+        fn next_frame(
+            &mut self,
+            config: &MazeConfig,
+            ctx: &FrameContext,
+        ) -> Option<(FrameType, Duration)> {
+            let step = Duration::from_millis(config.milliseconds_per_step);
+            self.schedule.update(ctx);
+
+            // Catch up on the steps that we missed, but don't skip showing the solution
+            for _ in 0..self.schedule.catch_up_steps(step) {
+                if matches!(self.phase, Phase::Solved { .. }) {
+                    break;
+                }
+                self.advance();
+            }
+
+            let frame = FrameType::Frame3D(Frame3D::new(self.frame_objects(config), false));
+
+            let duration = if matches!(self.phase, Phase::Solved { .. }) {
+                self.restart();
+                Duration::from_millis(config.milliseconds_to_show_solution)
+            } else {
+                self.advance();
+                step
+            };
+
+            Some((frame, self.schedule.request(duration)))
+        }
+
+        #[cfg(any(test, feature = "bench"))]
+        fn loops_to_test() -> Option<NonZeroU16> {
+            NonZeroU16::new(100)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot_effect;
+
+    #[test]
+    fn maze_test() {
+        snapshot_effect!(Maze);
+    }
+}
@@ -1,9 +1,12 @@
 //! This module contains effects that demonstrate interesting computation, such as pathfinding.
 
 pub mod ai_snake;
+pub mod boids;
+pub mod bouncing_balls;
+pub mod cellular_automaton;
+pub mod maze;
+pub mod sorting;
 
-#[cfg(feature = "effect-impls")]
-pub use self::ai_snake::AiSnake;
-
-#[cfg(feature = "config-impls")]
-pub use self::ai_snake::AiSnakeConfig;
+// The effects in these modules are found by the build script, which re-exports them along with
+// their configs
+include!(concat!(env!("OUT_DIR"), "/effects/computational.rs"));
@@ -0,0 +1,523 @@
+//! This module provides the [`Sorting`] effect.
+
+#[cfg(feature = "config-impls")]
+pub use config::{SortingAlgorithm, SortingConfig};
+
+#[cfg(feature = "effect-impls")]
+pub use effect::Sorting;
+
+use crate::effects::prelude::*;
+
+/// Contains the config for the [`Sorting`] effect.
+#[cfg(feature = "config-impls")]
+mod config {
+    use super::*;
+    use strum::IntoEnumIterator;
+
+    /// A sorting algorithm that the [`Sorting`] effect can visualise.
+    #[derive(
+        Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, strum::Display, strum::EnumIter,
+    )]
+    pub enum SortingAlgorithm {
+        /// Repeatedly swap adjacent elements that are in the wrong order.
+        #[strum(to_string = "Bubble sort")]
+        Bubble,
+
+        /// Move each element down into its place among the elements before it.
+        #[strum(to_string = "Insertion sort")]
+        Insertion,
+
+        /// Partition the elements around a pivot and sort each side.
+        #[strum(to_string = "Quicksort")]
+        Quick,
+
+        /// Sort each half and then merge the halves together.
+        #[strum(to_string = "Merge sort")]
+        Merge,
+
+        /// Build a max-heap and repeatedly move its largest element to the end.
+        #[strum(to_string = "Heapsort")]
+        Heap,
+    }
+
+    /// The config for the [`Sorting`] effect.
+    #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, BaseEffectConfig)]
+    #[serde(default)]
+    pub struct SortingConfig {
+        /// The sorting algorithm to visualise.
+        pub algorithm: SortingAlgorithm,
+
+        /// The number of horizontal layers that the tree is split into. Each layer is one element
+        /// of the list being sorted.
+        #[schema(range = 2..=40)]
+        pub layers: u8,
+
+        /// How many milliseconds we wait between each comparison or swap.
+        #[schema(range = 10..=2000)]
+        pub milliseconds_per_step: u64,
+
+        /// How many milliseconds we show the sorted list for before shuffling it again.
+        #[schema(range = 0..=30_000)]
+        pub milliseconds_to_show_sorted: u64,
+
+        /// The colour of layers that are being compared.
+        pub compare_colour: RGBArray,
+
+        /// The colour of layers that are being swapped or written.
+        pub swap_colour: RGBArray,
+    }
+
+    impl Default for SortingConfig {
+        fn default() -> Self {
+            Self {
+                algorithm: SortingAlgorithm::Quick,
+                layers: 16,
+                milliseconds_per_step: 150,
+                milliseconds_to_show_sorted: 3000,
+                compare_colour: [255, 255, 255],
+                swap_colour: [60, 60, 60],
+            }
+        }
+    }
+
+    impl EffectConfig for SortingConfig {
+        fn factory_presets() -> Vec<(&'static str, Self)> {
+            vec![
+                (
+                    "Bubble sort",
+                    Self {
+                        algorithm: SortingAlgorithm::Bubble,
+                        milliseconds_per_step: 40,
+                        ..Self::default()
+                    },
+                ),
+                (
+                    "Merge sort",
+                    Self {
+                        algorithm: SortingAlgorithm::Merge,
+                        layers: 32,
+                        ..Self::default()
+                    },
+                ),
+            ]
+        }
+
+        fn render_options_gui(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui) -> bool {
+            let schema = Self::schema();
+            let mut config_changed = false;
+
+            egui::ComboBox::from_label("Algorithm")
+                .selected_text(self.algorithm.to_string())
+                .show_ui(ui, |ui| {
+                    for algorithm in SortingAlgorithm::iter() {
+                        config_changed |= ui
+                            .selectable_value(&mut self.algorithm, algorithm, algorithm.to_string())
+                            .changed();
+                    }
+                });
+
+            config_changed |= ui
+                .add(schema.slider("layers", &mut self.layers).text("Layers"))
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("milliseconds_per_step", &mut self.milliseconds_per_step)
+                        .text("Milliseconds per step")
+                        .suffix("ms"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider(
+                            "milliseconds_to_show_sorted",
+                            &mut self.milliseconds_to_show_sorted,
+                        )
+                        .text("Time to show sorted list")
+                        .suffix("ms"),
+                )
+                .changed();
+
+            ui.add_space(UI_SPACING);
+
+            config_changed |=
+                colour_picker(ui, &mut self.compare_colour, "Compare colour").changed();
+            config_changed |= colour_picker(ui, &mut self.swap_colour, "Swap colour").changed();
+
+            config_changed
+        }
+    }
+}
+
+/// Contains the [`Sorting`] effect itself.
+#[cfg(feature = "effect-impls")]
+mod effect {
+    use super::*;
+    use rand::seq::SliceRandom;
+    use ww_gift_coords::COORDS;
+
+    /// The proportion of the colour wheel used to show the values, so that the smallest and
+    /// largest values don't end up with similar colours.
+    const HUE_RANGE: f32 = 0.8;
+
+    /// A single step of a sorting algorithm.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    enum Operation {
+        /// Compare the elements at two indices.
+        Compare(usize, usize),
+
+        /// Swap the elements at two indices.
+        Swap(usize, usize),
+
+        /// Write a value into an index.
+        Write(usize, u8),
+    }
+
+    /// Run a sorting algorithm on a list and record every comparison and swap that it makes.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    struct Recorder {
+        /// The list being sorted.
+        values: Vec<u8>,
+
+        /// The operations performed so far.
+        operations: Vec<Operation>,
+    }
+
+    impl Recorder {
+        /// Record a sorting algorithm sorting the given values.
+        fn record(values: Vec<u8>, algorithm: SortingAlgorithm) -> Vec<Operation> {
+            let len = values.len();
+            let mut recorder = Self {
+                values,
+                operations: vec![],
+            };
+
+            match algorithm {
+                SortingAlgorithm::Bubble => recorder.bubble_sort(),
+                SortingAlgorithm::Insertion => recorder.insertion_sort(),
+                SortingAlgorithm::Quick => recorder.quick_sort(0, len),
+                SortingAlgorithm::Merge => recorder.merge_sort(0, len),
+                SortingAlgorithm::Heap => recorder.heap_sort(),
+            }
+
+            debug_assert!(
+                recorder.values.is_sorted(),
+                "{algorithm} should have sorted the values"
+            );
+
+            recorder.operations
+        }
+
+        /// Is the value at `i` less than the value at `j`?
+        fn less(&mut self, i: usize, j: usize) -> bool {
+            self.operations.push(Operation::Compare(i, j));
+            self.values[i] < self.values[j]
+        }
+
+        /// Swap the values at `i` and `j`.
+        fn swap(&mut self, i: usize, j: usize) {
+            self.operations.push(Operation::Swap(i, j));
+            self.values.swap(i, j);
+        }
+
+        /// Write a value into `i`.
+        fn write(&mut self, i: usize, value: u8) {
+            self.operations.push(Operation::Write(i, value));
+            self.values[i] = value;
+        }
+
+        /// Sort the values with bubble sort.
+        fn bubble_sort(&mut self) {
+            for end in (1..self.values.len()).rev() {
+                let mut swapped = false;
+                for i in 0..end {
+                    if self.less(i + 1, i) {
+                        self.swap(i, i + 1);
+                        swapped = true;
+                    }
+                }
+
+                if !swapped {
+                    break;
+                }
+            }
+        }
+
+        /// Sort the values with insertion sort.
+        fn insertion_sort(&mut self) {
+            for i in 1..self.values.len() {
+                let mut j = i;
+                while j > 0 && self.less(j, j - 1) {
+                    self.swap(j, j - 1);
+                    j -= 1;
+                }
+            }
+        }
+
+        /// Sort the values in `lo..hi` with quicksort, using the last element as the pivot.
+        fn quick_sort(&mut self, lo: usize, hi: usize) {
+            if hi.saturating_sub(lo) < 2 {
+                return;
+            }
+
+            let pivot = hi - 1;
+            let mut store = lo;
+            for i in lo..pivot {
+                if self.less(i, pivot) {
+                    if i != store {
+                        self.swap(i, store);
+                    }
+                    store += 1;
+                }
+            }
+            if store != pivot {
+                self.swap(store, pivot);
+            }
+
+            self.quick_sort(lo, store);
+            self.quick_sort(store + 1, hi);
+        }
+
+        /// Sort the values in `lo..hi` with merge sort.
+        fn merge_sort(&mut self, lo: usize, hi: usize) {
+            if hi.saturating_sub(lo) < 2 {
+                return;
+            }
+
+            let mid = lo.midpoint(hi);
+            self.merge_sort(lo, mid);
+            self.merge_sort(mid, hi);
+
+            let mut merged = Vec::with_capacity(hi - lo);
+            let (mut left, mut right) = (lo, mid);
+            while left < mid && right < hi {
+                if self.less(right, left) {
+                    merged.push(self.values[right]);
+                    right += 1;
+                } else {
+                    merged.push(self.values[left]);
+                    left += 1;
+                }
+            }
+            merged.extend_from_slice(&self.values[left..mid]);
+            merged.extend_from_slice(&self.values[right..hi]);
+
+            for (offset, value) in merged.into_iter().enumerate() {
+                if self.values[lo + offset] != value {
+                    self.write(lo + offset, value);
+                }
+            }
+        }
+
+        /// Sort the values with heapsort.
+        fn heap_sort(&mut self) {
+            let len = self.values.len();
+
+            for start in (0..len / 2).rev() {
+                self.sift_down(start, len);
+            }
+
+            for end in (1..len).rev() {
+                self.swap(0, end);
+                self.sift_down(0, end);
+            }
+        }
+
+        /// Move the value at `root` down the max-heap in `0..end` until it's in the right place.
+        fn sift_down(&mut self, mut root: usize, end: usize) {
+            loop {
+                let mut child = 2 * root + 1;
+                if child >= end {
+                    break;
+                }
+
+                if child + 1 < end && self.less(child, child + 1) {
+                    child += 1;
+                }
+
+                if self.less(root, child) {
+                    self.swap(root, child);
+                    root = child;
+                } else {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Split the tree into horizontal layers with shuffled values, shown as hues, and animate a
+    /// sorting algorithm putting them in order from bottom to top.
+    #[derive(Clone, Debug, PartialEq, Eq, BaseEffect)]
+    #[effect(tags = ["algorithm", "sorting", "rainbow"], unattended)]
+    pub struct Sorting {
+        /// The RNG used to shuffle the values.
+        rng: StdRng,
+
+        /// The algorithm that recorded the current operations.
+        algorithm: SortingAlgorithm,
+
+        /// The layer that each light belongs to.
+        light_layers: Vec<usize>,
+
+        /// The value of each layer, from the bottom of the tree to the top.
+        values: Vec<u8>,
+
+        /// The operations that sort the values.
+        operations: Vec<Operation>,
+
+        /// The index of the next operation to perform.
+        step: usize,
+
+        /// Keeps the sort moving at the right speed when frames are late.
+        schedule: StepSchedule,
+    }
+
+    impl Sorting {
+        /// Shuffle the values and record the operations needed to sort them.
+        fn restart(&mut self, config: &SortingConfig) {
+            let layers = config.layers.max(2);
+
+            self.algorithm = config.algorithm;
+            self.light_layers = COORDS
+                .coords()
+                .iter()
+                .map(|&(_, _, z)| {
+                    ((z / COORDS.max_z() * layers as f32) as usize).min(layers as usize - 1)
+                })
+                .collect();
+
+            self.values = (0..layers).collect();
+            self.values.shuffle(&mut self.rng);
+
+            self.operations = Recorder::record(self.values.clone(), config.algorithm);
+            self.step = 0;
+
+            debug!(
+                algorithm = %config.algorithm,
+                operations = self.operations.len(),
+                "Shuffled values for sorting"
+            );
+        }
+
+        /// Apply the operation to the values.
+        fn perform(&mut self, operation: Operation) {
+            match operation {
+                Operation::Swap(i, j) => self.values.swap(i, j),
+                Operation::Write(i, value) => self.values[i] = value,
+                Operation::Compare(..) => {}
+            }
+        }
+
+        /// Get the colour of each layer, highlighting the layers involved in the operation.
+        fn layer_colours(
+            &self,
+            operation: Option<Operation>,
+            config: &SortingConfig,
+        ) -> Vec<RGBArray> {
+            let max_value = (self.values.len() - 1).max(1) as f32;
+            let mut colours: Vec<RGBArray> = self
+                .values
+                .iter()
+                .map(|&value| hue_colour(value as f32 / max_value * HUE_RANGE))
+                .collect();
+
+            match operation {
+                Some(Operation::Compare(i, j)) => {
+                    colours[i] = config.compare_colour;
+                    colours[j] = config.compare_colour;
+                }
+                Some(Operation::Swap(i, j)) => {
+                    colours[i] = config.swap_colour;
+                    colours[j] = config.swap_colour;
+                }
+                Some(Operation::Write(i, _)) => colours[i] = config.swap_colour,
+                None => {}
+            }
+
+            colours
+        }
+    }
+
+    impl Effect for Sorting {
+        fn from_config(config: SortingConfig) -> Self {
+            let mut effect = Self {
+                rng: rng!(),
+                algorithm: config.algorithm,
+                light_layers: vec![],
+                values: vec![],
+                operations: vec![],
+                step: 0,
+                schedule: StepSchedule::default(),
+            };
+            effect.restart(&config);
+            effect
+        }
+
+        fn next_frame(
+            &mut self,
+            config: &SortingConfig,
+            ctx: &FrameContext,
+        ) -> Option<(FrameType, Duration)> {
+            if config.algorithm != self.algorithm
+                || config.layers.max(2) as usize != self.values.len()
+            {
+                self.restart(config);
+            }
+
+            // Catch up on the operations that we missed, but don't skip showing the sorted values
+            let step = Duration::from_millis(config.milliseconds_per_step);
+            self.schedule.update(ctx);
+            for _ in 0..self.schedule.catch_up_steps(step) {
+                let Some(&operation) = self.operations.get(self.step) else {
+                    break;
+                };
+                self.perform(operation);
+                self.step += 1;
+            }
+
+            let operation = self.operations.get(self.step).copied();
+            if let Some(operation) = operation {
+                self.perform(operation);
+            }
+
+            let layer_colours = self.layer_colours(operation, config);
+            let frame_data = self
+                .light_layers
+                .iter()
+                .map(|&layer| layer_colours[layer])
+                .collect();
+
+            let duration = if operation.is_some() {
+                self.step += 1;
+                step
+            } else {
+                self.restart(config);
+                Duration::from_millis(config.milliseconds_to_show_sorted)
+            };
+
+            Some((
+                FrameType::RawData(frame_data),
+                self.schedule.request(duration),
+            ))
+        }
+
+        #[cfg(any(test, feature = "bench"))]
+        fn loops_to_test() -> Option<NonZeroU16> {
+            NonZeroU16::new(100)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot_effect;
+
+    #[test]
+    fn sorting_test() {
+        snapshot_effect!(Sorting);
+    }
+}
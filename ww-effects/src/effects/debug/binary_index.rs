@@ -15,17 +15,22 @@ mod config {
 
     /// The config for the [`DebugBinaryIndex`] effect; includes timing and colors.
     #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, BaseEffectConfig)]
+    #[serde(default)]
     pub struct DebugBinaryIndexConfig {
         /// The number of milliseconds that the lights are on for.
+        #[schema(range = 0..=1500)]
         pub light_time_ms: u64,
 
         /// The number of milliseconds to wait after turning off all the lights.
+        #[schema(range = 0..=1500)]
         pub dark_time_ms: u64,
 
         /// The color to illuminate lights representing 0.
+        #[schema(colour)]
         pub zero_color: [u8; 3],
 
         /// The color to illuminate lights representing 1.
+        #[schema(colour)]
         pub one_color: [u8; 3],
     }
 
@@ -41,12 +46,25 @@ mod config {
     }
 
     impl EffectConfig for DebugBinaryIndexConfig {
+        fn factory_presets() -> Vec<(&'static str, Self)> {
+            vec![(
+                "Fast",
+                Self {
+                    light_time_ms: 500,
+                    dark_time_ms: 200,
+                    ..Self::default()
+                },
+            )]
+        }
+
         fn render_options_gui(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui) -> bool {
+            let schema = Self::schema();
             let mut config_changed = false;
 
             config_changed |= ui
                 .add(
-                    egui::Slider::new(&mut self.light_time_ms, 0..=1500)
+                    schema
+                        .slider("light_time_ms", &mut self.light_time_ms)
                         .suffix("ms")
                         .text("Light time"),
                 )
@@ -54,7 +72,8 @@ mod config {
 
             config_changed |= ui
                 .add(
-                    egui::Slider::new(&mut self.dark_time_ms, 0..=1500)
+                    schema
+                        .slider("dark_time_ms", &mut self.dark_time_ms)
                         .suffix("ms")
                         .text("Dark time"),
                 )
@@ -79,6 +98,7 @@ mod effect {
 
     /// Make each light flash its index in binary.
     #[derive(Clone, Debug, PartialEq, Eq, BaseEffect)]
+    #[effect(tags = ["debug", "calibration"])]
     pub struct DebugBinaryIndex {
         /// The list of patterns to display, *in reverse order*.
         ///
@@ -89,6 +109,9 @@ mod effect {
 
         /// Should the lights be on or off in the next frame?
         on: bool,
+
+        /// Keeps the sequence on time when patterns are shown for longer than asked.
+        schedule: StepSchedule,
     }
 
     impl Effect for DebugBinaryIndex {
@@ -119,10 +142,20 @@ mod effect {
                 })
                 .collect();
 
-            Self { patterns, on: true }
+            Self {
+                patterns,
+                on: true,
+                schedule: StepSchedule::default(),
+            }
         }
 
-        fn next_frame(&mut self, config: &DebugBinaryIndexConfig) -> Option<(FrameType, Duration)> {
+        fn next_frame(
+            &mut self,
+            config: &DebugBinaryIndexConfig,
+            ctx: &FrameContext,
+        ) -> Option<(FrameType, Duration)> {
+            self.schedule.update(ctx);
+
             let ret_val = if self.on {
                 let pattern = self.patterns.pop()?;
 
@@ -145,7 +178,8 @@ mod effect {
 
                 (
                     FrameType::RawData(frame_data),
-                    Duration::from_millis(config.light_time_ms),
+                    self.schedule
+                        .request(Duration::from_millis(config.light_time_ms)),
                 )
             } else {
                 // If the previous frame was the final one, we don't want an extra pause before the
@@ -154,7 +188,12 @@ mod effect {
                     return None;
                 }
 
-                (FrameType::Off, Duration::from_millis(config.dark_time_ms))
+                // Every pattern must be shown for as long as asked, so catch up by shortening the
+                // dark gaps instead
+                let dark_time = self
+                    .schedule
+                    .shorten(Duration::from_millis(config.dark_time_ms));
+                (FrameType::Off, self.schedule.request(dark_time))
             };
 
             self.on = !self.on;
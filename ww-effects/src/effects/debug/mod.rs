@@ -3,8 +3,6 @@
 pub mod binary_index;
 pub mod one_by_one;
 
-#[cfg(feature = "effect-impls")]
-pub use self::{binary_index::DebugBinaryIndex, one_by_one::DebugOneByOne};
-
-#[cfg(feature = "config-impls")]
-pub use self::{binary_index::DebugBinaryIndexConfig, one_by_one::DebugOneByOneConfig};
+// The effects in these modules are found by the build script, which re-exports them along with
+// their configs
+include!(concat!(env!("OUT_DIR"), "/effects/debug.rs"));
@@ -15,14 +15,18 @@ mod config {
 
     /// The config for the [`DebugOneByOne`] effect; includes timing and colour.
     #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, BaseEffectConfig)]
+    #[serde(default)]
     pub struct DebugOneByOneConfig {
         /// The number of milliseconds that the light is on for.
+        #[schema(range = 0..=1500)]
         pub light_time_ms: u64,
 
         /// The number of milliseconds to wait after turning off all the lights.
+        #[schema(range = 0..=1500)]
         pub dark_time_ms: u64,
 
         /// The color for the current light.
+        #[schema(colour)]
         pub colour: [u8; 3],
     }
 
@@ -37,12 +41,25 @@ mod config {
     }
 
     impl EffectConfig for DebugOneByOneConfig {
+        fn factory_presets() -> Vec<(&'static str, Self)> {
+            vec![(
+                "Fast",
+                Self {
+                    light_time_ms: 200,
+                    dark_time_ms: 50,
+                    ..Self::default()
+                },
+            )]
+        }
+
         fn render_options_gui(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui) -> bool {
+            let schema = Self::schema();
             let mut config_changed = false;
 
             config_changed |= ui
                 .add(
-                    egui::Slider::new(&mut self.light_time_ms, 0..=1500)
+                    schema
+                        .slider("light_time_ms", &mut self.light_time_ms)
                         .suffix("ms")
                         .text("Light time"),
                 )
@@ -50,7 +67,8 @@ mod config {
 
             config_changed |= ui
                 .add(
-                    egui::Slider::new(&mut self.dark_time_ms, 0..=1500)
+                    schema
+                        .slider("dark_time_ms", &mut self.dark_time_ms)
                         .suffix("ms")
                         .text("Dark time"),
                 )
@@ -73,20 +91,34 @@ mod effect {
 
     /// Light up each light individually, one-by-one.
     #[derive(Clone, Debug, PartialEq, Eq, BaseEffect)]
+    #[effect(tags = ["debug", "calibration"])]
     pub struct DebugOneByOne {
         /// Which index should we be displaying next frame?
         index: usize,
 
         /// Should the lights be on or off in the next frame?
         on: bool,
+
+        /// Keeps the sequence on time when lights are shown for longer than asked.
+        schedule: StepSchedule,
     }
 
     impl Effect for DebugOneByOne {
         fn from_config(_config: DebugOneByOneConfig) -> Self {
-            Self { index: 0, on: true }
+            Self {
+                index: 0,
+                on: true,
+                schedule: StepSchedule::default(),
+            }
         }
 
-        fn next_frame(&mut self, config: &DebugOneByOneConfig) -> Option<(FrameType, Duration)> {
+        fn next_frame(
+            &mut self,
+            config: &DebugOneByOneConfig,
+            ctx: &FrameContext,
+        ) -> Option<(FrameType, Duration)> {
+            self.schedule.update(ctx);
+
             let ret_val = if self.on {
                 debug_assert!(
                     self.index < COORDS.lights_num(),
@@ -101,7 +133,8 @@ mod effect {
 
                 (
                     FrameType::RawData(frame_data),
-                    Duration::from_millis(config.light_time_ms),
+                    self.schedule
+                        .request(Duration::from_millis(config.light_time_ms)),
                 )
             } else {
                 // If the previous frame was the final one, we don't want an extra pause before the
@@ -110,7 +143,12 @@ mod effect {
                     return None;
                 }
 
-                (FrameType::Off, Duration::from_millis(config.dark_time_ms))
+                // Every light must be shown for as long as asked, so catch up by shortening the
+                // dark gaps instead
+                let dark_time = self
+                    .schedule
+                    .shorten(Duration::from_millis(config.dark_time_ms));
+                (FrameType::Off, self.schedule.request(dark_time))
             };
 
             self.on = !self.on;
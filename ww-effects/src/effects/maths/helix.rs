@@ -0,0 +1,279 @@
+//! This module contains the [`Helix`] effect.
+
+#[cfg(feature = "config-impls")]
+pub use config::HelixConfig;
+
+#[cfg(feature = "effect-impls")]
+pub use effect::Helix;
+
+use crate::effects::prelude::*;
+
+/// Contains the config for the [`Helix`] effect.
+#[cfg(feature = "config-impls")]
+mod config {
+    use super::*;
+
+    /// The config for the [`Helix`] effect.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, BaseEffectConfig)]
+    #[serde(default)]
+    pub struct HelixConfig {
+        /// The number of strands wound around the tree.
+        #[schema(range = 1..=12)]
+        pub strands: u8,
+
+        /// How far up the tree (in GIFT coords) each strand climbs in one full turn.
+        #[schema(range = 0.2..=10.0)]
+        pub pitch: f32,
+
+        /// The vertical thickness of each strand.
+        #[schema(range = 0.0..=1.0)]
+        pub band_width: f32,
+
+        /// The maximum distance where colour drops to zero.
+        ///
+        /// See [`ww_frame::FrameObject::fadeoff`].
+        #[schema(range = 0.0..=0.5)]
+        pub fadeoff: f32,
+
+        /// How many full turns the helix makes every second.
+        #[schema(range = 0.0..=2.0)]
+        pub rotations_per_second: f32,
+
+        /// Should the helix rotate clockwise when viewed from above?
+        pub clockwise: bool,
+
+        /// The colours of the strands. Each strand takes the next colour in the list.
+        pub colours: Vec<RGBArray>,
+
+        /// The colour of the lights between the strands.
+        pub background_colour: RGBArray,
+    }
+
+    impl Default for HelixConfig {
+        fn default() -> Self {
+            Self {
+                strands: 3,
+                pitch: 1.5,
+                band_width: 0.2,
+                fadeoff: 0.1,
+                rotations_per_second: 0.25,
+                clockwise: false,
+                colours: vec![[255, 0, 0], [255, 255, 255], [0, 200, 0]],
+                background_colour: [0, 0, 0],
+            }
+        }
+    }
+
+    impl EffectConfig for HelixConfig {
+        fn factory_presets() -> Vec<(&'static str, Self)> {
+            vec![
+                (
+                    "Candy cane",
+                    Self {
+                        strands: 2,
+                        pitch: 1.,
+                        band_width: 0.35,
+                        colours: vec![[255, 0, 0], [255, 255, 255]],
+                        ..Self::default()
+                    },
+                ),
+                (
+                    "Rainbow",
+                    Self {
+                        strands: 6,
+                        rotations_per_second: 0.5,
+                        colours: vec![
+                            [255, 0, 0],
+                            [255, 160, 0],
+                            [255, 255, 0],
+                            [0, 255, 0],
+                            [0, 120, 255],
+                            [160, 0, 255],
+                        ],
+                        ..Self::default()
+                    },
+                ),
+            ]
+        }
+
+        fn render_options_gui(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui) -> bool {
+            let schema = Self::schema();
+            let mut config_changed = false;
+
+            config_changed |= ui
+                .add(schema.slider("strands", &mut self.strands).text("Strands"))
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("pitch", &mut self.pitch)
+                        .suffix("units/turn")
+                        .text("Pitch"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("band_width", &mut self.band_width)
+                        .text("Band width"),
+                )
+                .changed();
+
+            config_changed |= ui
+                .add(schema.slider("fadeoff", &mut self.fadeoff).text("Fadeoff"))
+                .changed();
+
+            config_changed |= ui
+                .add(
+                    schema
+                        .slider("rotations_per_second", &mut self.rotations_per_second)
+                        .suffix("/s")
+                        .text("Rotation speed"),
+                )
+                .changed();
+
+            config_changed |= ui.checkbox(&mut self.clockwise, "Clockwise?").changed();
+
+            ui.add_space(UI_SPACING);
+
+            config_changed |=
+                colour_picker(ui, &mut self.background_colour, "Background colour").changed();
+            config_changed |= palette_editor(ui, &mut self.colours, "Strand colours");
+
+            config_changed
+        }
+    }
+}
+
+/// Contains the [`Helix`] effect itself.
+#[cfg(feature = "effect-impls")]
+mod effect {
+    use super::*;
+    use std::f32::consts::TAU;
+    use ww_gift_coords::{COORDS, PointF};
+
+    /// Wind coloured strands around the tree like a barber's pole and rotate them.
+    #[derive(Clone, Debug, PartialEq, BaseEffect)]
+    #[effect(tags = ["spiral", "rotation", "colourful"], unattended)]
+    pub struct Helix {
+        /// The cylindrical coordinates of the lights.
+        cylindrical_coords: Vec<PointF>,
+
+        /// How far the helix has rotated, in turns.
+        rotation: f32,
+    }
+
+    impl Effect for Helix {
+        fn from_config(_config: HelixConfig) -> Self {
+            Self {
+                cylindrical_coords: COORDS.cylindrical_coords(),
+                rotation: 0.,
+            }
+        }
+
+        fn next_frame(
+            &mut self,
+            config: &HelixConfig,
+            ctx: &FrameContext,
+        ) -> Option<(FrameType, Duration)> {
+            // Rotate by however long it's actually been since the last frame, so that the speed
+            // doesn't depend on the frame rate
+            let direction = if config.clockwise { -1. } else { 1. };
+            self.rotation = (direction * config.rotations_per_second)
+                .mul_add(ctx.delta_secs(), self.rotation)
+                .rem_euclid(1.);
+
+            let strands = config.strands.max(1) as f32;
+            let pitch = config.pitch.max(0.01);
+            let half_width = config.band_width / 2.;
+
+            let frame_data = self
+                .cylindrical_coords
+                .iter()
+                .map(|&(_, angle, z)| {
+                    // How far around the helix this light is, measured in strands
+                    let position = (angle / TAU - z / pitch - self.rotation) * strands;
+                    let nearest_strand = position.round();
+
+                    let strand = (nearest_strand as i32).rem_euclid(strands as i32) as usize;
+                    let colour = palette_colour(&config.colours, strand);
+
+                    // Moving up by one pitch takes us all the way round, so we can convert the
+                    // distance to the nearest strand into a vertical distance
+                    let distance = (position - nearest_strand).abs() * pitch / strands;
+
+                    if distance <= half_width {
+                        colour
+                    } else if distance <= half_width + config.fadeoff {
+                        lerp_colour(
+                            config.background_colour,
+                            colour,
+                            1. - (distance - half_width) / config.fadeoff,
+                        )
+                    } else {
+                        config.background_colour
+                    }
+                })
+                .collect();
+
+            Some((FrameType::RawData(frame_data), ctx.target_frame_time()))
+        }
+
+        #[cfg(any(test, feature = "bench"))]
+        fn loops_to_test() -> Option<NonZeroU16> {
+            NonZeroU16::new(100)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SimulatedClock, snapshot_effect, traits::Effect};
+
+    #[test]
+    fn helix_test() {
+        snapshot_effect!(Helix);
+    }
+
+    #[test]
+    fn helix_frame_rate_test() {
+        let config = HelixConfig::default();
+
+        // Show a frame every `frame_time`, no matter how long the effect asked for, and return
+        // the frame that was showing after a second
+        let frame_after_one_second = |frame_time: Duration| {
+            let mut effect = Helix::from_config(config.clone());
+            let mut clock = SimulatedClock::default();
+
+            loop {
+                let ctx = clock.tick();
+                let (frame, _) = effect
+                    .next_frame(&config, &ctx)
+                    .expect_or_log("Helix should never finish");
+                if ctx.elapsed >= Duration::from_secs(1) {
+                    let FrameType::RawData(data) = frame else {
+                        panic!("Helix should only return raw data");
+                    };
+                    return data;
+                }
+                clock.advance(frame_time);
+            }
+        };
+
+        // A server which can only manage 10fps should see the helix in the same place as one
+        // which manages the 50fps that it asks for, apart from rounding
+        let on_time = frame_after_one_second(Duration::from_millis(20));
+        let slow = frame_after_one_second(Duration::from_millis(100));
+        assert!(
+            on_time
+                .iter()
+                .flatten()
+                .zip(slow.iter().flatten())
+                .all(|(&a, &b)| a.abs_diff(b) <= 1),
+            "the helix should rotate at the same speed at any frame rate"
+        );
+    }
+}
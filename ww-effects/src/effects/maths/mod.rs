@@ -1,10 +1,9 @@
 //! This module contains purely mathematical effects.
 
+pub mod helix;
 pub mod moving_plane;
 pub mod split_plane;
 
-#[cfg(feature = "effect-impls")]
-pub use self::{moving_plane::MovingPlane, split_plane::SplitPlane};
-
-#[cfg(feature = "config-impls")]
-pub use self::{moving_plane::MovingPlaneConfig, split_plane::SplitPlaneConfig};
+// The effects in these modules are found by the build script, which re-exports them along with
+// their configs
+include!(concat!(env!("OUT_DIR"), "/effects/maths.rs"));
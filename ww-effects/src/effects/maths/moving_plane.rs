@@ -15,16 +15,20 @@ mod config {
 
     /// The config for the [`MovingPlane`] effect; includes speed.
     #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, BaseEffectConfig)]
+    #[serde(default)]
     pub struct MovingPlaneConfig {
         /// How many units (in GIFT coords) that the plane moves in one second.
+        #[schema(range = 0.0..=1.0)]
         pub units_per_second: f32,
 
         /// The thickness of the plane.
+        #[schema(range = 0.0..=0.5)]
         pub thickness: f32,
 
         /// The maximum distance where colour drops to zero.
         ///
         /// See [`ww_frame::FrameObject::fadeoff`].
+        #[schema(range = 0.0..=0.25)]
         pub fadeoff: f32,
     }
 
@@ -39,23 +43,50 @@ mod config {
     }
 
     impl EffectConfig for MovingPlaneConfig {
+        fn factory_presets() -> Vec<(&'static str, Self)> {
+            vec![
+                (
+                    "Slow scan",
+                    Self {
+                        units_per_second: 0.03,
+                        thickness: 0.05,
+                        ..Self::default()
+                    },
+                ),
+                (
+                    "Wide",
+                    Self {
+                        thickness: 0.3,
+                        fadeoff: 0.2,
+                        ..Self::default()
+                    },
+                ),
+            ]
+        }
+
         fn render_options_gui(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui) -> bool {
+            let schema = Self::schema();
             let mut config_changed = false;
 
             config_changed |= ui
                 .add(
-                    egui::Slider::new(&mut self.units_per_second, 0.0..=1.0)
+                    schema
+                        .slider("units_per_second", &mut self.units_per_second)
                         .suffix("/s")
                         .text("Speed (units/s)"),
                 )
                 .changed();
 
             config_changed |= ui
-                .add(egui::Slider::new(&mut self.thickness, 0.0..=0.5).text("Thickness"))
+                .add(
+                    schema
+                        .slider("thickness", &mut self.thickness)
+                        .text("Thickness"),
+                )
                 .changed();
 
             config_changed |= ui
-                .add(egui::Slider::new(&mut self.fadeoff, 0.0..=0.25).text("Fadeoff"))
+                .add(schema.slider("fadeoff", &mut self.fadeoff).text("Fadeoff"))
                 .changed();
 
             config_changed
@@ -71,6 +102,7 @@ mod effect {
 
     /// Move a plane through the tree at a random angle with a random colour.
     #[derive(Clone, Debug, PartialEq, BaseEffect)]
+    #[effect(tags = ["plane", "geometry"], unattended)]
     pub struct MovingPlane {
         /// The colour of this plane.
         colour: RGBArray,
@@ -147,7 +179,15 @@ mod effect {
             }
         }
 
-        fn next_frame(&mut self, config: &MovingPlaneConfig) -> Option<(FrameType, Duration)> {
+        fn next_frame(
+            &mut self,
+            config: &MovingPlaneConfig,
+            ctx: &FrameContext,
+        ) -> Option<(FrameType, Duration)> {
+            // Move by however long it's actually been since the last frame, so that the speed
+            // doesn't depend on the frame rate
+            self.position += (config.units_per_second * ctx.delta_secs()) * self.normal_vector;
+
             let mut frame = generate_frame(self.position, self.colour, self.normal_vector, config);
 
             frame.compute_raw_data();
@@ -157,10 +197,6 @@ mod effect {
                 .iter()
                 .all(|colour| colour == &[0; 3]);
 
-            // We're going to sleep for 20ms every loop, which gives 50 fps. This means we
-            // want to move 1/50th of the units per second
-            self.position += (config.units_per_second / 50.) * self.normal_vector;
-
             if self.in_start_phase && !all_lights_are_off {
                 self.in_start_phase = false;
             }
@@ -169,7 +205,7 @@ mod effect {
                 return None;
             }
 
-            Some((FrameType::Frame3D(frame), Duration::from_millis(20)))
+            Some((FrameType::Frame3D(frame), ctx.target_frame_time()))
         }
 
         #[cfg(any(test, feature = "bench"))]
@@ -15,6 +15,7 @@ mod config {
 
     /// The config for the [`SplitPlane`] effect.
     #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, BaseEffectConfig)]
+    #[serde(default)]
     pub struct SplitPlaneConfig {
         /// The colour of one side of the split plane.
         pub side_a_colour: RGBArray,
@@ -25,15 +26,18 @@ mod config {
         /// The level of blending between the two colours.
         ///
         /// See [`ww_frame::object::Object::SplitPlane::blend`](../../../../../ww_frame/object/enum.Object.html#variant.SplitPlane.field.blend).
+        #[schema(range = 0.0..=0.2, unclamped)]
         pub colour_blend: f32,
 
         /// The speed of rotation around the axis of rotation, measured in radians per second in
         /// the anti-clockwise direction.
+        #[schema(range = -3.0..=3.0, unclamped)]
         pub rotation_speed: f32,
 
         /// The axis of rotation starts as the x-axis, centered vertically in the middle of the
         /// tree. This variable rotates that axis of rotation about the z-axis. Measured in
         /// degrees.
+        #[schema(range = 0.0..=360.0)]
         pub rotation_axis_z_rotation_degrees: f32,
 
         /// The rotation axis is always parallel to the floor. When this value is 0, the middle of
@@ -42,10 +46,12 @@ mod config {
         /// coordinate space, so a distance of 1 is the radius of the base of the tree.
         ///
         /// This value has no effect when the vertical oscillation period is 0.
+        #[schema(range = -2.0..=2.0, unclamped)]
         pub rotation_axis_initial_height_offset: f32,
 
         /// The vertical movement speed of the rotation axis, measured in GIFT coordinate units
         /// per second.
+        #[schema(range = 0.0..=2., unclamped)]
         pub rotation_axis_vertical_oscillation_speed: f32,
     }
 
@@ -64,13 +70,35 @@ mod config {
     }
 
     impl EffectConfig for SplitPlaneConfig {
+        fn factory_presets() -> Vec<(&'static str, Self)> {
+            vec![
+                (
+                    "Fire and ice",
+                    Self {
+                        side_a_colour: [255, 90, 0],
+                        side_b_colour: [60, 160, 255],
+                        ..Self::default()
+                    },
+                ),
+                (
+                    "Spinning",
+                    Self {
+                        rotation_speed: 2.,
+                        colour_blend: 0.,
+                        ..Self::default()
+                    },
+                ),
+            ]
+        }
+
         fn render_options_gui(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui) -> bool {
+            let schema = Self::schema();
             let mut config_changed = false;
 
             config_changed |= ui
                 .add(
-                    egui::Slider::new(&mut self.colour_blend, 0.0..=0.2)
-                        .clamp_to_range(false)
+                    schema
+                        .slider("colour_blend", &mut self.colour_blend)
                         .text("Colour blend"),
                 )
                 .changed();
@@ -80,16 +108,20 @@ mod config {
 
             config_changed |= ui
                 .add(
-                    egui::Slider::new(&mut self.rotation_speed, -3.0..=3.0)
+                    schema
+                        .slider("rotation_speed", &mut self.rotation_speed)
                         .suffix("rad/s")
-                        .clamp_to_range(false)
                         .text("Rotation speed"),
                 )
                 .changed();
 
             config_changed |= ui
                 .add(
-                    egui::Slider::new(&mut self.rotation_axis_z_rotation_degrees, 0.0..=360.0)
+                    schema
+                        .slider(
+                            "rotation_axis_z_rotation_degrees",
+                            &mut self.rotation_axis_z_rotation_degrees,
+                        )
                         .suffix("°")
                         .text("Rotation axis z rotation"),
                 )
@@ -97,17 +129,23 @@ mod config {
 
             config_changed |= ui
                 .add(
-                    egui::Slider::new(&mut self.rotation_axis_initial_height_offset, -2.0..=2.0)
-                        .clamp_to_range(false)
+                    schema
+                        .slider(
+                            "rotation_axis_initial_height_offset",
+                            &mut self.rotation_axis_initial_height_offset,
+                        )
                         .text("Rotation axis initial height offset"),
                 )
                 .changed();
 
             config_changed |= ui
                 .add(
-                    egui::Slider::new(&mut self.rotation_axis_vertical_oscillation_speed, 0.0..=2.)
+                    schema
+                        .slider(
+                            "rotation_axis_vertical_oscillation_speed",
+                            &mut self.rotation_axis_vertical_oscillation_speed,
+                        )
                         .suffix("units/s")
-                        .clamp_to_range(false)
                         .text("Rotation axis vertical oscillation speed"),
                 )
                 .changed();
@@ -132,6 +170,7 @@ mod effect {
 
     /// Spin a split plane around a point in the center of the tree.
     #[derive(Clone, Debug, PartialEq, BaseEffect)]
+    #[effect(tags = ["plane", "geometry", "rotation"], unattended)]
     pub struct SplitPlane {
         /// The height of the center of the plane.
         height: f32,
@@ -155,29 +194,14 @@ mod effect {
             }
         }
 
-        fn next_frame(&mut self, config: &SplitPlaneConfig) -> Option<(FrameType, Duration)> {
-            let normal =
-                Quat::from_rotation_z(config.rotation_axis_z_rotation_degrees.to_radians())
-                    * Quat::from_rotation_x(self.angle)
-                    * Vec3::Y;
-
-            let frame = FrameType::Frame3D(Frame3D::new(
-                vec![FrameObject {
-                    object: Object::SplitPlane {
-                        normal,
-                        k: normal.dot(Vec3::new(0., 0., self.height)),
-                        blend: config.colour_blend,
-                        positive_side_colour: config.side_a_colour,
-                        negative_side_colour: config.side_b_colour,
-                    },
-                    colour: [0; 3],
-                    fadeoff: 0.,
-                }],
-                false,
-            ));
-
-            // Update height
-            let delta = config.rotation_axis_vertical_oscillation_speed / 50.;
+        fn next_frame(
+            &mut self,
+            config: &SplitPlaneConfig,
+            ctx: &FrameContext,
+        ) -> Option<(FrameType, Duration)> {
+            // Update height, moving by however long it's actually been since the last frame so
+            // that the speed doesn't depend on the frame rate
+            let delta = config.rotation_axis_vertical_oscillation_speed * ctx.delta_secs();
 
             if self.going_up {
                 self.height += delta;
@@ -198,12 +222,32 @@ mod effect {
             }
 
             // Update angle
-            self.angle += config.rotation_speed / 50.;
+            self.angle = config.rotation_speed.mul_add(ctx.delta_secs(), self.angle);
             if self.angle > TAU {
                 self.angle = 0.;
             }
 
-            Some((frame, Duration::from_millis(20)))
+            let normal =
+                Quat::from_rotation_z(config.rotation_axis_z_rotation_degrees.to_radians())
+                    * Quat::from_rotation_x(self.angle)
+                    * Vec3::Y;
+
+            let frame = FrameType::Frame3D(Frame3D::new(
+                vec![FrameObject {
+                    object: Object::SplitPlane {
+                        normal,
+                        k: normal.dot(Vec3::new(0., 0., self.height)),
+                        blend: config.colour_blend,
+                        positive_side_colour: config.side_a_colour,
+                        negative_side_colour: config.side_b_colour,
+                    },
+                    colour: [0; 3],
+                    fadeoff: 0.,
+                }],
+                false,
+            ));
+
+            Some((frame, ctx.target_frame_time()))
         }
 
         #[cfg(any(test, feature = "bench"))]
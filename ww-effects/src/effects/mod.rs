@@ -16,15 +16,15 @@
 #[cfg(doc)]
 use crate::traits::{Effect, EffectConfig};
 
-/// Create a `rand::rngs::StdRng` from entropy in a normal build, or seeded from 12345 in a test or
-/// bench build.
+/// Create a `rand::rngs::StdRng` from entropy in a normal build, or seeded from 12345 in a test build
+/// or with the `deterministic` feature.
 #[cfg(feature = "effect-impls")]
 macro_rules! rng {
     () => {{
         use ::rand::{SeedableRng, rngs::StdRng};
 
         cfg_if::cfg_if! {
-            if #[cfg(any(test, feature = "bench"))] {
+            if #[cfg(any(test, feature = "deterministic"))] {
                 StdRng::seed_from_u64(12345)
             } else {
                 StdRng::from_os_rng()
@@ -48,7 +48,7 @@ pub(crate) mod prelude {
         reason = "this is a prelude module and everything is pub used"
     )]
     pub mod config_prelude {
-        pub use crate::traits::EffectConfig;
+        pub use crate::traits::{BaseEffectConfig as _, EffectConfig};
         pub use effect_proc_macros::BaseEffectConfig;
         pub use serde::{Deserialize, Serialize};
 
@@ -74,6 +74,43 @@ pub(crate) mod prelude {
             )
             .inner
         }
+
+        /// Display an editable list of colours, with buttons to add and remove colours. Return
+        /// whether the list was changed.
+        ///
+        /// The last colour in the list cannot be removed, so the list will never become empty.
+        pub fn palette_editor(
+            ui: &mut egui::Ui,
+            palette: &mut Vec<RGBArray>,
+            label: impl Into<egui::WidgetText>,
+        ) -> bool {
+            let mut changed = false;
+            let mut index_to_remove = None;
+
+            ui.label(label);
+            for (idx, colour) in palette.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    changed |= ui.color_edit_button_srgb(colour).changed();
+                    if ui.button("Remove").clicked() {
+                        index_to_remove = Some(idx);
+                    }
+                });
+            }
+
+            if let Some(idx) = index_to_remove
+                && palette.len() > 1
+            {
+                palette.remove(idx);
+                changed = true;
+            }
+
+            if ui.button("Add colour").clicked() {
+                palette.push(palette.last().copied().unwrap_or([255; 3]));
+                changed = true;
+            }
+
+            changed
+        }
     }
 
     #[cfg(feature = "config-impls")]
@@ -87,10 +124,20 @@ pub(crate) mod prelude {
     )]
     pub mod effect_prelude {
         pub(crate) use crate::{
-            effects::rng,
+            effects::{
+                rng,
+                utils::{
+                    colour::{
+                        add_colours, colour_temperature, hue_colour, lerp_colour, palette_colour,
+                        random_palette_colour, sample_gradient, scale_colour,
+                    },
+                    noise::SimplexNoise,
+                },
+            },
+            timing::{FrameContext, StepSchedule},
             traits::{BaseEffect, Effect},
         };
-        pub use effect_proc_macros::{BaseEffect, end_loop_in_test_or_bench};
+        pub use effect_proc_macros::BaseEffect;
         pub use glam::{Quat, Vec3};
         pub use rand::{Rng, rngs::StdRng};
         pub use std::time::Duration;
@@ -112,6 +159,8 @@ pub mod computational;
 pub mod debug;
 pub mod maths;
 
+pub(crate) mod utils;
+
 #[cfg(feature = "effect-impls")]
 pub use self::effects::*;
 
@@ -122,12 +171,7 @@ pub use self::effects::*;
 )]
 #[cfg(feature = "effect-impls")]
 pub mod effects {
-    pub use super::{
-        aesthetic::LavaLamp,
-        computational::AiSnake,
-        debug::{DebugBinaryIndex, DebugOneByOne},
-        maths::{MovingPlane, SplitPlane},
-    };
+    include!(concat!(env!("OUT_DIR"), "/effects/effects.rs"));
 }
 
 #[cfg(feature = "config-impls")]
@@ -136,10 +180,5 @@ pub use self::configs::*;
 /// This module re-exports all the [`EffectConfig`] implementors.
 #[cfg(feature = "config-impls")]
 pub mod configs {
-    pub use super::{
-        aesthetic::LavaLampConfig,
-        computational::AiSnakeConfig,
-        debug::{DebugBinaryIndexConfig, DebugOneByOneConfig},
-        maths::{MovingPlaneConfig, SplitPlaneConfig},
-    };
+    include!(concat!(env!("OUT_DIR"), "/effects/configs.rs"));
 }
@@ -0,0 +1,64 @@
+//! This module provides a clock for effects that show the real time, which is simulated in tests
+//! and with the `deterministic` feature so that their output doesn't depend on when they're run.
+
+use chrono::{Local, NaiveDateTime};
+use std::time::Duration;
+
+/// A source of the current local time.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Clock {
+    /// The real system clock.
+    #[cfg_attr(
+        any(test, feature = "deterministic"),
+        allow(
+            dead_code,
+            reason = "deterministic builds always use a simulated clock"
+        )
+    )]
+    System,
+
+    /// A simulated clock which only moves forwards when told to.
+    #[cfg_attr(
+        not(any(test, feature = "deterministic")),
+        allow(dead_code, reason = "only deterministic builds use a simulated clock")
+    )]
+    Simulated(NaiveDateTime),
+}
+
+impl Default for Clock {
+    /// Use the system clock normally, or a simulated clock which starts at a fixed time in test
+    /// builds and with the `deterministic` feature.
+    fn default() -> Self {
+        cfg_if::cfg_if! {
+            if #[cfg(any(test, feature = "deterministic"))] {
+                Self::Simulated(
+                    chrono::NaiveDate::from_ymd_opt(2023, 12, 1)
+                        .and_then(|date| date.and_hms_opt(18, 30, 0))
+                        .unwrap_or_default(),
+                )
+            } else {
+                Self::System
+            }
+        }
+    }
+}
+
+impl Clock {
+    /// Get the current local time.
+    pub fn now(&self) -> NaiveDateTime {
+        match self {
+            Self::System => Local::now().naive_local(),
+            Self::Simulated(now) => *now,
+        }
+    }
+
+    /// Tell the clock that some time has passed. This only affects simulated clocks, since the
+    /// system clock keeps moving by itself.
+    pub fn advance(&mut self, elapsed: Duration) {
+        if let Self::Simulated(now) = self
+            && let Ok(elapsed) = chrono::Duration::from_std(elapsed)
+        {
+            *now += elapsed;
+        }
+    }
+}
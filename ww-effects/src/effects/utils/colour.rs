@@ -0,0 +1,126 @@
+//! This module provides simple functions for manipulating colours.
+
+use rand::Rng;
+use ww_frame::RGBArray;
+
+/// Scale the brightness of the colour by the given factor, saturating at 0 and 255.
+pub fn scale_colour(colour: RGBArray, factor: f32) -> RGBArray {
+    let [r, g, b] = colour;
+    [
+        (r as f32 * factor).clamp(0., 255.) as u8,
+        (g as f32 * factor).clamp(0., 255.) as u8,
+        (b as f32 * factor).clamp(0., 255.) as u8,
+    ]
+}
+
+/// Add two colours together channel by channel, saturating at 255. This is how overlapping lights
+/// mix.
+pub fn add_colours(first: RGBArray, second: RGBArray) -> RGBArray {
+    let [r1, g1, b1] = first;
+    let [r2, g2, b2] = second;
+    [
+        r1.saturating_add(r2),
+        g1.saturating_add(g2),
+        b1.saturating_add(b2),
+    ]
+}
+
+/// Linearly interpolate between two colours. A `t` of 0 gives `start` and a `t` of 1 gives `end`.
+///
+/// `t` is clamped to the range `[0, 1]`.
+pub fn lerp_colour(start: RGBArray, end: RGBArray, t: f32) -> RGBArray {
+    let t = t.clamp(0., 1.);
+    let [sr, sg, sb] = start;
+    let [er, eg, eb] = end;
+    [
+        (er as f32 - sr as f32).mul_add(t, sr as f32) as u8,
+        (eg as f32 - sg as f32).mul_add(t, sg as f32) as u8,
+        (eb as f32 - sb as f32).mul_add(t, sb as f32) as u8,
+    ]
+}
+
+/// Sample a gradient made of evenly spaced colour stops at position `t`, where 0 is the first
+/// stop and 1 is the last stop. `t` is clamped to the range `[0, 1]`.
+///
+/// An empty gradient is black everywhere.
+pub fn sample_gradient(stops: &[RGBArray], t: f32) -> RGBArray {
+    match stops {
+        [] => [0; 3],
+        [colour] => *colour,
+        _ => {
+            let scaled = t.clamp(0., 1.) * (stops.len() - 1) as f32;
+            let idx = (scaled.floor() as usize).min(stops.len() - 2);
+            lerp_colour(stops[idx], stops[idx + 1], scaled - idx as f32)
+        }
+    }
+}
+
+/// Get the colour at the given index of a palette, wrapping around at the end of the palette.
+///
+/// An empty palette is white everywhere.
+pub fn palette_colour(palette: &[RGBArray], idx: usize) -> RGBArray {
+    if palette.is_empty() {
+        [255; 3]
+    } else {
+        palette[idx % palette.len()]
+    }
+}
+
+/// Pick a random colour from a palette. An empty palette always gives white, without using the
+/// RNG.
+pub fn random_palette_colour(rng: &mut impl Rng, palette: &[RGBArray]) -> RGBArray {
+    if palette.is_empty() {
+        [255; 3]
+    } else {
+        palette[rng.random_range(0..palette.len())]
+    }
+}
+
+/// Get the fully saturated, fully bright colour with the given hue, where 0 and 1 are both red.
+/// Hues outside the range `[0, 1]` wrap around.
+pub fn hue_colour(hue: f32) -> RGBArray {
+    let hue = hue.rem_euclid(1.) * 6.;
+    let channel = |offset: f32| {
+        let distance = (hue - offset).rem_euclid(6.);
+        let distance = distance.min(6. - distance);
+        ((2. - distance).clamp(0., 1.) * 255.) as u8
+    };
+
+    [channel(0.), channel(2.), channel(4.)]
+}
+
+/// Approximate the colour of a black body at the given temperature in Kelvin, which is how the
+/// "warmth" of white lights is usually described. Candlelight is about 1900K, a traditional
+/// incandescent bulb is about 2700K, and daylight is about 6500K.
+///
+/// This uses Tanner Helland's curve fit, which is accurate enough for temperatures between 1000K
+/// and 40000K.
+pub fn colour_temperature(kelvin: f32) -> RGBArray {
+    let temp = kelvin.clamp(1000., 40_000.) / 100.;
+
+    let red = if temp <= 66. {
+        255.
+    } else {
+        329.698_73 * (temp - 60.).powf(-0.133_204_76)
+    };
+
+    let green = if temp <= 66. {
+        99.470_8_f32.mul_add(temp.ln(), -161.119_57)
+    } else {
+        288.122_17 * (temp - 60.).powf(-0.075_514_85)
+    };
+
+    let blue = if temp >= 66. {
+        255.
+    } else if temp <= 19. {
+        0.
+    } else {
+        138.517_73_f32.mul_add((temp - 10.).ln(), -305.044_8)
+    };
+
+    [
+        red.clamp(0., 255.) as u8,
+        green.clamp(0., 255.) as u8,
+        blue.clamp(0., 255.) as u8,
+    ]
+}
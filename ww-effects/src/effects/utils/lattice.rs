@@ -0,0 +1,185 @@
+//! This module provides a regular lattice of points that follows the shape of the lights, for
+//! effects that move things around on a grid.
+
+use ordered_float::NotNan;
+use rand::Rng;
+use tracing::{info, instrument, trace};
+use tracing_unwrap::ResultExt;
+use ww_gift_coords::GIFTCoords;
+
+/// A coordinate in lattice space.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct Coord(pub i8, pub i8, pub i8);
+
+impl Coord {
+    /// Check if this coordinate is orthogonally adjacent to another one.
+    pub fn is_orthogonal(self, other: Self) -> bool {
+        let diffs = (
+            self.0.abs_diff(other.0),
+            self.1.abs_diff(other.1),
+            self.2.abs_diff(other.2),
+        );
+        diffs == (1, 0, 0) || diffs == (0, 1, 0) || diffs == (0, 0, 1)
+    }
+
+    /// Check if this coordinate is diagonally adjacent to another one.
+    pub fn is_diagonal(self, other: Self) -> bool {
+        let diffs = (
+            self.0.abs_diff(other.0),
+            self.1.abs_diff(other.1),
+            self.2.abs_diff(other.2),
+        );
+        diffs == (1, 1, 0) || diffs == (1, 0, 1) || diffs == (0, 1, 1) || diffs == (1, 1, 1)
+    }
+
+    /// Get the six coordinates that are orthogonally adjacent to this one.
+    pub fn orthogonal_neighbours(self) -> [Self; 6] {
+        let Self(x, y, z) = self;
+        [
+            Self(x + 1, y, z),
+            Self(x - 1, y, z),
+            Self(x, y + 1, z),
+            Self(x, y - 1, z),
+            Self(x, y, z + 1),
+            Self(x, y, z - 1),
+        ]
+    }
+
+    /// Convert the coordinate to a GIFT coordinate.
+    pub fn to_gift(self, cell_width: f32) -> (f32, f32, f32) {
+        (
+            self.0 as f32 * cell_width,
+            self.1 as f32 * cell_width,
+            self.2 as f32 * cell_width,
+        )
+    }
+
+    /// Calculate the Euclidean distance between this coordinate and another one.
+    pub fn distance(self, other: Self) -> NotNan<f32> {
+        let (dx, dy, dz) = (
+            self.0 as f32 - other.0 as f32,
+            self.1 as f32 - other.1 as f32,
+            self.2 as f32 - other.2 as f32,
+        );
+        NotNan::new(f32::sqrt(dx.mul_add(dx, dy.mul_add(dy, dz * dz))))
+            .expect_or_log("Euclidean distance of lattice points should never be NaN")
+    }
+}
+
+/// A collection of valid lattice points.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lattice {
+    /// The points in the lattice.
+    pub points: Box<[Coord]>,
+
+    /// The width of each cell in GIFT coord units.
+    pub cell_width: f32,
+}
+
+impl Lattice {
+    /// Create a new lattice with the given number of points across the diameter of the bottom of
+    /// the tree, keeping only the points that are close to a light.
+    #[instrument(skip_all)]
+    pub fn new(coords: &GIFTCoords, points_across_diameter: u8) -> Self {
+        let bound = (points_across_diameter / 2) as i8;
+        let range = -bound..=bound;
+        let square: Box<[_]> = range
+            .clone()
+            .flat_map(|x| range.clone().map(move |y| (x, y)))
+            .collect();
+
+        let max_vertical: i8 =
+            (coords.max_z() / (2.0 / (points_across_diameter as f32))).floor() as i8;
+        let z_range = 0..=max_vertical;
+
+        let cell_width = 2.0 / points_across_diameter as f32;
+
+        let points: Box<[Coord]> = z_range
+            .into_iter()
+            .flat_map(|z| square.iter().map(move |(x, y)| Coord(*x, *y, z)))
+            .filter(|coord| {
+                let (px, py, pz) = coord.to_gift(cell_width);
+                coords.coords().iter().any(|&(lx, ly, lz)| {
+                    let dx = px - lx;
+                    let dy = py - ly;
+                    let dz = pz - lz;
+
+                    let dist = f32::sqrt(dx.mul_add(dx, dy.mul_add(dy, dz * dz)));
+
+                    dist <= cell_width / 2.0
+                })
+            })
+            .collect();
+
+        info!(num_points = points.len(), "Created lattice");
+
+        Self {
+            points,
+            cell_width: 2.0 / points_across_diameter as f32,
+        }
+    }
+
+    /// Get a random point from the lattice.
+    pub fn random_point<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<Coord> {
+        let idx = rng.random_range(0..self.points.len());
+        self.points.get(idx).copied()
+    }
+
+    /// Find the shortest path between two points on the lattice
+    pub fn shortest_path(
+        &self,
+        start: Coord,
+        end: Coord,
+        avoid: &[Coord],
+        allow_diagonals: bool,
+    ) -> Option<Vec<Coord>> {
+        debug_assert!(
+            self.points.contains(&start),
+            "Start point must be on lattice"
+        );
+        debug_assert!(self.points.contains(&end), "End point must be on lattice");
+
+        let path = pathfinding::directed::dijkstra::dijkstra(
+            &start,
+            |&coord| {
+                let Coord(x, y, z) = coord;
+                coord
+                    .orthogonal_neighbours()
+                    .into_iter()
+                    .chain(if allow_diagonals {
+                        vec![
+                            Coord(x + 1, y + 1, z),
+                            Coord(x + 1, y, z + 1),
+                            Coord(x, y + 1, z + 1),
+                            Coord(x + 1, y - 1, z),
+                            Coord(x + 1, y, z - 1),
+                            Coord(x, y + 1, z - 1),
+                            Coord(x - 1, y + 1, z),
+                            Coord(x - 1, y, z + 1),
+                            Coord(x, y - 1, z + 1),
+                            Coord(x - 1, y - 1, z),
+                            Coord(x - 1, y, z - 1),
+                            Coord(x, y - 1, z - 1),
+                            Coord(x + 1, y + 1, z + 1),
+                            Coord(x + 1, y + 1, z - 1),
+                            Coord(x + 1, y - 1, z + 1),
+                            Coord(x + 1, y - 1, z - 1),
+                            Coord(x - 1, y + 1, z + 1),
+                            Coord(x - 1, y + 1, z - 1),
+                            Coord(x - 1, y - 1, z + 1),
+                            Coord(x - 1, y - 1, z - 1),
+                        ]
+                        .into_iter()
+                    } else {
+                        vec![].into_iter()
+                    })
+                    .filter(|coord| self.points.contains(coord) && !avoid.contains(coord))
+                    .map(move |neighbour| (neighbour, coord.distance(neighbour)))
+            },
+            |pos| pos == &end,
+        )
+        .map(|(path, _dist)| path);
+        trace!(?start, ?end, ?path);
+        path
+    }
+}
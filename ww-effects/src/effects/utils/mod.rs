@@ -0,0 +1,13 @@
+//! This module contains small utilities that are shared between several effect implementations.
+
+#[cfg(feature = "effect-impls")]
+pub mod clock;
+
+#[cfg(feature = "effect-impls")]
+pub mod colour;
+
+#[cfg(feature = "effect-impls")]
+pub mod lattice;
+
+#[cfg(feature = "effect-impls")]
+pub mod noise;
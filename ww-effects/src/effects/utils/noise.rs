@@ -0,0 +1,184 @@
+//! This module provides gradient noise that can be sampled anywhere in space and time.
+//!
+//! The implementation of [`SimplexNoise::noise4`] follows Stefan Gustavson's paper [Simplex noise
+//! demystified](https://itn-web.it.liu.se/~stegu76/TNM084-2017/simplexnoise.pdf).
+
+use glam::Vec4;
+use rand::{Rng, seq::SliceRandom};
+
+/// The skewing factor for 4D simplex noise, `(sqrt(5) - 1) / 4`.
+const F4: f32 = 0.309_017;
+
+/// The unskewing factor for 4D simplex noise, `(5 - sqrt(5)) / 20`.
+const G4: f32 = 0.138_196_6;
+
+/// The gradients used for 4D noise, which are the midpoints of the edges of a 4D hypercube.
+const GRAD4: [Vec4; 32] = {
+    /// Shorthand for [`Vec4::new`].
+    const fn v(x: f32, y: f32, z: f32, w: f32) -> Vec4 {
+        Vec4::new(x, y, z, w)
+    }
+
+    [
+        v(0., 1., 1., 1.),
+        v(0., 1., 1., -1.),
+        v(0., 1., -1., 1.),
+        v(0., 1., -1., -1.),
+        v(0., -1., 1., 1.),
+        v(0., -1., 1., -1.),
+        v(0., -1., -1., 1.),
+        v(0., -1., -1., -1.),
+        v(1., 0., 1., 1.),
+        v(1., 0., 1., -1.),
+        v(1., 0., -1., 1.),
+        v(1., 0., -1., -1.),
+        v(-1., 0., 1., 1.),
+        v(-1., 0., 1., -1.),
+        v(-1., 0., -1., 1.),
+        v(-1., 0., -1., -1.),
+        v(1., 1., 0., 1.),
+        v(1., 1., 0., -1.),
+        v(1., -1., 0., 1.),
+        v(1., -1., 0., -1.),
+        v(-1., 1., 0., 1.),
+        v(-1., 1., 0., -1.),
+        v(-1., -1., 0., 1.),
+        v(-1., -1., 0., -1.),
+        v(1., 1., 1., 0.),
+        v(1., 1., -1., 0.),
+        v(1., -1., 1., 0.),
+        v(1., -1., -1., 0.),
+        v(-1., 1., 1., 0.),
+        v(-1., 1., -1., 0.),
+        v(-1., -1., 1., 0.),
+        v(-1., -1., -1., 0.),
+    ]
+};
+
+/// A source of simplex noise, defined by a random permutation table.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SimplexNoise {
+    /// A random permutation of `0..=255`, repeated twice to avoid wrapping indices.
+    perm: Box<[u8; 512]>,
+}
+
+impl SimplexNoise {
+    /// Create a new noise source with a permutation table shuffled by the given RNG.
+    pub fn new<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        let mut table: [u8; 256] = std::array::from_fn(|idx| idx as u8);
+        table.shuffle(rng);
+
+        Self {
+            perm: Box::new(std::array::from_fn(|idx| table[idx % 256])),
+        }
+    }
+
+    /// Look up the permutation table.
+    fn perm(&self, idx: usize) -> usize {
+        self.perm[idx] as usize
+    }
+
+    /// Get the index of the gradient for the simplex corner with the given lattice coordinates.
+    fn gradient_index(&self, [i, j, k, l]: [usize; 4]) -> usize {
+        self.perm(i + self.perm(j + self.perm(k + self.perm(l)))) % 32
+    }
+
+    /// Sample 4D simplex noise at the given point. The result is roughly in the range `[-1, 1]`.
+    pub fn noise4(&self, point: Vec4) -> f32 {
+        // Skew the input space to work out which simplex cell we're in
+        let skew = point.element_sum() * F4;
+        let cell = (point + skew).floor();
+        let unskew = cell.element_sum() * G4;
+
+        // The distance from the cell origin
+        let d0 = point - (cell - unskew);
+
+        // Rank the components of d0 to work out which of the 24 simplices we're in. The simplex
+        // is traversed from the origin by stepping along the axes in order of decreasing size
+        let mut rank = [0_u8; 4];
+        for a in 0..4 {
+            for b in (a + 1)..4 {
+                if d0[a] > d0[b] {
+                    rank[a] += 1;
+                } else {
+                    rank[b] += 1;
+                }
+            }
+        }
+
+        let step = |threshold: u8| -> [usize; 4] { rank.map(|r| usize::from(r >= threshold)) };
+        let offsets = [[0; 4], step(3), step(2), step(1), [1; 4]];
+
+        // Wrap the cell coordinates to index the permutation table
+        let lattice = cell.to_array().map(|c| (c as i32 & 255) as usize);
+
+        offsets
+            .into_iter()
+            .enumerate()
+            .map(|(corner, offset)| {
+                let d = d0 - Vec4::from_array(offset.map(|o| o as f32)) + corner as f32 * G4;
+
+                let t = 0.6 - d.length_squared();
+                if t < 0. {
+                    0.
+                } else {
+                    let gradient = GRAD4[self.gradient_index([
+                        lattice[0] + offset[0],
+                        lattice[1] + offset[1],
+                        lattice[2] + offset[2],
+                        lattice[3] + offset[3],
+                    ])];
+                    t * t * t * t * gradient.dot(d)
+                }
+            })
+            .sum::<f32>()
+            * 27.
+    }
+
+    /// Sample several octaves of 4D simplex noise at the given point, with each octave at double
+    /// the frequency and half the amplitude of the previous one. The result is normalised to be
+    /// roughly in the range `[-1, 1]`.
+    pub fn fractal_noise4(&self, point: Vec4, octaves: u8) -> f32 {
+        let mut total = 0_f32;
+        let mut amplitude = 1_f32;
+        let mut max_amplitude = 0.;
+        let mut frequency = 1.;
+
+        for _ in 0..octaves.max(1) {
+            total = amplitude.mul_add(self.noise4(point * frequency), total);
+            max_amplitude += amplitude;
+            amplitude *= 0.5;
+            frequency *= 2.;
+        }
+
+        total / max_amplitude
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{SeedableRng, rngs::StdRng};
+
+    #[test]
+    fn noise4_is_bounded_and_continuous() {
+        let noise = SimplexNoise::new(&mut StdRng::seed_from_u64(12345));
+
+        for x in -20..20 {
+            for y in -20..20 {
+                let point = Vec4::new(x as f32 * 0.37, y as f32 * 0.29, 1.3, x as f32 * -0.11);
+                let value = noise.noise4(point);
+                let nearby = noise.noise4(point + Vec4::splat(0.001));
+
+                assert!(
+                    (-1.0..=1.0).contains(&value),
+                    "Noise value {value} at {point} should be in [-1, 1]"
+                );
+                assert!(
+                    (value - nearby).abs() < 0.05,
+                    "Noise should be continuous, but {value} at {point} and {nearby} nearby are too far apart"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,485 @@
+//! This module handles layering several effects on top of each other.
+//!
+//! The main effect is always the bottom layer. Each [`EffectLayer`] is drawn on top of it in
+//! order, with its own config, opacity, and [`BlendMode`]. With the `effect-impls` feature, a
+//! [`LayerStack`] runs all the effects together, calling each one's `next_frame` method according
+//! to its own returned [`Duration`] and blending the latest frames together.
+
+use crate::{
+    list::EffectConfigDispatchList,
+    modulation::{Modulation, modulate},
+};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use strum::EnumIter;
+use tracing::warn;
+
+#[cfg(feature = "effect-impls")]
+pub use self::stack::LayerStack;
+
+/// The colour of a single light. See [`ww_frame::RGBArray`](../../ww_frame/type.RGBArray.html).
+type RGBArray = [u8; 3];
+
+/// The most layers that can be drawn on top of the main effect, since each one runs its own effect.
+pub const MAX_LAYERS: usize = 8;
+
+/// How to combine a layer with the layers underneath it.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize, EnumIter)]
+pub enum BlendMode {
+    /// Cover the layers underneath, including with black.
+    Normal,
+
+    /// Add the colours together, saturating at full brightness.
+    Add,
+
+    /// Multiply the colours together, which only keeps light where both layers are lit.
+    Multiply,
+
+    /// Like [`Add`](Self::Add), but brightening less as the colours get brighter, so nothing gets
+    /// washed out to white. Black is transparent.
+    #[default]
+    Screen,
+
+    /// Take the brighter of the two colours in each channel. Black is transparent.
+    Lighten,
+
+    /// Take the darker of the two colours in each channel.
+    Darken,
+}
+
+impl BlendMode {
+    /// Get the name of this blend mode for the GUI.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Normal => "Normal",
+            Self::Add => "Add",
+            Self::Multiply => "Multiply",
+            Self::Screen => "Screen",
+            Self::Lighten => "Lighten",
+            Self::Darken => "Darken",
+        }
+    }
+
+    /// Blend the `top` colour onto the `bottom` colour with this blend mode. The `opacity` is
+    /// clamped to `[0, 1]` and fades between `bottom` (0) and the fully blended colour (1).
+    pub fn blend(self, bottom: RGBArray, top: RGBArray, opacity: f32) -> RGBArray {
+        let opacity = opacity.clamp(0., 1.);
+
+        std::array::from_fn(|i| {
+            let b = bottom[i] as f32 / 255.;
+            let t = top[i] as f32 / 255.;
+
+            let blended = match self {
+                Self::Normal => t,
+                Self::Add => (b + t).min(1.),
+                Self::Multiply => b * t,
+                Self::Screen => (1. - b).mul_add(-(1. - t), 1.),
+                Self::Lighten => b.max(t),
+                Self::Darken => b.min(t),
+            };
+
+            ((blended - b).mul_add(opacity, b) * 255.).round() as u8
+        })
+    }
+
+    /// Blend every light of `top` onto `bottom` in place. See [`Self::blend`].
+    pub fn blend_frames(self, bottom: &mut [RGBArray], top: &[RGBArray], opacity: f32) {
+        for (bottom, &top) in bottom.iter_mut().zip(top) {
+            *bottom = self.blend(*bottom, top, opacity);
+        }
+    }
+}
+
+/// A single effect drawn on top of the main effect.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EffectLayer {
+    /// The config of the effect in this layer, which also determines which effect it is.
+    pub config: EffectConfigDispatchList,
+
+    /// How opaque this layer is, from 0 (invisible) to 1 (fully blended).
+    pub opacity: f32,
+
+    /// How to combine this layer with the layers underneath it.
+    pub blend_mode: BlendMode,
+
+    /// The modulations applied to the config of this layer.
+    #[serde(default)]
+    pub modulations: Vec<Modulation>,
+}
+
+impl EffectLayer {
+    /// Create a new fully opaque layer with the default blend mode.
+    pub fn new(config: EffectConfigDispatchList) -> Self {
+        Self {
+            config,
+            opacity: 1.,
+            blend_mode: BlendMode::default(),
+            modulations: vec![],
+        }
+    }
+
+    /// Get a copy of this layer with its modulations applied to its config after the given time.
+    /// See [`modulate`].
+    pub fn modulated(&self, elapsed: Duration) -> Self {
+        Self {
+            config: modulate(&self.config, &self.modulations, elapsed),
+            opacity: self.opacity,
+            blend_mode: self.blend_mode,
+            modulations: self.modulations.clone(),
+        }
+    }
+
+    /// Clamp the config and opacity of this layer into range, logging a warning if either of them
+    /// was out of range. See [`EffectConfig::validate`](crate::traits::EffectConfig::validate).
+    pub fn clamp_to_ranges(&mut self) {
+        if let Err(errors) = self.config.validate() {
+            for error in errors {
+                warn!(%error, "Layer config is out of range, so clamping it");
+            }
+            self.config.clamp_to_ranges();
+        }
+
+        if !(0. ..=1.).contains(&self.opacity) {
+            warn!(
+                opacity = self.opacity,
+                "Layer opacity is out of range, so clamping it"
+            );
+            self.opacity = if self.opacity.is_nan() {
+                1.
+            } else {
+                self.opacity.clamp(0., 1.)
+            };
+        }
+    }
+}
+
+/// Contains the [`LayerStack`] which runs several effects at once.
+#[cfg(feature = "effect-impls")]
+mod stack {
+    use super::*;
+    use crate::{list::EffectDispatchList, timing::FrameClock};
+    use std::time::Instant;
+    use ww_frame::FrameType;
+    use ww_gift_coords::COORDS;
+
+    /// An effect along with its latest frame and when it next needs to be asked for a frame.
+    #[derive(Clone, Debug, PartialEq)]
+    struct ScheduledEffect {
+        /// The effect itself.
+        effect: EffectDispatchList,
+
+        /// When the effect started.
+        started: Instant,
+
+        /// The clock which tells the effect how much time has actually passed.
+        clock: FrameClock,
+
+        /// The most recent frame that the effect returned.
+        latest_frame: FrameType,
+
+        /// When the effect next needs to be asked for a frame.
+        due: Instant,
+    }
+
+    impl ScheduledEffect {
+        /// Create a new scheduled effect which is due immediately.
+        fn new(effect: EffectDispatchList, now: Instant) -> Self {
+            Self {
+                effect,
+                started: now,
+                clock: FrameClock::default(),
+                latest_frame: FrameType::Off,
+                due: now,
+            }
+        }
+
+        /// Get the next frame of the effect if it's due. Return false if the effect has finished.
+        fn advance(&mut self, config: &EffectConfigDispatchList, now: Instant) -> bool {
+            if now < self.due {
+                return true;
+            }
+
+            let ctx = self.clock.tick(now.duration_since(self.started));
+            let Some((frame, duration)) = self.effect.next_frame(config, &ctx) else {
+                return false;
+            };
+
+            self.latest_frame = frame;
+
+            // Schedule from when the frame was due rather than from now so that small delays
+            // don't accumulate, but don't try to catch up if we've fallen a long way behind
+            self.due = (self.due + duration).max(now);
+            true
+        }
+    }
+
+    /// Convert any frame into one colour for each light.
+    fn raw_data(frame: &FrameType) -> Vec<RGBArray> {
+        match frame {
+            FrameType::Off => vec![[0; 3]; COORDS.lights_num()],
+            FrameType::RawData(data) => {
+                let mut data = data.clone();
+                data.resize(COORDS.lights_num(), [0; 3]);
+                data
+            }
+            FrameType::Frame3D(frame) => frame.clone().to_raw_data(),
+        }
+    }
+
+    /// A stack of effects running at once, with the main effect at the bottom and the
+    /// [`EffectLayer`]s on top of it.
+    ///
+    /// Every effect runs at its own frame rate. Each call to [`next_frame`](Self::next_frame)
+    /// advances the effects which are due and blends together the latest frame from every effect.
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct LayerStack {
+        /// The main effect, which is the bottom layer.
+        base: ScheduledEffect,
+
+        /// The effects drawn on top of the main effect, in order from bottom to top.
+        layers: Vec<ScheduledEffect>,
+    }
+
+    impl LayerStack {
+        /// Create a new stack from the main effect and the layers to draw on top of it.
+        pub fn new(base: EffectDispatchList, layers: &[EffectLayer], now: Instant) -> Self {
+            Self {
+                base: ScheduledEffect::new(base, now),
+                layers: layers
+                    .iter()
+                    .map(|layer| ScheduledEffect::new(layer.config.clone().into(), now))
+                    .collect(),
+            }
+        }
+
+        /// Make the scheduled layers match the given layers, in case the client has added,
+        /// removed, reordered, or changed the effect of any layers since the stack was created.
+        /// Layers which are still running the same effect keep their state, and the others are
+        /// started from their configs.
+        fn resync(&mut self, layers: &[EffectLayer], now: Instant) {
+            let in_sync = self.layers.len() == layers.len()
+                && self.layers.iter().zip(layers).all(|(scheduled, layer)| {
+                    scheduled.effect.effect_name() == layer.config.effect_name()
+                });
+            if in_sync {
+                return;
+            }
+
+            let mut old_layers: Vec<_> = std::mem::take(&mut self.layers)
+                .into_iter()
+                .map(Some)
+                .collect();
+            self.layers = layers
+                .iter()
+                .map(|layer| {
+                    old_layers
+                        .iter_mut()
+                        .find(|old| {
+                            old.as_ref().is_some_and(|old| {
+                                old.effect.effect_name() == layer.config.effect_name()
+                            })
+                        })
+                        .and_then(Option::take)
+                        .unwrap_or_else(|| ScheduledEffect::new(layer.config.clone().into(), now))
+                })
+                .collect();
+        }
+
+        /// Advance every effect which is due at `now` and blend the latest frames together.
+        ///
+        /// The configs are passed in every time so that they can be edited while the effects are
+        /// running. If `layers` doesn't match the layers that this stack is running, then the
+        /// stack is resynced to match them first, so every layer is always blended with its own
+        /// opacity and blend mode.
+        ///
+        /// This method returns the blended frame along with the time at which the next effect is
+        /// due, or `None` if the main effect has finished. A layer which finishes is restarted
+        /// from its config, since only the main effect decides when the whole stack loops.
+        pub fn next_frame(
+            &mut self,
+            base_config: &EffectConfigDispatchList,
+            layers: &[EffectLayer],
+            now: Instant,
+        ) -> Option<(FrameType, Instant)> {
+            if !self.base.advance(base_config, now) {
+                return None;
+            }
+
+            self.resync(layers, now);
+
+            for (scheduled, layer) in self.layers.iter_mut().zip(layers) {
+                if !scheduled.advance(&layer.config, now) {
+                    *scheduled = ScheduledEffect::new(layer.config.clone().into(), now);
+                    scheduled.advance(&layer.config, now);
+                }
+            }
+
+            let next_due = self
+                .layers
+                .iter()
+                .map(|scheduled| scheduled.due)
+                .fold(self.base.due, Instant::min);
+
+            // Only the main effect is running, so just pass its frame along untouched
+            if self.layers.is_empty() {
+                return Some((self.base.latest_frame.clone(), next_due));
+            }
+
+            let mut data = raw_data(&self.base.latest_frame);
+            for (scheduled, layer) in self.layers.iter().zip(layers) {
+                layer.blend_mode.blend_frames(
+                    &mut data,
+                    &raw_data(&scheduled.latest_frame),
+                    layer.opacity,
+                );
+            }
+
+            Some((FrameType::RawData(data), next_due))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blend_test() {
+        let bottom = [200, 100, 0];
+        let top = [100, 255, 50];
+
+        assert_eq!(BlendMode::Normal.blend(bottom, top, 1.), [100, 255, 50]);
+        assert_eq!(BlendMode::Add.blend(bottom, top, 1.), [255, 255, 50]);
+        assert_eq!(BlendMode::Multiply.blend(bottom, top, 1.), [78, 100, 0]);
+        assert_eq!(BlendMode::Screen.blend(bottom, top, 1.), [222, 255, 50]);
+        assert_eq!(BlendMode::Lighten.blend(bottom, top, 1.), [200, 255, 50]);
+        assert_eq!(BlendMode::Darken.blend(bottom, top, 1.), [100, 100, 0]);
+
+        assert_eq!(BlendMode::Normal.blend(bottom, top, 0.5), [150, 178, 25]);
+        assert_eq!(BlendMode::Add.blend(bottom, top, 0.), bottom);
+        assert_eq!(BlendMode::Normal.blend(bottom, top, 2.), top);
+
+        // Black is transparent in these modes
+        for mode in [BlendMode::Add, BlendMode::Screen, BlendMode::Lighten] {
+            assert_eq!(mode.blend(bottom, [0; 3], 1.), bottom);
+        }
+    }
+
+    #[test]
+    fn clamp_to_ranges_test() {
+        use crate::effects::configs::HelixConfig;
+
+        let mut layer = EffectLayer {
+            opacity: 1.5,
+            ..EffectLayer::new(EffectConfigDispatchList::HelixConfig(HelixConfig {
+                pitch: 50.,
+                ..HelixConfig::default()
+            }))
+        };
+        layer.clamp_to_ranges();
+        assert_eq!(layer.opacity, 1.);
+        assert_eq!(
+            layer.config,
+            EffectConfigDispatchList::HelixConfig(HelixConfig {
+                pitch: 10.,
+                ..HelixConfig::default()
+            })
+        );
+
+        layer.opacity = -1.;
+        layer.clamp_to_ranges();
+        assert_eq!(layer.opacity, 0.);
+
+        layer.opacity = f32::NAN;
+        layer.clamp_to_ranges();
+        assert_eq!(layer.opacity, 1.);
+    }
+
+    #[cfg(feature = "effect-impls")]
+    #[test]
+    fn layer_stack_scheduling_test() {
+        use crate::list::{EffectConfigNameList, EffectDispatchList};
+        use std::time::{Duration, Instant};
+        use tracing_unwrap::OptionExt;
+        use ww_frame::FrameType;
+
+        // Maze takes a step every 60ms and Sorting takes a step every 150ms
+        let base_config = EffectConfigNameList::MazeConfig.default_dispatch();
+        let layers = [EffectLayer::new(
+            EffectConfigNameList::SortingConfig.default_dispatch(),
+        )];
+
+        let start = Instant::now();
+        let mut stack = LayerStack::new(
+            EffectDispatchList::from(base_config.clone()),
+            &layers,
+            start,
+        );
+
+        let mut now = start;
+        let mut due_times = vec![];
+        for _ in 0..8 {
+            let (frame, next_due) = stack
+                .next_frame(&base_config, &layers, now)
+                .expect_or_log("Maze should never finish");
+            assert!(matches!(frame, FrameType::RawData(_)));
+
+            due_times.push(next_due.duration_since(start));
+            now = next_due;
+        }
+
+        assert_eq!(
+            due_times,
+            [60, 120, 150, 180, 240, 300, 360, 420].map(Duration::from_millis)
+        );
+
+        // Without any layers, the frame from the main effect is passed through untouched
+        let base_config = EffectConfigNameList::FireConfig.default_dispatch();
+        let mut stack = LayerStack::new(EffectDispatchList::from(base_config.clone()), &[], start);
+        let mut fire = EffectDispatchList::from(base_config.clone());
+        assert_eq!(
+            stack
+                .next_frame(&base_config, &[], start)
+                .map(|(frame, _)| frame),
+            fire.next_frame(&base_config, &crate::FrameContext::default())
+                .map(|(frame, _)| frame)
+        );
+    }
+
+    #[cfg(feature = "effect-impls")]
+    #[test]
+    fn layer_stack_resync_test() {
+        use crate::list::{EffectConfigNameList, EffectDispatchList};
+        use std::time::Instant;
+        use tracing_unwrap::OptionExt;
+
+        let base_config = EffectConfigNameList::FireConfig.default_dispatch();
+        let lava_lamp = EffectLayer {
+            opacity: 0.,
+            ..EffectLayer::new(EffectConfigNameList::LavaLampConfig.default_dispatch())
+        };
+        let helix = EffectLayer {
+            blend_mode: BlendMode::Normal,
+            ..EffectLayer::new(EffectConfigNameList::HelixConfig.default_dispatch())
+        };
+
+        let start = Instant::now();
+        let mut stack = LayerStack::new(
+            EffectDispatchList::from(base_config.clone()),
+            &[lava_lamp, helix.clone()],
+            start,
+        );
+
+        // The client removes the invisible bottom layer before the stack is restarted, so the
+        // opaque helix should be drawn over everything rather than the lava lamp taking its place
+        let (frame, _) = stack
+            .next_frame(&base_config, std::slice::from_ref(&helix), start)
+            .expect_or_log("Fire should never finish");
+
+        let mut expected = EffectDispatchList::from(helix.config.clone());
+        let (expected_frame, _) = expected
+            .next_frame(&helix.config, &crate::FrameContext::default())
+            .expect_or_log("Helix should never finish");
+        assert_eq!(frame, expected_frame);
+    }
+}
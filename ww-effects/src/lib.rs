@@ -3,24 +3,19 @@
 //! own effect.
 
 #![feature(let_chains)]
-#![feature(proc_macro_hygiene)]
-#![feature(stmt_expr_attributes)]
 
 pub mod list {
     //! This module contains list enums for effects and their configs, in name and dispatch (instance
     //! wrapper) form.
+    //!
+    //! The effects are found by the build script, so every effect in a category module of
+    //! [`effects`](crate::effects) is in these lists.
 
-    effect_proc_macros::generate_lists_and_impls! {
-        DebugOneByOne,
-        DebugBinaryIndex,
-        MovingPlane,
-        SplitPlane,
-        LavaLamp,
-        AiSnake,
-    }
+    include!(concat!(env!("OUT_DIR"), "/effect_list.rs"));
+    include!(concat!(env!("OUT_DIR"), "/effect_metadata.rs"));
 }
 
-pub use self::list::{EffectConfigNameList, EffectNameList};
+pub use self::list::{EffectCategory, EffectConfigNameList, EffectNameList};
 
 #[cfg(feature = "config-impls")]
 pub use self::list::EffectConfigDispatchList;
@@ -28,6 +23,10 @@ pub use self::list::EffectConfigDispatchList;
 #[cfg(feature = "effect-impls")]
 pub use self::list::EffectDispatchList;
 
+pub mod metadata;
+
+pub use self::metadata::{EffectMetadata, Thumbnail};
+
 #[cfg(any(feature = "effect-trait", feature = "config-trait"))]
 pub mod traits;
 
@@ -37,9 +36,51 @@ pub use self::traits::{EffectConfig, save_effect_config_to_file};
 #[cfg(feature = "effect-trait")]
 pub use self::traits::Effect;
 
+#[cfg(feature = "effect-trait")]
+pub mod timing;
+
+#[cfg(feature = "effect-trait")]
+pub use self::timing::{FrameClock, FrameContext, SimulatedClock, StepSchedule};
+
+#[cfg(feature = "config-trait")]
+pub mod config_file;
+
+#[cfg(feature = "config-trait")]
+pub mod schema;
+
+#[cfg(feature = "config-trait")]
+pub use self::schema::{ConfigSchema, FieldSchema, FieldType};
+
 #[cfg(any(feature = "config-impls", feature = "effect-impls"))]
 pub mod effects;
 
+#[cfg(feature = "config-impls")]
+pub mod layers;
+
+#[cfg(feature = "config-impls")]
+pub use self::layers::{BlendMode, EffectLayer};
+
+#[cfg(feature = "config-impls")]
+pub mod modulation;
+
+#[cfg(feature = "config-impls")]
+pub use self::modulation::{Modulation, ModulationShape};
+
+#[cfg(feature = "config-impls")]
+pub mod presets;
+
+#[cfg(feature = "config-impls")]
+pub use self::presets::{Preset, PresetBundle, PresetLibrary, PresetStore};
+
+#[cfg(feature = "effect-impls")]
+pub use self::layers::LayerStack;
+
+#[cfg(feature = "scripting")]
+pub mod scripting;
+
+#[cfg(feature = "plugins")]
+pub mod plugins;
+
 cfg_if::cfg_if! {
     if #[cfg(test)] {
         use ww_driver_trait::Driver;
@@ -59,11 +100,20 @@ cfg_if::cfg_if! {
                 let config = E::Config::default();
                 let mut effect = E::from_config(config.clone());
 
+                // The clock pretends that every frame is shown for exactly as long as the effect
+                // asked for, so the frames don't depend on how fast the tests run
+                let mut clock = SimulatedClock::default();
+                let mut next_frame = || {
+                    let (frame, duration) = effect.next_frame(&config, &clock.tick())?;
+                    clock.advance(duration);
+                    Some(frame)
+                };
+
                 if let Some(number) = E::loops_to_test() {
                     self.data.reserve_exact(u16::from(number) as usize);
 
                     for i in 0..u16::from(number) {
-                        if let Some((frame, _duration)) = effect.next_frame(&config) {
+                        if let Some(frame) = next_frame() {
                             self.data.push(frame);
                         } else {
                             panic!(
@@ -73,7 +123,7 @@ cfg_if::cfg_if! {
                         }
                     }
                 } else {
-                    while let Some((frame, _duration)) = effect.next_frame(&config) {
+                    while let Some(frame) = next_frame() {
                         self.data.push(frame);
                     }
                 }
@@ -0,0 +1,261 @@
+//! This module contains the metadata of effects, which the client uses to show a browser of all the
+//! effects.
+//!
+//! Most of the metadata is declared on the effect struct itself and found by the build script. The
+//! description is the first paragraph of the doc comment of the struct, the category is the module
+//! that the effect is in, and the rest comes from an optional `#[effect(...)]` attribute after the
+//! `#[derive(BaseEffect)]`:
+//!
+//! ```ignore
+//! /// Wind coloured strands around the tree like a barber's pole and rotate them.
+//! #[derive(Clone, Debug, PartialEq, BaseEffect)]
+//! #[effect(author = "Someone Else", tags = ["spiral", "colourful"], unattended)]
+//! pub struct Helix {
+//!     // ...
+//! }
+//! ```
+//!
+//! The author defaults to the author of this crate, the tags default to none, and `unattended`
+//! should be given if the effect is good to leave looping on its own for hours.
+//!
+//! The [`Thumbnail`]s are rendered when the server is built, since rendering them needs the
+//! coordinates of the lights.
+
+use crate::{EffectCategory, EffectNameList};
+use serde::{Deserialize, Serialize};
+
+/// The width of a [`Thumbnail`] in pixels.
+pub const THUMBNAIL_WIDTH: usize = 24;
+
+/// The height of a [`Thumbnail`] in pixels.
+pub const THUMBNAIL_HEIGHT: usize = 32;
+
+/// How many frames of an effect to look at to find the frame for its [`Thumbnail`].
+#[cfg(feature = "effect-impls")]
+const THUMBNAIL_FRAMES: usize = 60;
+
+/// The metadata of an effect. See the [module docs](self).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EffectMetadata {
+    /// The effect that this metadata describes.
+    pub effect: EffectNameList,
+
+    /// A description of the effect for people.
+    pub description: String,
+
+    /// The category of the effect.
+    pub category: EffectCategory,
+
+    /// Who wrote the effect.
+    pub author: String,
+
+    /// Some tags to help people search for the effect.
+    pub tags: Vec<String>,
+
+    /// Whether the effect is good to leave looping on its own.
+    pub unattended: bool,
+
+    /// A small preview of the effect, if one was rendered.
+    pub thumbnail: Option<Thumbnail>,
+}
+
+impl EffectMetadata {
+    /// Check whether the effect matches the search query, ignoring case. An empty query matches
+    /// every effect, and otherwise every word in the query has to appear in the name, description,
+    /// category, author, or tags of the effect.
+    pub fn matches(&self, query: &str) -> bool {
+        let haystack = [
+            self.effect.effect_name(),
+            &self.description,
+            self.category.name(),
+            &self.author,
+        ]
+        .into_iter()
+        .chain(self.tags.iter().map(String::as_str))
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+
+        query
+            .to_lowercase()
+            .split_whitespace()
+            .all(|word| haystack.contains(word))
+    }
+}
+
+/// Get the metadata of every effect, without thumbnails.
+pub fn all_metadata() -> Vec<EffectMetadata> {
+    use strum::IntoEnumIterator;
+
+    EffectNameList::iter()
+        .map(|effect| effect.metadata())
+        .collect()
+}
+
+/// A small picture of the tree from the front, showing a frame of an effect.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Thumbnail {
+    /// The colour of every pixel, row by row from the top left.
+    pub pixels: Vec<[u8; 3]>,
+}
+
+impl Thumbnail {
+    /// Draw the lights at the given coordinates in the given colours. The tree is seen from the
+    /// front, so the x coordinate goes across and the z coordinate goes up, and the brightest light
+    /// wins when several lights land on the same pixel.
+    pub fn from_lights(coords: &[(f32, f32, f32)], max_z: f32, colours: &[[u8; 3]]) -> Self {
+        let mut pixels = vec![[0; 3]; THUMBNAIL_WIDTH * THUMBNAIL_HEIGHT];
+        let brightness = |colour: &[u8; 3]| colour.iter().map(|&c| u16::from(c)).sum::<u16>();
+
+        for (&(x, _, z), colour) in coords.iter().zip(colours) {
+            let column = ((x + 1.) / 2. * THUMBNAIL_WIDTH as f32) as usize;
+            let row = ((1. - z / max_z) * THUMBNAIL_HEIGHT as f32) as usize;
+
+            let pixel = &mut pixels
+                [row.min(THUMBNAIL_HEIGHT - 1) * THUMBNAIL_WIDTH + column.min(THUMBNAIL_WIDTH - 1)];
+            if brightness(colour) > brightness(pixel) {
+                *pixel = *colour;
+            }
+        }
+
+        Self { pixels }
+    }
+
+    /// Run the effect with its default config and draw the brightest of its first few frames.
+    #[cfg(feature = "effect-impls")]
+    pub fn render(effect: EffectNameList) -> Self {
+        use crate::SimulatedClock;
+        use ww_frame::FrameType;
+        use ww_gift_coords::COORDS;
+
+        let config = effect.config_name().default_dispatch();
+        let mut dispatch = effect.default_dispatch();
+        let mut clock = SimulatedClock::default();
+
+        let frames = std::iter::from_fn(|| {
+            let (frame, duration) = dispatch.next_frame(&config, &clock.tick())?;
+            clock.advance(duration);
+            Some(frame)
+        })
+        .take(THUMBNAIL_FRAMES)
+        .map(|frame| match frame {
+            FrameType::Off => vec![[0; 3]; COORDS.lights_num()],
+            FrameType::RawData(data) => data,
+            FrameType::Frame3D(frame) => frame.to_raw_data(),
+        });
+
+        let total_brightness = |colours: &Vec<[u8; 3]>| {
+            colours
+                .iter()
+                .flatten()
+                .map(|&channel| u32::from(channel))
+                .sum::<u32>()
+        };
+        let colours = frames
+            .max_by_key(total_brightness)
+            .unwrap_or_else(|| vec![[0; 3]; COORDS.lights_num()]);
+
+        Self::from_lights(COORDS.coords(), COORDS.max_z(), &colours)
+    }
+
+    /// Get the pixels as a flat list of RGB bytes.
+    pub fn to_rgb_bytes(&self) -> Vec<u8> {
+        self.pixels.iter().flatten().copied().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn metadata_test() {
+        for metadata in all_metadata() {
+            let name = metadata.effect.effect_name();
+            assert!(
+                !metadata.description.is_empty(),
+                "{name} should have a description"
+            );
+            assert!(!metadata.author.is_empty(), "{name} should have an author");
+            assert_eq!(metadata.category, metadata.effect.category());
+            assert_eq!(metadata.thumbnail, None);
+        }
+
+        assert!(
+            EffectCategory::iter()
+                .all(|category| all_metadata().iter().any(|m| m.category == category)),
+            "every category should have an effect"
+        );
+
+        let helix = EffectNameList::Helix.metadata();
+        assert_eq!(helix.category, EffectCategory::Maths);
+        assert!(helix.unattended);
+        assert!(
+            !EffectNameList::DebugOneByOne.metadata().unattended,
+            "debug effects shouldn't be left running"
+        );
+    }
+
+    #[test]
+    fn matches_test() {
+        let helix = EffectNameList::Helix.metadata();
+        assert!(helix.matches(""));
+        assert!(helix.matches("helix"));
+        assert!(helix.matches("HELIX maths"));
+        assert!(
+            helix.matches("barber"),
+            "the description should be searched"
+        );
+        assert!(!helix.matches("helix fire"), "every word should match");
+
+        let fire_matches: Vec<_> = all_metadata()
+            .into_iter()
+            .filter(|metadata| metadata.matches("fire"))
+            .map(|metadata| metadata.effect)
+            .collect();
+        assert!(fire_matches.contains(&EffectNameList::Fire));
+        assert!(fire_matches.contains(&EffectNameList::Fireworks));
+        assert!(!fire_matches.contains(&EffectNameList::Helix));
+    }
+
+    #[test]
+    fn from_lights_test() {
+        let coords = [(-1., 0., 0.), (1., 0., 2.), (0., 0., 1.), (0.01, 0.5, 1.)];
+        let colours = [[255, 0, 0], [0, 255, 0], [0, 0, 10], [0, 0, 200]];
+        let thumbnail = Thumbnail::from_lights(&coords, 2., &colours);
+
+        assert_eq!(thumbnail.pixels.len(), THUMBNAIL_WIDTH * THUMBNAIL_HEIGHT);
+        assert_eq!(
+            thumbnail.pixels[(THUMBNAIL_HEIGHT - 1) * THUMBNAIL_WIDTH],
+            [255, 0, 0],
+            "the bottom left light"
+        );
+        assert_eq!(
+            thumbnail.pixels[THUMBNAIL_WIDTH - 1],
+            [0, 255, 0],
+            "the top right light"
+        );
+        assert_eq!(
+            thumbnail.pixels[THUMBNAIL_HEIGHT / 2 * THUMBNAIL_WIDTH + THUMBNAIL_WIDTH / 2],
+            [0, 0, 200],
+            "the brightest light should win"
+        );
+        assert_eq!(thumbnail.pixels.iter().filter(|&&p| p != [0; 3]).count(), 3);
+        assert_eq!(
+            thumbnail.to_rgb_bytes().len(),
+            THUMBNAIL_WIDTH * THUMBNAIL_HEIGHT * 3
+        );
+    }
+
+    #[cfg(feature = "effect-impls")]
+    #[test]
+    fn render_test() {
+        let thumbnail = Thumbnail::render(EffectNameList::Helix);
+        assert_eq!(thumbnail.pixels.len(), THUMBNAIL_WIDTH * THUMBNAIL_HEIGHT);
+        assert!(
+            thumbnail.pixels.iter().any(|&pixel| pixel != [0; 3]),
+            "the helix should light up the thumbnail"
+        );
+    }
+}
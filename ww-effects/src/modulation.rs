@@ -0,0 +1,300 @@
+//! This module handles modulating the numeric fields of effect configs over time.
+//!
+//! A [`Modulation`] binds a field of a config, addressed by name like `fadeoff` or `colour.0` (see
+//! [`BaseEffectConfig::numeric_field_names`](crate::traits::BaseEffectConfig::numeric_field_names)),
+//! to an LFO or a ramp. The server applies the modulations to a copy of the config before every
+//! frame, so the config that the client edits never changes by itself.
+
+use crate::list::EffectConfigDispatchList;
+use serde::{Deserialize, Serialize};
+use std::{f32::consts::TAU, time::Duration};
+use strum::EnumIter;
+
+/// The shortest allowed period of a modulation, in seconds, to avoid dividing by zero.
+const MIN_PERIOD_SECONDS: f32 = 0.01;
+
+/// The shape of a modulation over time.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize, EnumIter)]
+pub enum ModulationShape {
+    /// A sine wave LFO, starting at the minimum.
+    #[default]
+    Sine,
+
+    /// A triangle wave LFO, starting at the minimum.
+    Triangle,
+
+    /// An LFO which drifts smoothly to a new random value once every period.
+    Random,
+
+    /// A ramp from the minimum to the maximum over one period, which then starts again.
+    Ramp,
+
+    /// A ramp from the minimum to the maximum over one period, which then stays at the maximum.
+    RampOnce,
+}
+
+impl ModulationShape {
+    /// Get the name of this shape for the GUI.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Sine => "Sine",
+            Self::Triangle => "Triangle",
+            Self::Random => "Random",
+            Self::Ramp => "Ramp",
+            Self::RampOnce => "Ramp once",
+        }
+    }
+}
+
+/// A binding of a numeric config field to an LFO or a ramp.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Modulation {
+    /// The name of the field to modulate. See
+    /// [`BaseEffectConfig::numeric_field_names`](crate::traits::BaseEffectConfig::numeric_field_names).
+    pub field: String,
+
+    /// The shape of the modulation.
+    pub shape: ModulationShape,
+
+    /// The length of one cycle of an LFO or the length of a ramp, in seconds.
+    pub period_seconds: f32,
+
+    /// How far through its cycle the modulation starts, as a fraction of the period.
+    pub phase: f32,
+
+    /// The value of the field at the bottom of the modulation.
+    pub min: f32,
+
+    /// The value of the field at the top of the modulation.
+    pub max: f32,
+}
+
+impl Modulation {
+    /// Create a new sine LFO for the given field of the config, which swings between half and one
+    /// and a half times the current value of the field every 10 seconds. Return `None` if the
+    /// config has no numeric field with that name.
+    pub fn new(config: &EffectConfigDispatchList, field: &str) -> Option<Self> {
+        let value = config.get_numeric_field(field)?;
+
+        Some(Self {
+            field: field.to_string(),
+            shape: ModulationShape::default(),
+            period_seconds: 10.,
+            phase: 0.,
+            min: value * 0.5,
+            max: value * 1.5,
+        })
+    }
+
+    /// Get the position of the modulation between `min` (0) and `max` (1) after the given time.
+    fn position_at(&self, elapsed: Duration) -> f32 {
+        let cycles =
+            elapsed.as_secs_f32() / self.period_seconds.max(MIN_PERIOD_SECONDS) + self.phase;
+
+        match self.shape {
+            ModulationShape::Sine => 0.5_f32.mul_add(-(TAU * cycles).cos(), 0.5),
+            ModulationShape::Triangle => 1. - 2_f32.mul_add(cycles.rem_euclid(1.), -1.).abs(),
+            ModulationShape::Random => {
+                let step = cycles.floor();
+                let t = cycles - step;
+                let smooth_t = t * t * 2_f32.mul_add(-t, 3.);
+
+                let seed = field_seed(&self.field);
+                let start = random_unit(step as i64, seed);
+                let end = random_unit(step as i64 + 1, seed);
+                (end - start).mul_add(smooth_t, start)
+            }
+            ModulationShape::Ramp => cycles.rem_euclid(1.),
+            ModulationShape::RampOnce => cycles.clamp(0., 1.),
+        }
+    }
+
+    /// Get the value of the modulated field after the given time.
+    pub fn value_at(&self, elapsed: Duration) -> f32 {
+        (self.max - self.min).mul_add(self.position_at(elapsed), self.min)
+    }
+
+    /// Set the field of the config to the value of this modulation after the given time, and
+    /// return whether the config has the field.
+    pub fn apply(&self, config: &mut EffectConfigDispatchList, elapsed: Duration) -> bool {
+        config.set_numeric_field(&self.field, self.value_at(elapsed))
+    }
+}
+
+/// Get a copy of the config with all the modulations applied after the given time. Modulations
+/// for fields that the config doesn't have are ignored.
+///
+/// The modulated config is [clamped](crate::traits::EffectConfig::clamp_to_ranges) afterwards, so
+/// a modulation which swings past the range of its field can't give the effect a value that the
+/// client wouldn't be allowed to set.
+pub fn modulate(
+    config: &EffectConfigDispatchList,
+    modulations: &[Modulation],
+    elapsed: Duration,
+) -> EffectConfigDispatchList {
+    let mut config = config.clone();
+    for modulation in modulations {
+        modulation.apply(&mut config, elapsed);
+    }
+    config.clamp_to_ranges();
+    config
+}
+
+/// Hash the name of a field so that random modulations of different fields don't move together.
+fn field_seed(field: &str) -> u64 {
+    // This is FNV-1a, which is simple and stable across builds, unlike the std hasher
+    field.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Get a random number in `[0, 1)` which is always the same for the same step and seed.
+fn random_unit(step: i64, seed: u64) -> f32 {
+    // This is the finaliser from SplitMix64
+    let mut x = (step as u64 ^ seed).wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^= x >> 31;
+
+    (x >> 40) as f32 / (1_u64 << 24) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::list::EffectConfigNameList;
+    use tracing_unwrap::OptionExt;
+
+    #[test]
+    fn numeric_fields_test() {
+        let mut config = EffectConfigNameList::HelixConfig.default_dispatch();
+        let names = config.numeric_field_names();
+
+        assert!(names.contains(&"pitch"), "f32 fields should be numeric");
+        assert!(names.contains(&"strands"), "u8 fields should be numeric");
+        assert!(
+            names.contains(&"background_colour.2"),
+            "each channel of a colour should be numeric"
+        );
+        assert!(
+            !names.contains(&"clockwise"),
+            "bool fields should not be numeric"
+        );
+        assert!(!names.contains(&"colours"), "Vecs should not be numeric");
+
+        assert!(config.set_numeric_field("pitch", 2.5));
+        assert_eq!(config.get_numeric_field("pitch"), Some(2.5));
+
+        // Integers are rounded and saturate
+        assert!(config.set_numeric_field("background_colour.1", 12.6));
+        assert_eq!(config.get_numeric_field("background_colour.1"), Some(13.));
+        assert!(config.set_numeric_field("strands", 1000.));
+        assert_eq!(config.get_numeric_field("strands"), Some(255.));
+
+        assert!(!config.set_numeric_field("not_a_field", 1.));
+        assert_eq!(config.get_numeric_field("not_a_field"), None);
+    }
+
+    #[test]
+    fn modulation_shapes_test() {
+        let mut modulation = Modulation {
+            field: "pitch".to_string(),
+            shape: ModulationShape::Sine,
+            period_seconds: 4.,
+            phase: 0.,
+            min: 1.,
+            max: 3.,
+        };
+        let at = |modulation: &Modulation, seconds: f32| {
+            modulation.value_at(Duration::from_secs_f32(seconds))
+        };
+        let close = |a: f32, b: f32| (a - b).abs() < 1e-4;
+
+        assert!(close(at(&modulation, 0.), 1.), "sine starts at min");
+        assert!(close(at(&modulation, 1.), 2.), "sine is halfway up");
+        assert!(close(at(&modulation, 2.), 3.), "sine peaks halfway through");
+        assert!(close(at(&modulation, 4.), 1.), "sine repeats");
+
+        modulation.shape = ModulationShape::Triangle;
+        assert!(close(at(&modulation, 1.), 2.), "triangle is linear");
+        assert!(
+            close(at(&modulation, 2.), 3.),
+            "triangle peaks halfway through"
+        );
+        assert!(close(at(&modulation, 3.), 2.), "triangle comes back down");
+
+        modulation.shape = ModulationShape::Ramp;
+        assert!(close(at(&modulation, 3.), 2.5), "ramp is linear");
+        assert!(close(at(&modulation, 5.), 1.5), "ramp starts again");
+
+        modulation.shape = ModulationShape::RampOnce;
+        assert!(close(at(&modulation, 5.), 3.), "ramp once stays at max");
+
+        modulation.phase = 0.5;
+        modulation.shape = ModulationShape::Sine;
+        assert!(close(at(&modulation, 0.), 3.), "phase offsets the start");
+
+        modulation.shape = ModulationShape::Random;
+        for seconds in 0..100 {
+            let value = at(&modulation, seconds as f32 * 0.37);
+            assert!((1. ..=3.).contains(&value), "random stays in range");
+        }
+        assert_eq!(
+            at(&modulation, 12.3),
+            at(&modulation, 12.3),
+            "random is deterministic"
+        );
+    }
+
+    #[test]
+    fn modulate_test() {
+        let config = EffectConfigNameList::HelixConfig.default_dispatch();
+        let modulation = Modulation {
+            shape: ModulationShape::RampOnce,
+            ..Modulation::new(&config, "band_width")
+                .expect_or_log("band_width should be a numeric field")
+        };
+
+        let modulated = modulate(
+            &config,
+            std::slice::from_ref(&modulation),
+            Duration::from_secs(modulation.period_seconds as u64),
+        );
+        assert_eq!(
+            modulated.get_numeric_field("band_width"),
+            Some(modulation.max)
+        );
+        assert_ne!(modulated, config, "the original config is left untouched");
+    }
+
+    #[test]
+    fn modulate_clamp_test() {
+        let config = EffectConfigNameList::HelixConfig.default_dispatch();
+        let modulation = Modulation {
+            field: "strands".to_string(),
+            shape: ModulationShape::RampOnce,
+            period_seconds: 1.,
+            phase: 0.,
+            min: -5.,
+            max: 100.,
+        };
+
+        let range = EffectConfigNameList::HelixConfig
+            .schema()
+            .fields
+            .iter()
+            .find(|field| field.name == "strands")
+            .and_then(|field| field.range)
+            .expect_or_log("strands should have a range");
+
+        for elapsed in [Duration::ZERO, Duration::from_secs(1)] {
+            let strands = modulate(&config, std::slice::from_ref(&modulation), elapsed)
+                .get_numeric_field("strands")
+                .expect_or_log("strands should be a numeric field");
+            assert!(
+                (range.0..=range.1).contains(&f64::from(strands)),
+                "modulated strands {strands} should be clamped to {range:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,888 @@
+//! This module handles effects compiled to WebAssembly, which are loaded from `DATA_DIR/plugins`
+//! while the server is running, so they don't need to be compiled into the server. Plugins can be
+//! written in any language that compiles to WASM and are much faster than
+//! [scripts](crate::scripting).
+//!
+//! # The plugin ABI
+//!
+//! A plugin is a file like `DATA_DIR/plugins/my_effect.wasm`, which appears in the effect list as
+//! `Plugin: my_effect`. It must export these items:
+//!
+//! - `memory`, its linear memory.
+//! - `next_frame() -> (i32, i32)`, which is called for every frame and returns a pointer to the
+//!   frame and how many milliseconds to show it for. The frame is `lights_num() * 3` bytes of RGB
+//!   data, one colour for each light. A negative number of milliseconds ends the effect.
+//!
+//! It can also export these items:
+//!
+//! - `init(i32, i32)`, which is called once when the plugin is loaded with a pointer to the config
+//!   of the plugin and its length. The config is the JSON in `DATA_DIR/plugins/my_effect.json`,
+//!   or `{}` if there is no such file.
+//! - `alloc(i32) -> i32`, which must be exported along with `init` and returns a pointer to the
+//!   given number of bytes, which the config gets written into.
+//!
+//! Plugins can import these functions from the `ww` module to read the coordinates of the lights,
+//! which are described in [`ww_gift_coords::COORDS`]:
+//!
+//! - `lights_num() -> i32`
+//! - `light_x(i32) -> f32`, `light_y(i32) -> f32`, and `light_z(i32) -> f32`, which get one
+//!   coordinate of the light with the given index.
+//! - `max_z() -> f32`
+//!
+//! They can also import `random() -> f32`, which gets a random float in `[0, 1)`, and
+//! `log(i32, i32)`, which writes the UTF-8 string at the given pointer with the given length to
+//! the server log.
+//!
+//! For timing, they can import `elapsed() -> f32`, which gets the number of seconds since the
+//! effect started, and `delta() -> f32`, which gets the number of seconds since the last frame.
+//! Plugins which move things should move them by `delta()` so that they keep the same speed when
+//! frames are late. See [`crate::timing`].
+//!
+//! # Sandboxing
+//!
+//! Plugins can only see their own memory and the functions above, and their memory is limited to
+//! [`MEMORY_LIMIT`] bytes. Each call to a plugin has a limited amount of fuel, which is used up by
+//! running instructions. The limit is [`INIT_FUEL`] for `init` and [`FRAME_FUEL`] for
+//! `next_frame`. A plugin which runs out of fuel or traps is stopped, and the effect ends.
+//!
+//! # Hot reloading
+//!
+//! Before every frame, [`PluginEffect`] checks whether the plugin file has changed. If it has, the
+//! new plugin is loaded and initialised in place of the old one. If the new plugin can't be
+//! loaded, the old one keeps running until the file changes again.
+
+use crate::timing::FrameContext;
+use rand::{Rng, rngs::StdRng};
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+use thiserror::Error;
+use tracing::{info, warn};
+use tracing_unwrap::ResultExt;
+use wasmi::{
+    Caller, Config, Engine, Instance, Linker, Memory, Module, Store, StoreLimits,
+    StoreLimitsBuilder, TypedFunc, core::TrapCode,
+};
+use ww_frame::{FrameType, RGBArray};
+use ww_gift_coords::COORDS;
+
+/// The file extension of plugins.
+pub const PLUGIN_EXTENSION: &str = "wasm";
+
+/// The file extension of the config files of plugins.
+pub const PLUGIN_CONFIG_EXTENSION: &str = "json";
+
+/// The fuel for running `init` and the start function of a plugin.
+pub const INIT_FUEL: u64 = 100_000_000;
+
+/// The fuel for a single call to `next_frame`.
+pub const FRAME_FUEL: u64 = 10_000_000;
+
+/// The most memory that a plugin can use, in bytes.
+pub const MEMORY_LIMIT: usize = 16 * 1024 * 1024;
+
+/// An error from loading or running a plugin.
+#[derive(Debug, Error)]
+pub enum PluginError {
+    /// The plugin file or its config couldn't be read.
+    #[error("unable to read plugin: {0}")]
+    Io(#[from] io::Error),
+
+    /// There's no plugin with the given name. See [`is_plugin_in`].
+    #[error("there is no plugin called {0:?}")]
+    UnknownPlugin(String),
+
+    /// The plugin isn't valid WASM, doesn't match the ABI, or trapped.
+    #[error("error in plugin: {0}")]
+    Wasm(#[from] wasmi::Error),
+
+    /// The plugin ran out of fuel.
+    #[error("plugin ran out of fuel")]
+    OutOfFuel,
+
+    /// The plugin doesn't export something that it needs to.
+    #[error("plugin has no `{0}` export")]
+    MissingExport(&'static str),
+
+    /// The frame returned by `next_frame` isn't in the memory of the plugin.
+    #[error("the frame at {0} is outside the plugin's memory")]
+    BadFrame(u32),
+}
+
+/// Get the directory which contains the plugins.
+pub fn plugins_dir() -> PathBuf {
+    PathBuf::from(format!(
+        "{}/plugins",
+        std::env::var("DATA_DIR").expect_or_log("DATA_DIR must be defined")
+    ))
+}
+
+/// Get the names of all the plugins in [`plugins_dir`], in alphabetical order.
+pub fn list_plugins() -> Vec<String> {
+    list_plugins_in(&plugins_dir())
+}
+
+/// Get the names of all the plugins in the given directory, in alphabetical order. The name of a
+/// plugin is its filename without the extension.
+pub fn list_plugins_in(dir: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            if path.extension()? == PLUGIN_EXTENSION {
+                Some(path.file_stem()?.to_str()?.to_string())
+            } else {
+                None
+            }
+        })
+        .collect();
+    names.sort();
+    names
+}
+
+/// Check whether there's a plugin with the given name in [`plugins_dir`]. See [`is_plugin_in`].
+pub fn is_plugin(name: &str) -> bool {
+    is_plugin_in(&plugins_dir(), name)
+}
+
+/// Check whether there's a plugin with the given name in the given directory.
+///
+/// Names with path separators or `..` in them are never plugins, even if a file of that name
+/// exists, so that clients can't get the server to read files outside the directory.
+pub fn is_plugin_in(dir: &Path, name: &str) -> bool {
+    !name.contains(['/', '\\'])
+        && !name.contains("..")
+        && list_plugins_in(dir).iter().any(|plugin| plugin == name)
+}
+
+/// Get the time that the file at the given path was last modified, if we can.
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+/// Turn a wasmi error into a [`PluginError`], picking out running out of fuel.
+fn wasm_error(error: wasmi::Error) -> PluginError {
+    if error.as_trap_code() == Some(TrapCode::OutOfFuel) {
+        PluginError::OutOfFuel
+    } else {
+        PluginError::Wasm(error)
+    }
+}
+
+/// The data that the host functions of a plugin can see.
+struct HostState {
+    /// The name of the plugin, for logging.
+    name: String,
+
+    /// The limits on the memory of the plugin.
+    limits: StoreLimits,
+
+    /// The random number generator for `random`.
+    rng: StdRng,
+
+    /// The timing of the current frame, for `elapsed` and `delta`.
+    frame: FrameContext,
+}
+
+/// Get the light with the given index, or trap if there isn't one.
+fn light(index: i32) -> Result<(f32, f32, f32), wasmi::Error> {
+    usize::try_from(index)
+        .ok()
+        .and_then(|index| COORDS.coords().get(index).copied())
+        .ok_or_else(|| wasmi::Error::new(format!("there is no light with index {index}")))
+}
+
+/// Define the functions that plugins can import from the `ww` module.
+fn define_host_functions(linker: &mut Linker<HostState>) -> Result<(), wasmi::Error> {
+    linker
+        .func_wrap("ww", "lights_num", || COORDS.lights_num() as i32)?
+        .func_wrap("ww", "light_x", |index: i32| {
+            light(index).map(|(x, _, _)| x)
+        })?
+        .func_wrap("ww", "light_y", |index: i32| {
+            light(index).map(|(_, y, _)| y)
+        })?
+        .func_wrap("ww", "light_z", |index: i32| {
+            light(index).map(|(_, _, z)| z)
+        })?
+        .func_wrap("ww", "max_z", || COORDS.max_z())?
+        .func_wrap("ww", "random", |mut caller: Caller<'_, HostState>| {
+            caller.data_mut().rng.random::<f32>()
+        })?
+        .func_wrap("ww", "elapsed", |caller: Caller<'_, HostState>| {
+            caller.data().frame.elapsed_secs()
+        })?
+        .func_wrap("ww", "delta", |caller: Caller<'_, HostState>| {
+            caller.data().frame.delta_secs()
+        })?
+        .func_wrap(
+            "ww",
+            "log",
+            |caller: Caller<'_, HostState>, ptr: i32, len: i32| -> Result<(), wasmi::Error> {
+                let memory = caller
+                    .get_export("memory")
+                    .and_then(|export| export.into_memory())
+                    .ok_or_else(|| wasmi::Error::new("plugin has no `memory` export"))?;
+
+                // Check the string against the memory before touching it, so that a huge length
+                // traps instead of allocating
+                let start = ptr as u32 as usize;
+                let bytes = usize::try_from(len)
+                    .ok()
+                    .and_then(|len| memory.data(&caller).get(start..start.checked_add(len)?))
+                    .ok_or_else(|| {
+                        wasmi::Error::new(format!(
+                            "the string at {start} with length {len} is outside the plugin's memory"
+                        ))
+                    })?;
+
+                info!(
+                    plugin = caller.data().name,
+                    "{}",
+                    String::from_utf8_lossy(bytes)
+                );
+                Ok(())
+            },
+        )?;
+
+    Ok(())
+}
+
+/// A loaded and initialised instance of a plugin.
+struct LoadedPlugin {
+    /// The store which owns the instance.
+    store: Store<HostState>,
+
+    /// The memory of the plugin.
+    memory: Memory,
+
+    /// The `next_frame` function of the plugin.
+    next_frame: TypedFunc<(), (i32, i32)>,
+}
+
+impl LoadedPlugin {
+    /// Load the given WASM, instantiate it, and call `init` with the given config.
+    fn new(name: &str, wasm: &[u8], config_json: &str) -> Result<Self, PluginError> {
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module = Module::new(&engine, wasm)?;
+
+        let mut store = Store::new(
+            &engine,
+            HostState {
+                name: name.to_string(),
+                limits: StoreLimitsBuilder::new().memory_size(MEMORY_LIMIT).build(),
+                rng: crate::effects::rng!(),
+                frame: FrameContext::default(),
+            },
+        );
+        store.limiter(|state| &mut state.limits);
+        store.set_fuel(INIT_FUEL).map_err(wasmi::Error::from)?;
+
+        let mut linker = Linker::new(&engine);
+        define_host_functions(&mut linker)?;
+        let instance: Instance = linker
+            .instantiate(&mut store, &module)?
+            .start(&mut store)
+            .map_err(wasm_error)?;
+
+        let memory = instance
+            .get_memory(&store, "memory")
+            .ok_or(PluginError::MissingExport("memory"))?;
+        let next_frame = instance
+            .get_typed_func(&store, "next_frame")
+            .map_err(|_| PluginError::MissingExport("next_frame"))?;
+
+        if let Ok(init) = instance.get_typed_func::<(i32, i32), ()>(&store, "init") {
+            let alloc = instance
+                .get_typed_func::<i32, i32>(&store, "alloc")
+                .map_err(|_| PluginError::MissingExport("alloc"))?;
+
+            let len = config_json.len() as i32;
+            let ptr = alloc.call(&mut store, len).map_err(wasm_error)?;
+            memory
+                .write(&mut store, ptr as u32 as usize, config_json.as_bytes())
+                .map_err(wasmi::Error::from)?;
+            init.call(&mut store, (ptr, len)).map_err(wasm_error)?;
+        }
+
+        Ok(Self {
+            store,
+            memory,
+            next_frame,
+        })
+    }
+
+    /// Call `next_frame` with the given frame timing and read the frame out of the memory of the
+    /// plugin.
+    fn next_frame(
+        &mut self,
+        ctx: &FrameContext,
+    ) -> Result<Option<(FrameType, Duration)>, PluginError> {
+        self.store.data_mut().frame = *ctx;
+        self.store
+            .set_fuel(FRAME_FUEL)
+            .map_err(wasmi::Error::from)?;
+        let (ptr, milliseconds) = self
+            .next_frame
+            .call(&mut self.store, ())
+            .map_err(wasm_error)?;
+
+        // A negative number of milliseconds ends the effect
+        let Ok(milliseconds) = u64::try_from(milliseconds) else {
+            return Ok(None);
+        };
+
+        let mut bytes = vec![0; COORDS.lights_num() * 3];
+        self.memory
+            .read(&self.store, ptr as u32 as usize, &mut bytes)
+            .map_err(|_| PluginError::BadFrame(ptr as u32))?;
+
+        let colours: Vec<RGBArray> = bytes.as_chunks::<3>().0.to_vec();
+
+        Ok(Some((
+            FrameType::RawData(colours),
+            Duration::from_millis(milliseconds),
+        )))
+    }
+}
+
+/// An effect which runs a WASM plugin. See the [module docs](self).
+pub struct PluginEffect {
+    /// The name of the plugin.
+    name: String,
+
+    /// The path to the plugin file.
+    path: PathBuf,
+
+    /// The path to the config file of the plugin, which might not exist.
+    config_path: PathBuf,
+
+    /// When the plugin file was last modified when it was loaded, to know when to reload it.
+    modified: Option<SystemTime>,
+
+    /// The running plugin.
+    plugin: LoadedPlugin,
+}
+
+impl fmt::Debug for PluginEffect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PluginEffect")
+            .field("name", &self.name)
+            .field("path", &self.path)
+            .field("modified", &self.modified)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PluginEffect {
+    /// Load the plugin with the given name from [`plugins_dir`] and initialise it.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an error if there's no plugin with that name, if the plugin can't be
+    /// read, doesn't match the ABI, or if its initialisation fails.
+    pub fn load(name: &str) -> Result<Self, PluginError> {
+        Self::load_from(&plugins_dir(), name)
+    }
+
+    /// Load the plugin with the given name from the given directory and initialise it.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an error if there's no plugin with that name, if the plugin can't be
+    /// read, doesn't match the ABI, or if its initialisation fails.
+    pub fn load_from(dir: &Path, name: &str) -> Result<Self, PluginError> {
+        if !is_plugin_in(dir, name) {
+            return Err(PluginError::UnknownPlugin(name.to_string()));
+        }
+
+        let path = dir.join(format!("{name}.{PLUGIN_EXTENSION}"));
+        let config_path = dir.join(format!("{name}.{PLUGIN_CONFIG_EXTENSION}"));
+
+        let modified = modified_time(&path);
+        let plugin = Self::instantiate(name, &path, &config_path)?;
+
+        info!(name, "Loaded plugin effect");
+        Ok(Self {
+            name: name.to_string(),
+            path,
+            config_path,
+            modified,
+            plugin,
+        })
+    }
+
+    /// Read the plugin and its config from the given paths and instantiate it.
+    fn instantiate(
+        name: &str,
+        path: &Path,
+        config_path: &Path,
+    ) -> Result<LoadedPlugin, PluginError> {
+        let wasm = fs::read(path)?;
+        let config_json = match fs::read_to_string(config_path) {
+            Ok(json) => json,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => "{}".to_string(),
+            Err(error) => return Err(error.into()),
+        };
+
+        LoadedPlugin::new(name, &wasm, &config_json)
+    }
+
+    /// Get the name of the plugin.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Reload the plugin if its file has changed since it was loaded, and return whether it was
+    /// reloaded. See the [module docs](self#hot-reloading).
+    pub fn reload_if_changed(&mut self) -> bool {
+        let modified = modified_time(&self.path);
+        if modified == self.modified {
+            return false;
+        }
+
+        // Even if this load fails, we don't want to try again until the file changes again
+        self.modified = modified;
+        match Self::instantiate(&self.name, &self.path, &self.config_path) {
+            Ok(plugin) => {
+                info!(name = self.name, "Reloaded plugin effect");
+                self.plugin = plugin;
+                true
+            }
+            Err(error) => {
+                warn!(%error, name = self.name, "Unable to reload plugin effect, keeping the old one");
+                false
+            }
+        }
+    }
+
+    /// Reload the plugin if it has changed, then call `next_frame` with the given frame timing
+    /// and return the frame and how long to show it for, or `None` if the effect has finished.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an error if the plugin traps, runs out of [`FRAME_FUEL`], or returns
+    /// a frame which isn't in its memory.
+    pub fn try_next_frame(
+        &mut self,
+        ctx: &FrameContext,
+    ) -> Result<Option<(FrameType, Duration)>, PluginError> {
+        self.reload_if_changed();
+        self.plugin.next_frame(ctx)
+    }
+
+    /// Like [`try_next_frame`](Self::try_next_frame), but log any error and end the effect.
+    pub fn next_frame(&mut self, ctx: &FrameContext) -> Option<(FrameType, Duration)> {
+        self.try_next_frame(ctx).unwrap_or_else(|error| {
+            warn!(%error, name = self.name, "Stopping plugin effect");
+            None
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_unwrap::OptionExt;
+
+    /// A plugin which lights every light whose x coordinate is positive in the colour from its
+    /// config, and ends after the given number of frames.
+    const POSITIVE_X: &str = r#"
+        (module
+            (import "ww" "lights_num" (func $lights_num (result i32)))
+            (import "ww" "light_x" (func $light_x (param i32) (result f32)))
+            (memory (export "memory") 1)
+            (global $frames (mut i32) (i32.const 0))
+            (global $colour (mut i32) (i32.const 0))
+
+            ;; The config is just a single byte for the red channel, like `{"r":"X"}`, so we
+            ;; don't need a JSON parser. It goes at 1024 and the frame goes at 4096
+            (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+            (func (export "init") (param $ptr i32) (param $len i32)
+                (global.set $colour (i32.load8_u (i32.add (local.get $ptr) (i32.const 6)))))
+
+            (func (export "next_frame") (result i32 i32)
+                (local $i i32)
+                (global.set $frames (i32.add (global.get $frames) (i32.const 1)))
+                (if (i32.gt_s (global.get $frames) (i32.const 3))
+                    (then (return (i32.const 0) (i32.const -1))))
+
+                (block $done
+                    (loop $next
+                        (br_if $done (i32.ge_s (local.get $i) (call $lights_num)))
+                        (i32.store8
+                            (i32.add (i32.const 4096) (i32.mul (local.get $i) (i32.const 3)))
+                            (select
+                                (global.get $colour)
+                                (i32.const 0)
+                                (f32.gt (call $light_x (local.get $i)) (f32.const 0))))
+                        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                        (br $next)))
+                (i32.const 4096)
+                (i32.const 40)))
+    "#;
+
+    /// A plugin which shows a single frame with every light off.
+    const ONE_FRAME: &str = r#"
+        (module
+            (memory (export "memory") 1)
+            (global $done (mut i32) (i32.const 0))
+            (func (export "next_frame") (result i32 i32)
+                (if (global.get $done) (then (return (i32.const 0) (i32.const -1))))
+                (global.set $done (i32.const 1))
+                (i32.const 0)
+                (i32.const 10)))
+    "#;
+
+    /// Create a fresh temporary directory for plugins.
+    fn temp_plugins_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("ww-plugins-test-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect_or_log("Should be able to create a temporary directory");
+        dir
+    }
+
+    /// Compile the WAT to WASM and write it as the plugin with the given name.
+    fn write_plugin(dir: &Path, name: &str, wat: &str) {
+        let wasm = wat::parse_str(wat).expect_or_log("The test plugin should be valid WAT");
+        fs::write(dir.join(format!("{name}.{PLUGIN_EXTENSION}")), wasm)
+            .expect_or_log("Should be able to write the plugin");
+    }
+
+    #[test]
+    fn positive_x_test() {
+        let dir = temp_plugins_dir("positive_x");
+        write_plugin(&dir, "positive_x", POSITIVE_X);
+        fs::write(dir.join("positive_x.json"), r#"{"r":"X"}"#)
+            .expect_or_log("Should be able to write the config");
+
+        let mut effect =
+            PluginEffect::load_from(&dir, "positive_x").expect_or_log("The plugin should load");
+
+        for _ in 0..3 {
+            let (frame, duration) = effect
+                .try_next_frame(&FrameContext::default())
+                .expect_or_log("The plugin should run")
+                .expect_or_log("The plugin should return a frame");
+            assert_eq!(duration, Duration::from_millis(40));
+
+            let FrameType::RawData(data) = frame else {
+                panic!("Plugins should return raw data, not {frame:?}");
+            };
+            assert_eq!(data.len(), COORDS.lights_num());
+            for (colour, &(x, _, _)) in data.iter().zip(COORDS.coords()) {
+                assert_eq!(*colour, [if x > 0. { b'X' } else { 0 }, 0, 0]);
+            }
+        }
+
+        assert!(matches!(
+            effect.try_next_frame(&FrameContext::default()),
+            Ok(None)
+        ));
+    }
+
+    #[test]
+    fn frame_context_test() {
+        let dir = temp_plugins_dir("frame_context");
+
+        // Show each frame for the number of milliseconds since the start, times 1000, plus the
+        // number of milliseconds since the last frame, so that we can check both
+        write_plugin(
+            &dir,
+            "timing",
+            r#"
+                (module
+                    (import "ww" "elapsed" (func $elapsed (result f32)))
+                    (import "ww" "delta" (func $delta (result f32)))
+                    (memory (export "memory") 1)
+
+                    (func $millis (param f32) (result i32)
+                        (i32.trunc_f32_s (f32.nearest (f32.mul (local.get 0) (f32.const 1000)))))
+
+                    (func (export "next_frame") (result i32 i32)
+                        (i32.const 0)
+                        (i32.add
+                            (i32.mul (call $millis (call $elapsed)) (i32.const 1000))
+                            (call $millis (call $delta)))))
+            "#,
+        );
+        let mut effect =
+            PluginEffect::load_from(&dir, "timing").expect_or_log("The plugin should load");
+
+        let mut clock = crate::SimulatedClock::default();
+        let mut durations = vec![];
+        for step in [30, 20] {
+            clock.advance(Duration::from_millis(step));
+            let (_, duration) = effect
+                .try_next_frame(&clock.tick())
+                .expect_or_log("The plugin should run")
+                .expect_or_log("The plugin should return a frame");
+            durations.push(duration.as_millis());
+        }
+
+        assert_eq!(durations, [30_000, 50_020], "the first frame has no delta");
+    }
+
+    #[test]
+    fn errors_test() {
+        let dir = temp_plugins_dir("errors");
+
+        assert!(matches!(
+            PluginEffect::load_from(&dir, "missing"),
+            Err(PluginError::UnknownPlugin(_))
+        ));
+
+        fs::write(dir.join("garbage.wasm"), "not wasm").expect_or_log("Should write the file");
+        assert!(matches!(
+            PluginEffect::load_from(&dir, "garbage"),
+            Err(PluginError::Wasm(_))
+        ));
+
+        write_plugin(
+            &dir,
+            "no_frames",
+            r#"(module (memory (export "memory") 1))"#,
+        );
+        assert!(matches!(
+            PluginEffect::load_from(&dir, "no_frames"),
+            Err(PluginError::MissingExport("next_frame"))
+        ));
+
+        write_plugin(
+            &dir,
+            "no_alloc",
+            r#"(module
+                (memory (export "memory") 1)
+                (func (export "init") (param i32 i32))
+                (func (export "next_frame") (result i32 i32) (i32.const 0) (i32.const 10)))"#,
+        );
+        assert!(matches!(
+            PluginEffect::load_from(&dir, "no_alloc"),
+            Err(PluginError::MissingExport("alloc"))
+        ));
+
+        write_plugin(
+            &dir,
+            "files",
+            r#"(module
+                (import "wasi_snapshot_preview1" "fd_write" (func (param i32 i32 i32 i32) (result i32)))
+                (memory (export "memory") 1)
+                (func (export "next_frame") (result i32 i32) (i32.const 0) (i32.const 10)))"#,
+        );
+        assert!(
+            matches!(
+                PluginEffect::load_from(&dir, "files"),
+                Err(PluginError::Wasm(_))
+            ),
+            "plugins can only import from `ww`"
+        );
+
+        write_plugin(
+            &dir,
+            "bad_frame",
+            r#"(module
+                (memory (export "memory") 1)
+                (func (export "next_frame") (result i32 i32) (i32.const 65535) (i32.const 10)))"#,
+        );
+        let mut effect =
+            PluginEffect::load_from(&dir, "bad_frame").expect_or_log("The plugin should load");
+        assert!(matches!(
+            effect.try_next_frame(&FrameContext::default()),
+            Err(PluginError::BadFrame(65535))
+        ));
+        assert_eq!(
+            effect.next_frame(&FrameContext::default()),
+            None,
+            "errors end the effect"
+        );
+    }
+
+    #[test]
+    fn fuel_test() {
+        let dir = temp_plugins_dir("fuel");
+
+        write_plugin(
+            &dir,
+            "slow_start",
+            r#"(module
+                (memory (export "memory") 1)
+                (func $forever (loop $l (br $l)))
+                (func (export "next_frame") (result i32 i32) (i32.const 0) (i32.const 10))
+                (start $forever))"#,
+        );
+        assert!(matches!(
+            PluginEffect::load_from(&dir, "slow_start"),
+            Err(PluginError::OutOfFuel)
+        ));
+
+        write_plugin(
+            &dir,
+            "slow_frame",
+            r#"(module
+                (memory (export "memory") 1)
+                (func (export "next_frame") (result i32 i32)
+                    (loop $l (br $l))
+                    (i32.const 0)
+                    (i32.const 10)))"#,
+        );
+        let mut effect =
+            PluginEffect::load_from(&dir, "slow_frame").expect_or_log("The plugin should load");
+        assert!(matches!(
+            effect.try_next_frame(&FrameContext::default()),
+            Err(PluginError::OutOfFuel)
+        ));
+
+        write_plugin(
+            &dir,
+            "greedy",
+            r#"(module
+                (memory (export "memory") 1)
+                (func $grow (drop (memory.grow (i32.const 1024))))
+                (func (export "next_frame") (result i32 i32) (i32.const 0) (i32.const 10))
+                (start $grow))"#,
+        );
+        let mut effect =
+            PluginEffect::load_from(&dir, "greedy").expect_or_log("The plugin should load");
+        assert_eq!(
+            effect.plugin.memory.data(&effect.plugin.store).len(),
+            1 << 16,
+            "memory can't grow past the limit"
+        );
+        assert!(effect.try_next_frame(&FrameContext::default()).is_ok());
+    }
+
+    #[test]
+    fn log_test() {
+        let dir = temp_plugins_dir("log");
+        let plugin = |len: i32| {
+            format!(
+                r#"(module
+                    (import "ww" "log" (func $log (param i32 i32)))
+                    (memory (export "memory") 1)
+                    (data (i32.const 0) "hello")
+                    (func (export "next_frame") (result i32 i32)
+                        (call $log (i32.const 0) (i32.const {len}))
+                        (i32.const 0)
+                        (i32.const 10)))"#
+            )
+        };
+
+        write_plugin(&dir, "hello", &plugin(5));
+        let mut effect =
+            PluginEffect::load_from(&dir, "hello").expect_or_log("The plugin should load");
+        assert!(effect.try_next_frame(&FrameContext::default()).is_ok());
+
+        for (name, len) in [
+            ("huge", i32::MAX),
+            ("negative", -1),
+            ("past_end", (1 << 16) + 1),
+        ] {
+            write_plugin(&dir, name, &plugin(len));
+            let mut effect =
+                PluginEffect::load_from(&dir, name).expect_or_log("The plugin should load");
+            assert!(
+                matches!(
+                    effect.try_next_frame(&FrameContext::default()),
+                    Err(PluginError::Wasm(_))
+                ),
+                "a {name} length should trap"
+            );
+        }
+    }
+
+    #[test]
+    fn hot_reload_test() {
+        let dir = temp_plugins_dir("hot_reload");
+        write_plugin(&dir, "reload", ONE_FRAME);
+
+        let mut effect =
+            PluginEffect::load_from(&dir, "reload").expect_or_log("The plugin should load");
+        assert!(!effect.reload_if_changed(), "the file hasn't changed");
+
+        let set_modified = |time: SystemTime| {
+            fs::File::options()
+                .write(true)
+                .open(dir.join("reload.wasm"))
+                .and_then(|file| file.set_modified(time))
+                .expect_or_log("Should be able to set the modified time");
+        };
+
+        // A broken plugin doesn't replace the working one
+        fs::write(dir.join("reload.wasm"), "not wasm").expect_or_log("Should write the file");
+        set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(1));
+        assert!(!effect.reload_if_changed());
+        assert!(
+            effect
+                .try_next_frame(&FrameContext::default())
+                .expect_or_log("The old plugin should run")
+                .is_some()
+        );
+        assert!(
+            effect
+                .try_next_frame(&FrameContext::default())
+                .expect_or_log("The old plugin should run")
+                .is_none()
+        );
+
+        write_plugin(&dir, "reload", ONE_FRAME);
+        set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(2));
+        assert!(
+            effect
+                .try_next_frame(&FrameContext::default())
+                .expect_or_log("The new plugin should run")
+                .is_some(),
+            "the new plugin should start from the beginning"
+        );
+        assert!(!effect.reload_if_changed());
+    }
+
+    #[test]
+    fn list_plugins_test() {
+        let dir = temp_plugins_dir("list");
+
+        for filename in ["b.wasm", "a.wasm", "a.json", "notes.txt"] {
+            fs::write(dir.join(filename), "").expect_or_log("Should be able to write a plugin");
+        }
+
+        assert_eq!(list_plugins_in(&dir), ["a", "b"]);
+        assert!(list_plugins_in(&dir.join("missing")).is_empty());
+
+        assert!(is_plugin_in(&dir, "a"));
+        assert!(!is_plugin_in(&dir, "notes"));
+    }
+
+    #[test]
+    fn path_traversal_test() {
+        let dir = temp_plugins_dir("escape");
+        let plugins = dir.join("plugins");
+        fs::create_dir_all(&plugins)
+            .expect_or_log("Should be able to create a temporary directory");
+
+        // A valid plugin and config just outside the plugins directory
+        write_plugin(&dir, "outside", POSITIVE_X);
+        fs::write(dir.join("outside.json"), r#"{"r":"X"}"#)
+            .expect_or_log("Should be able to write the config");
+
+        for name in ["../outside", "..", "./../outside", "..\\outside"] {
+            assert!(
+                !is_plugin_in(&plugins, name),
+                "{name} should not be a plugin"
+            );
+            assert!(
+                matches!(
+                    PluginEffect::load_from(&plugins, name),
+                    Err(PluginError::UnknownPlugin(_))
+                ),
+                "{name} should not load"
+            );
+        }
+    }
+}
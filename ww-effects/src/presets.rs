@@ -0,0 +1,474 @@
+//! This module handles named presets of effect configs, like `LavaLamp – Icy`.
+//!
+//! Every effect has some factory presets which ship with the code: its default config, which is
+//! called [`"Default"`](DEFAULT_PRESET_NAME), and anything from
+//! [`EffectConfig::factory_presets`](crate::traits::EffectConfig::factory_presets). Users can save
+//! their own presets with a [`PresetStore`], which keeps them in `DATA_DIR/presets`, with one file
+//! per effect. A user preset with the same name as a factory preset hides the factory preset.
+//!
+//! Presets can be shared between trees as a [`PresetBundle`], which can be written as either RON or
+//! JSON and can hold presets for any number of effects.
+
+use crate::list::{EffectConfigDispatchList, EffectNameList};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::PathBuf};
+use strum::IntoEnumIterator;
+use thiserror::Error;
+use tracing::warn;
+use tracing_unwrap::ResultExt;
+
+/// The name of the factory preset which every effect has, which is just its default config.
+pub const DEFAULT_PRESET_NAME: &str = "Default";
+
+/// A named config for an effect.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Preset {
+    /// The name of the preset, which is unique among the presets of its effect.
+    pub name: String,
+
+    /// The config of the preset, which also determines which effect it's for.
+    pub config: EffectConfigDispatchList,
+}
+
+impl Preset {
+    /// Create a new preset with the given name and config.
+    pub fn new(name: impl Into<String>, config: EffectConfigDispatchList) -> Self {
+        Self {
+            name: name.into(),
+            config,
+        }
+    }
+
+    /// Get the effect that this preset is for.
+    pub fn effect(&self) -> EffectNameList {
+        EffectNameList::from(&self.config)
+    }
+
+    /// Get the name of this preset along with the name of its effect, like `LavaLamp – Icy`.
+    pub fn full_name(&self) -> String {
+        format!("{} – {}", self.config.effect_name(), self.name)
+    }
+}
+
+/// An error from reading a [`PresetBundle`].
+#[derive(Debug, Error)]
+pub enum PresetBundleError {
+    /// The bundle looked like JSON but couldn't be read.
+    #[error("unable to read preset bundle as JSON: {0}")]
+    Json(#[from] serde_json::Error),
+
+    /// The bundle looked like RON but couldn't be read.
+    #[error("unable to read preset bundle as RON: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+}
+
+/// A list of presets for any effects, used to share presets between trees.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PresetBundle {
+    /// The presets in this bundle.
+    pub presets: Vec<Preset>,
+}
+
+impl PresetBundle {
+    /// Write this bundle as pretty RON.
+    pub fn to_ron(&self) -> String {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default().struct_names(true))
+            .expect_or_log("A preset bundle should be serializable")
+    }
+
+    /// Write this bundle as pretty JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect_or_log("A preset bundle should be serializable")
+    }
+
+    /// Read a bundle written with either [`to_ron`](Self::to_ron) or [`to_json`](Self::to_json).
+    /// Text which starts with `{` is read as JSON and anything else is read as RON.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an error if the text isn't a valid bundle.
+    pub fn parse(text: &str) -> Result<Self, PresetBundleError> {
+        if text.trim_start().starts_with('{') {
+            Ok(serde_json::from_str(text)?)
+        } else {
+            Ok(ron::from_str(text)?)
+        }
+    }
+
+    /// Clamp every config in this bundle into range, logging a warning for each config which was
+    /// out of range. See [`EffectConfig::validate`](crate::traits::EffectConfig::validate).
+    pub fn clamp_to_ranges(&mut self) {
+        for preset in &mut self.presets {
+            if let Err(errors) = preset.config.validate() {
+                for error in errors {
+                    warn!(
+                        %error,
+                        preset = preset.full_name(),
+                        "Preset is out of range, so clamping it"
+                    );
+                }
+                preset.config.clamp_to_ranges();
+            }
+        }
+    }
+}
+
+/// The factory and user presets for every effect.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PresetLibrary {
+    /// The factory presets, which can't be changed.
+    pub factory: Vec<Preset>,
+
+    /// The presets which have been saved by users.
+    pub user: Vec<Preset>,
+}
+
+impl PresetLibrary {
+    /// Get the usable presets for the given effect, along with whether each one is a user preset.
+    /// Factory presets come first, except for those hidden by a user preset with the same name.
+    pub fn presets_for(&self, effect: EffectNameList) -> Vec<(&Preset, bool)> {
+        let user: Vec<_> = self
+            .user
+            .iter()
+            .filter(|preset| preset.effect() == effect)
+            .collect();
+
+        let mut presets: Vec<_> = self
+            .factory
+            .iter()
+            .filter(|preset| {
+                preset.effect() == effect && !user.iter().any(|user| user.name == preset.name)
+            })
+            .map(|preset| (preset, false))
+            .collect();
+        presets.extend(user.into_iter().map(|preset| (preset, true)));
+        presets
+    }
+
+    /// Find the preset of the given effect with the given name, preferring user presets.
+    pub fn find(&self, effect: EffectNameList, name: &str) -> Option<&Preset> {
+        self.user
+            .iter()
+            .chain(&self.factory)
+            .find(|preset| preset.effect() == effect && preset.name == name)
+    }
+
+    /// Get a bundle of all the user presets, ready to be shared.
+    pub fn export(&self) -> PresetBundle {
+        PresetBundle {
+            presets: self.user.clone(),
+        }
+    }
+}
+
+/// The presets saved by users, stored in one file per effect.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PresetStore {
+    /// The directory containing the preset files.
+    dir: PathBuf,
+}
+
+impl PresetStore {
+    /// Get the store in `DATA_DIR/presets`.
+    pub fn new() -> Self {
+        Self::in_dir(format!(
+            "{}/presets",
+            std::env::var("DATA_DIR").expect_or_log("DATA_DIR must be defined")
+        ))
+    }
+
+    /// Get the store in the given directory.
+    pub fn in_dir(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Get the file which holds the user presets for the given effect.
+    fn filename(&self, effect: EffectNameList) -> PathBuf {
+        use heck::ToSnakeCase;
+
+        self.dir
+            .join(format!("{}.ron", effect.effect_name().to_snake_case()))
+    }
+
+    /// Load the user presets for the given effect. An unreadable file is [backed
+    /// up](crate::config_file::back_up_unreadable_file) and treated as empty, and presets in the
+    /// file which are for a different effect are ignored.
+    pub fn load(&self, effect: EffectNameList) -> Vec<Preset> {
+        let filename = self.filename(effect);
+        let Ok(text) = fs::read_to_string(&filename) else {
+            return Vec::new();
+        };
+
+        match PresetBundle::parse(&text) {
+            Ok(bundle) => bundle
+                .presets
+                .into_iter()
+                .filter(|preset| {
+                    let matches = preset.effect() == effect;
+                    if !matches {
+                        warn!(
+                            preset = preset.full_name(),
+                            ?filename,
+                            "Ignoring preset for the wrong effect"
+                        );
+                    }
+                    matches
+                })
+                .collect(),
+            Err(error) => {
+                let backup = filename
+                    .to_str()
+                    .and_then(crate::config_file::back_up_unreadable_file);
+                warn!(
+                    %error,
+                    ?backup,
+                    ?filename,
+                    "Unable to read preset file, so ignoring it"
+                );
+                Vec::new()
+            }
+        }
+    }
+
+    /// Save the user presets for the given effect, replacing any which were there before.
+    fn save(&self, effect: EffectNameList, presets: Vec<Preset>) {
+        let filename = self.filename(effect);
+        let result = fs::create_dir_all(&self.dir).and_then(|()| {
+            if presets.is_empty() {
+                fs::remove_file(&filename).or_else(|error| match error.kind() {
+                    std::io::ErrorKind::NotFound => Ok(()),
+                    _ => Err(error),
+                })
+            } else {
+                fs::write(&filename, PresetBundle { presets }.to_ron())
+            }
+        });
+
+        if let Err(error) = result {
+            warn!(%error, ?filename, "Unable to save presets");
+        }
+    }
+
+    /// Get the factory presets of every effect along with all the user presets.
+    pub fn library(&self) -> PresetLibrary {
+        PresetLibrary {
+            factory: EffectNameList::iter()
+                .flat_map(|effect| effect.config_name().factory_presets())
+                .collect(),
+            user: EffectNameList::iter()
+                .flat_map(|effect| self.load(effect))
+                .collect(),
+        }
+    }
+
+    /// Save a user preset, replacing any user preset of the same effect with the same name.
+    pub fn add(&self, preset: Preset) {
+        self.import(PresetBundle {
+            presets: vec![preset],
+        });
+    }
+
+    /// Delete the user preset of the given effect with the given name, and return whether it
+    /// existed. Factory presets can't be deleted.
+    pub fn delete(&self, effect: EffectNameList, name: &str) -> bool {
+        let mut presets = self.load(effect);
+        let count = presets.len();
+        presets.retain(|preset| preset.name != name);
+
+        let deleted = presets.len() != count;
+        if deleted {
+            self.save(effect, presets);
+        }
+        deleted
+    }
+
+    /// Save every preset in the bundle as a user preset, replacing any user presets of the same
+    /// effect with the same name. Configs which are out of range are clamped.
+    pub fn import(&self, mut bundle: PresetBundle) {
+        bundle.clamp_to_ranges();
+
+        for effect in EffectNameList::iter() {
+            let (new, rest): (Vec<_>, Vec<_>) = bundle
+                .presets
+                .into_iter()
+                .partition(|preset| preset.effect() == effect);
+            bundle.presets = rest;
+
+            if new.is_empty() {
+                continue;
+            }
+
+            let mut presets = self.load(effect);
+            for preset in new {
+                match presets.iter_mut().find(|old| old.name == preset.name) {
+                    Some(old) => *old = preset,
+                    None => presets.push(preset),
+                }
+            }
+            self.save(effect, presets);
+        }
+    }
+}
+
+impl Default for PresetStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::list::EffectConfigNameList;
+    use tracing_unwrap::OptionExt;
+
+    /// Get a fresh store in a temporary directory.
+    fn temp_store(name: &str) -> PresetStore {
+        let dir =
+            std::env::temp_dir().join(format!("ww-presets-test-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        PresetStore::in_dir(dir)
+    }
+
+    /// Get a lava lamp preset with the given name and fadeoff.
+    fn lava_lamp(name: &str, fadeoff: f32) -> Preset {
+        let mut config = EffectConfigNameList::LavaLampConfig.default_dispatch();
+        config.set_numeric_field("fadeoff", fadeoff);
+        Preset::new(name, config)
+    }
+
+    #[test]
+    fn factory_presets_test() {
+        for config_name in EffectConfigNameList::iter() {
+            let presets = config_name.factory_presets();
+
+            assert_eq!(
+                presets.first(),
+                Some(&Preset::new(
+                    DEFAULT_PRESET_NAME,
+                    config_name.default_dispatch()
+                )),
+                "the first preset of {config_name:?} should be the default"
+            );
+            assert!(
+                presets.len() > 1,
+                "{config_name:?} should have factory presets other than the default"
+            );
+
+            for (i, preset) in presets.iter().enumerate() {
+                assert_eq!(preset.effect(), EffectNameList::from(config_name));
+                assert!(
+                    preset.config.validate().is_ok(),
+                    "{} should be in range",
+                    preset.full_name()
+                );
+                assert!(
+                    presets[..i].iter().all(|other| other.name != preset.name),
+                    "{} should have a unique name",
+                    preset.full_name()
+                );
+            }
+        }
+
+        assert!(
+            EffectConfigNameList::LavaLampConfig
+                .factory_presets()
+                .iter()
+                .any(|preset| preset.full_name() == "LavaLamp – Icy")
+        );
+    }
+
+    #[test]
+    fn bundle_test() {
+        let bundle = PresetBundle {
+            presets: vec![
+                lava_lamp("Dim", 0.1),
+                Preset::new("Slow", EffectConfigNameList::HelixConfig.default_dispatch()),
+            ],
+        };
+
+        let ron = bundle.to_ron();
+        let json = bundle.to_json();
+        assert!(ron.starts_with("PresetBundle("));
+        assert!(json.starts_with('{'));
+
+        assert_eq!(PresetBundle::parse(&ron).ok(), Some(bundle.clone()));
+        assert_eq!(PresetBundle::parse(&json).ok(), Some(bundle));
+
+        assert!(matches!(
+            PresetBundle::parse("{ \"presets\": 3 }"),
+            Err(PresetBundleError::Json(_))
+        ));
+        assert!(matches!(
+            PresetBundle::parse("PresetBundle(presets: 3)"),
+            Err(PresetBundleError::Ron(_))
+        ));
+    }
+
+    #[test]
+    fn store_test() {
+        let store = temp_store("store");
+        let effect = EffectNameList::LavaLamp;
+        assert!(store.load(effect).is_empty());
+
+        store.add(lava_lamp("Dim", 0.1));
+        store.add(lava_lamp("Bright", 1.));
+        store.add(lava_lamp("Dim", 0.2));
+        assert_eq!(
+            store.load(effect),
+            [lava_lamp("Dim", 0.2), lava_lamp("Bright", 1.)],
+            "saving a preset with the same name should replace it"
+        );
+
+        assert!(store.delete(effect, "Dim"));
+        assert!(!store.delete(effect, "Dim"), "it's already gone");
+        assert!(!store.delete(effect, DEFAULT_PRESET_NAME));
+        assert_eq!(store.load(effect), [lava_lamp("Bright", 1.)]);
+
+        store.import(PresetBundle {
+            presets: vec![
+                lava_lamp("Bright", 0.5),
+                lava_lamp("Too bright", 100.),
+                Preset::new("Slow", EffectConfigNameList::HelixConfig.default_dispatch()),
+            ],
+        });
+        assert_eq!(
+            store.load(effect),
+            [lava_lamp("Bright", 0.5), lava_lamp("Too bright", 1.5)],
+            "imported presets should replace old ones and be clamped"
+        );
+        assert_eq!(store.load(EffectNameList::Helix).len(), 1);
+    }
+
+    #[test]
+    fn library_test() {
+        let store = temp_store("library");
+        let effect = EffectNameList::LavaLamp;
+        let factory_count = EffectConfigNameList::LavaLampConfig.factory_presets().len();
+
+        store.add(lava_lamp(DEFAULT_PRESET_NAME, 0.5));
+        store.add(lava_lamp("Mine", 0.7));
+
+        let library = store.library();
+        let presets = library.presets_for(effect);
+        assert_eq!(
+            presets.len(),
+            factory_count + 1,
+            "the user preset called Default should hide the factory one"
+        );
+        assert!(presets[..factory_count - 1].iter().all(|(_, user)| !user));
+        assert!(presets[factory_count - 1..].iter().all(|(_, user)| *user));
+
+        assert_eq!(
+            library
+                .find(effect, DEFAULT_PRESET_NAME)
+                .expect_or_log("LavaLamp should have a default preset")
+                .config
+                .get_numeric_field("fadeoff"),
+            Some(0.5),
+            "user presets are found before factory presets"
+        );
+        assert!(library.find(EffectNameList::Helix, "Mine").is_none());
+        assert_eq!(library.export().presets.len(), 2);
+    }
+}
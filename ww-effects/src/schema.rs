@@ -0,0 +1,576 @@
+//! This module describes the fields of effect configs so that they can be discovered and edited
+//! without knowing anything about the effect.
+//!
+//! Every config has a [`ConfigSchema`], which is generated by the
+//! [`BaseEffectConfig`](effect_proc_macros::BaseEffectConfig) derive from the types and doc
+//! comments of its fields, along with any `#[schema(...)]` hints. The schema can be exported as a
+//! [JSON Schema](https://json-schema.org/) for other tools, or used to render a GUI for the config
+//! with [`render_schema_gui`].
+
+use crate::traits::BaseEffectConfig;
+use egui::Ui;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use thiserror::Error;
+
+/// The type of a single field in a config.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum FieldType {
+    /// A floating point number.
+    Float,
+
+    /// An integer, which can only be negative if it's signed.
+    Integer {
+        /// Whether the integer can be negative.
+        signed: bool,
+    },
+
+    /// A boolean flag.
+    Bool,
+
+    /// An RGB colour with channels from 0 to 255.
+    Colour,
+
+    /// An array with a fixed length.
+    Array {
+        /// The type of each element.
+        element: Box<Self>,
+
+        /// The number of elements.
+        len: usize,
+    },
+
+    /// A list of any length.
+    List(Box<Self>),
+
+    /// Any other type, like an enum, with the name of the Rust type.
+    Other(String),
+}
+
+impl FieldType {
+    /// Get the JSON Schema for a value of this type.
+    fn json_schema(&self) -> Map<String, Value> {
+        let schema = match self {
+            Self::Float => json!({ "type": "number" }),
+            Self::Integer { signed: true } => json!({ "type": "integer" }),
+            Self::Integer { signed: false } => json!({ "type": "integer", "minimum": 0 }),
+            Self::Bool => json!({ "type": "boolean" }),
+            Self::Colour => json!({
+                "type": "array",
+                "items": { "type": "integer", "minimum": 0, "maximum": 255 },
+                "minItems": 3,
+                "maxItems": 3,
+                "x-colour": true,
+            }),
+            Self::Array { element, len } => json!({
+                "type": "array",
+                "items": element.json_schema(),
+                "minItems": len,
+                "maxItems": len,
+            }),
+            Self::List(element) => json!({ "type": "array", "items": element.json_schema() }),
+            Self::Other(type_name) => json!({ "x-rust-type": type_name }),
+        };
+
+        match schema {
+            Value::Object(map) => map,
+            _ => Map::new(),
+        }
+    }
+}
+
+/// The description of a single field in a config.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FieldSchema {
+    /// The name of the field, as it appears in the config file.
+    pub name: String,
+
+    /// The doc comment of the field, in Markdown.
+    pub description: String,
+
+    /// The type of the field.
+    pub field_type: FieldType,
+
+    /// The range of valid values for a numeric field, inclusive at both ends.
+    pub range: Option<(f64, f64)>,
+
+    /// Whether values outside [`range`](Self::range) are invalid. If this is false, then the range
+    /// is only a suggestion for the GUI.
+    pub clamped: bool,
+
+    /// The value of this field in the default config.
+    pub default: Value,
+}
+
+impl FieldSchema {
+    /// Get a human-readable label for this field, like `Drops per second` for `drops_per_second`.
+    pub fn label(&self) -> String {
+        let words = self.name.replace('_', " ");
+        let mut chars = words.chars();
+        chars.next().map_or_else(String::new, |first| {
+            first.to_uppercase().chain(chars).collect()
+        })
+    }
+
+    /// Get the JSON Schema for this field.
+    pub fn json_schema(&self) -> Value {
+        let mut schema = self.field_type.json_schema();
+
+        if !self.description.is_empty() {
+            schema.insert("description".to_string(), json!(self.description));
+        }
+        match self.range {
+            Some((min, max)) if self.clamped => {
+                schema.insert("minimum".to_string(), json!(min));
+                schema.insert("maximum".to_string(), json!(max));
+            }
+            Some((min, max)) => {
+                schema.insert("x-suggested-range".to_string(), json!([min, max]));
+            }
+            None => {}
+        }
+        schema.insert("default".to_string(), self.default.clone());
+
+        Value::Object(schema)
+    }
+
+    /// Make a slider to edit this field in a hand-written GUI, using the range from the schema so
+    /// that the GUI and the validation can't disagree. Fields without a range get a slider over the
+    /// whole range of the type, which is never what you want, so give every slider field a range.
+    pub fn slider<'value, Num: egui::emath::Numeric>(
+        &self,
+        value: &'value mut Num,
+    ) -> egui::Slider<'value> {
+        let range = self.range.map_or(Num::MIN..=Num::MAX, |(min, max)| {
+            Num::from_f64(min)..=Num::from_f64(max)
+        });
+        egui::Slider::new(value, range).clamp_to_range(self.clamped)
+    }
+
+    /// Render the GUI to edit the JSON value of this field and return whether it has changed.
+    /// Fields which aren't numbers, bools, or colours can't be edited here and are skipped.
+    pub fn render_gui(&self, value: &mut Value, ui: &mut Ui) -> bool {
+        let label = self.label();
+
+        match &self.field_type {
+            FieldType::Float => {
+                let mut number = value.as_f64().unwrap_or_default();
+                let changed = match self.range {
+                    Some((min, max)) => ui
+                        .add(
+                            egui::Slider::new(&mut number, min..=max)
+                                .clamp_to_range(self.clamped)
+                                .text(label),
+                        )
+                        .changed(),
+                    None => {
+                        ui.horizontal(|ui| {
+                            ui.label(label);
+                            ui.add(egui::DragValue::new(&mut number).speed(0.01))
+                                .changed()
+                        })
+                        .inner
+                    }
+                };
+
+                if changed {
+                    *value = json!(number);
+                }
+                changed
+            }
+            FieldType::Integer { signed } => {
+                let mut number = value.as_i64().unwrap_or_default();
+                let changed = match self.range {
+                    Some((min, max)) => ui
+                        .add(
+                            egui::Slider::new(&mut number, min as i64..=max as i64)
+                                .clamp_to_range(self.clamped)
+                                .text(label),
+                        )
+                        .changed(),
+                    None => {
+                        ui.horizontal(|ui| {
+                            ui.label(label);
+                            let drag = egui::DragValue::new(&mut number);
+                            let drag = if *signed {
+                                drag
+                            } else {
+                                drag.clamp_range(0..=i64::MAX)
+                            };
+                            ui.add(drag).changed()
+                        })
+                        .inner
+                    }
+                };
+
+                if changed {
+                    *value = json!(number);
+                }
+                changed
+            }
+            FieldType::Bool => {
+                let mut flag = value.as_bool().unwrap_or_default();
+                let changed = ui.checkbox(&mut flag, label).changed();
+
+                if changed {
+                    *value = json!(flag);
+                }
+                changed
+            }
+            FieldType::Colour => {
+                let Ok(mut colour) = serde_json::from_value::<[u8; 3]>(value.clone()) else {
+                    return false;
+                };
+                let changed = ui
+                    .horizontal(|ui| {
+                        let changed = ui.color_edit_button_srgb(&mut colour).changed();
+                        ui.label(label);
+                        changed
+                    })
+                    .inner;
+
+                if changed {
+                    *value = json!(colour);
+                }
+                changed
+            }
+            FieldType::Array { .. } | FieldType::List(_) | FieldType::Other(_) => false,
+        }
+    }
+}
+
+/// A numeric field of a config which is outside the range given in its schema.
+#[derive(Clone, Debug, PartialEq, Error)]
+#[error("`{field}` is {value}, but it must be between {min} and {max}")]
+pub struct RangeError {
+    /// The name of the field.
+    pub field: String,
+
+    /// The value of the field.
+    pub value: f64,
+
+    /// The smallest valid value of the field.
+    pub min: f64,
+
+    /// The largest valid value of the field.
+    pub max: f64,
+}
+
+/// The description of all the fields in an effect config.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ConfigSchema {
+    /// The name of the effect that this config belongs to.
+    pub effect_name: String,
+
+    /// The doc comment of the config, in Markdown.
+    pub description: String,
+
+    /// The fields of the config, in the order that they're declared.
+    pub fields: Vec<FieldSchema>,
+}
+
+impl ConfigSchema {
+    /// Get the schema of the field with the given name.
+    pub fn field(&self, name: &str) -> Option<&FieldSchema> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+
+    /// Make a slider to edit the field with the given name. See [`FieldSchema::slider`].
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the schema has no field with that name, which is always a typo in the
+    /// GUI of the effect.
+    pub fn slider<'value, Num: egui::emath::Numeric>(
+        &self,
+        name: &str,
+        value: &'value mut Num,
+    ) -> egui::Slider<'value> {
+        self.field(name)
+            .unwrap_or_else(|| panic!("{} config has no field called `{name}`", self.effect_name))
+            .slider(value)
+    }
+
+    /// Check every numeric field of the given config, serialized as JSON, against the ranges in
+    /// this schema, and return an error for each field which is out of range. Fields with
+    /// [unclamped](FieldSchema::clamped) ranges are never out of range.
+    pub fn validate(&self, config: &Value) -> Vec<RangeError> {
+        self.fields
+            .iter()
+            .filter(|field| field.clamped)
+            .filter_map(|field| {
+                let (min, max) = field.range?;
+                let value = config.get(&field.name)?.as_f64()?;
+
+                (!(min..=max).contains(&value)).then(|| RangeError {
+                    field: field.name.clone(),
+                    value,
+                    min,
+                    max,
+                })
+            })
+            .collect()
+    }
+
+    /// Clamp every numeric field of the given config, serialized as JSON, into the range in this
+    /// schema. See [`validate`](Self::validate).
+    pub fn clamp(&self, config: &mut Value) {
+        for error in self.validate(config) {
+            let Some(value) = config.get_mut(&error.field) else {
+                continue;
+            };
+
+            let clamped = error.value.clamp(error.min, error.max);
+            *value = if value.is_f64() {
+                json!(clamped)
+            } else {
+                json!(clamped.round() as i64)
+            };
+        }
+    }
+
+    /// Export this schema as a [JSON Schema](https://json-schema.org/) describing the config file.
+    ///
+    /// Colour fields are marked with `"x-colour": true`, and fields with types that JSON Schema
+    /// can't describe, like enums, are marked with their Rust type name in `"x-rust-type"`. No
+    /// fields are required, since missing fields in config files take their default values.
+    pub fn to_json_schema(&self) -> Value {
+        let properties: Map<String, Value> = self
+            .fields
+            .iter()
+            .map(|field| (field.name.clone(), field.json_schema()))
+            .collect();
+
+        json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "title": format!("{} config", self.effect_name),
+            "description": self.description,
+            "type": "object",
+            "properties": properties,
+            "additionalProperties": false,
+        })
+    }
+}
+
+/// Check every numeric field of the given config against the ranges in its [`ConfigSchema`]. This
+/// is the default implementation of [`EffectConfig::validate`](crate::traits::EffectConfig::validate).
+///
+/// # Errors
+///
+/// This function returns an error for each field which is out of range.
+pub fn validate_schema_ranges<T: BaseEffectConfig>(config: &T) -> Result<(), Vec<RangeError>> {
+    let Ok(config) = serde_json::to_value(config) else {
+        return Ok(());
+    };
+
+    let errors = T::schema().validate(&config);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Clamp every numeric field of the given config into the range in its [`ConfigSchema`]. This is
+/// the default implementation of
+/// [`EffectConfig::clamp_to_ranges`](crate::traits::EffectConfig::clamp_to_ranges).
+pub fn clamp_to_schema_ranges<T: BaseEffectConfig>(config: &mut T) {
+    let Ok(mut value) = serde_json::to_value(&*config) else {
+        return;
+    };
+    T::schema().clamp(&mut value);
+
+    if let Ok(clamped) = serde_json::from_value(value) {
+        *config = clamped;
+    }
+}
+
+/// Render a GUI to edit the given config using its [`ConfigSchema`] and return whether the config
+/// has changed. This is the default implementation of
+/// [`EffectConfig::render_options_gui`](crate::traits::EffectConfig::render_options_gui).
+pub fn render_schema_gui<T: BaseEffectConfig>(config: &mut T, ui: &mut Ui) -> bool {
+    let Ok(Value::Object(mut values)) = serde_json::to_value(&*config) else {
+        return false;
+    };
+
+    let mut config_changed = false;
+    for field in &T::schema().fields {
+        if let Some(value) = values.get_mut(&field.name) {
+            config_changed |= field.render_gui(value, ui);
+        }
+    }
+
+    if config_changed && let Ok(new_config) = serde_json::from_value(Value::Object(values)) {
+        *config = new_config;
+        true
+    } else {
+        false
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "config-impls")]
+mod tests {
+    use super::*;
+    use crate::{
+        effects::configs::{HelixConfig, SplitPlaneConfig},
+        list::EffectConfigNameList,
+    };
+    use strum::IntoEnumIterator;
+    use tracing_unwrap::{OptionExt, ResultExt};
+
+    #[test]
+    fn helix_schema_test() {
+        let schema = EffectConfigNameList::HelixConfig.schema();
+        assert_eq!(schema.effect_name, "Helix");
+        assert_eq!(schema.description, "The config for the [`Helix`] effect.");
+
+        let pitch = schema
+            .field("pitch")
+            .expect_or_log("Helix should have a pitch");
+        assert_eq!(pitch.field_type, FieldType::Float);
+        assert_eq!(pitch.range, Some((0.2, 10.)));
+        assert_eq!(pitch.label(), "Pitch");
+        assert!(
+            pitch.default.is_number(),
+            "the default should be read from the default config"
+        );
+
+        let strands = schema
+            .field("strands")
+            .expect_or_log("Helix should have strands");
+        assert_eq!(strands.field_type, FieldType::Integer { signed: false });
+        assert_eq!(strands.range, Some((1., 12.)));
+
+        let clockwise = schema
+            .field("clockwise")
+            .expect_or_log("Helix should have clockwise");
+        assert_eq!(clockwise.field_type, FieldType::Bool);
+        assert_eq!(clockwise.range, None);
+
+        assert_eq!(
+            schema.field("colours").map(|field| &field.field_type),
+            Some(&FieldType::List(Box::new(FieldType::Colour)))
+        );
+        assert_eq!(
+            schema
+                .field("background_colour")
+                .map(|field| &field.field_type),
+            Some(&FieldType::Colour)
+        );
+    }
+
+    #[test]
+    fn validate_test() {
+        let schema = EffectConfigNameList::HelixConfig.schema();
+        let mut config = serde_json::to_value(HelixConfig {
+            pitch: 20.,
+            strands: 0,
+            ..Default::default()
+        })
+        .expect_or_log("Configs should serialize to JSON");
+
+        let errors = schema.validate(&config);
+        assert_eq!(
+            errors
+                .iter()
+                .map(|error| error.field.as_str())
+                .collect::<Vec<_>>(),
+            ["strands", "pitch"]
+        );
+        assert_eq!(
+            errors[1].to_string(),
+            "`pitch` is 20, but it must be between 0.2 and 10"
+        );
+
+        schema.clamp(&mut config);
+        assert!(schema.validate(&config).is_empty(), "clamping fixes errors");
+        assert_eq!(config["pitch"], 10.);
+        assert_eq!(config["strands"], 1);
+
+        // Unclamped ranges are only suggestions
+        let schema = EffectConfigNameList::SplitPlaneConfig.schema();
+        let config = serde_json::to_value(SplitPlaneConfig {
+            rotation_speed: 100.,
+            ..Default::default()
+        })
+        .expect_or_log("Configs should serialize to JSON");
+        assert!(
+            schema.validate(&config).is_empty(),
+            "unclamped ranges are valid"
+        );
+    }
+
+    #[test]
+    fn json_schema_test() {
+        let json = EffectConfigNameList::DebugOneByOneConfig.json_schema();
+
+        assert_eq!(json["title"], "DebugOneByOne config");
+        assert_eq!(json["type"], "object");
+        assert_eq!(json["properties"]["colour"]["x-colour"], true);
+        assert_eq!(json["properties"]["colour"]["minItems"], 3);
+        assert!(
+            json.get("required").is_none(),
+            "every field has a default, so none are required"
+        );
+    }
+
+    #[test]
+    fn all_schemas_match_configs_test() {
+        for config_name in EffectConfigNameList::iter() {
+            let schema = config_name.schema();
+            let config = serde_json::to_value(config_name.default_dispatch())
+                .expect_or_log("Configs should serialize to JSON");
+
+            // The dispatch list serializes as `{ "NameConfig": { ... } }`
+            let fields = config
+                .as_object()
+                .and_then(|map| map.values().next())
+                .and_then(Value::as_object)
+                .expect_or_log("Configs should serialize to JSON objects");
+
+            // JSON objects are sorted by key, so we sort the schema fields to compare them
+            let mut names: Vec<&str> = schema
+                .fields
+                .iter()
+                .map(|field| field.name.as_str())
+                .collect();
+            names.sort_unstable();
+            assert_eq!(
+                names,
+                fields.keys().map(String::as_str).collect::<Vec<_>>(),
+                "the schema of {config_name:?} should have every field of the config"
+            );
+
+            for field in &schema.fields {
+                assert_eq!(
+                    field.default, fields[&field.name],
+                    "the default of {}.{} should match the default config",
+                    schema.effect_name, field.name
+                );
+                assert!(
+                    !field.description.is_empty(),
+                    "{}.{} should have a doc comment",
+                    schema.effect_name,
+                    field.name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn all_option_guis_render_test() {
+        // Hand-written GUIs get their slider ranges from the schema by name, so this checks that
+        // every slider is for a field which exists
+        let ctx = egui::Context::default();
+        let _ = ctx.run(egui::RawInput::default(), |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                for config_name in EffectConfigNameList::iter() {
+                    config_name
+                        .default_dispatch()
+                        .render_full_options_gui(ctx, ui);
+                }
+            });
+        });
+    }
+}
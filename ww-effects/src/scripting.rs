@@ -0,0 +1,803 @@
+//! This module handles effects written as [Rhai](https://rhai.rs) scripts, which are loaded from
+//! `DATA_DIR/effects` while the server is running, so they don't need to be compiled in.
+//!
+//! # Writing a script
+//!
+//! A script is a file like `DATA_DIR/effects/my_effect.rhai`, which appears in the effect list as
+//! `Script: my_effect`. It must define a `next_frame` function, which is called for every frame
+//! and returns the frame along with how many milliseconds to show it for, or `()` to end the
+//! effect. It can also define an `init` function, which is called once when the effect starts.
+//! Both functions can keep the state of the effect in the object map `this`, since Rhai functions
+//! can't see variables from outside themselves.
+//!
+//! ```text
+//! fn init() {
+//!     this.height = 0.0;
+//! }
+//!
+//! fn next_frame() {
+//!     if this.height > max_z() {
+//!         return ();
+//!     }
+//!     this.height += 0.05;
+//!
+//!     let plane = plane(vec3(0.0, 0.0, 1.0), this.height, 0.1);
+//!     let frame = frame_3d([frame_object(plane, [0, 200, 255], 0.1)], false);
+//!     [frame, 50]
+//! }
+//! ```
+//!
+//! The functions available to scripts mirror [`ww_frame`]. Numbers passed to them should be
+//! floats like `1.0` rather than integers like `1`, except for the channels of colours, which are
+//! arrays like `[255, 0, 0]`.
+//!
+//! - `vec3(x, y, z)` makes a vector, with `x`, `y`, and `z` properties, the usual arithmetic
+//!   operators, and the `dot`, `cross`, `length`, `normalize`, and `distance` functions.
+//! - `coords()` gets the position of every light as an array of vectors, with `lights_num()`,
+//!   `max_z()`, and `centre()` to describe them. See [`ww_gift_coords::COORDS`].
+//! - `plane(normal, k, threshold)`, `sphere(centre, radius)`, `split_plane(normal, k, blend,
+//!   positive_colour, negative_colour)`, and `catmull_rom_spline(points, threshold, start_colour,
+//!   end_colour)` make an [`Object`].
+//! - `frame_object(object, colour, fadeoff)` makes a [`FrameObject`].
+//! - `frame_3d(frame_objects, blend)` makes a frame from a [`Frame3D`], `raw_data(colours)` makes
+//!   a frame with a colour for each light, and `off()` makes a frame with every light off.
+//! - `random()` gets a random float in `[0, 1)` and `random_vector()` gets a random unit vector.
+//! - `elapsed()` gets the number of seconds since the effect started and `delta()` gets the
+//!   number of seconds since the last frame. Scripts which move things should move them by
+//!   `delta()` so that they keep the same speed when frames are late. See [`crate::timing`].
+//!
+//! # Sandboxing
+//!
+//! Scripts can't read files or import modules, and `print` and `debug` go to the server log. Each
+//! call to a script has a time limit, which is [`INIT_TIME_LIMIT`] for `init` and anything at the
+//! top level of the script, and [`FRAME_TIME_LIMIT`] for `next_frame`. A script which goes over
+//! its time limit or runs into an error is stopped, and the effect ends.
+
+use crate::timing::FrameContext;
+use rand::{Rng, rngs::StdRng};
+use rhai::{
+    AST, Array, CallFnOptions, Dynamic, Engine, EvalAltResult, Map, Scope,
+    module_resolvers::DummyModuleResolver,
+};
+use std::{
+    cell::{Cell, RefCell},
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+use tracing::{debug, info, warn};
+use tracing_unwrap::ResultExt;
+use ww_frame::{Frame3D, FrameObject, FrameType, Object, RGBArray, random_vector};
+use ww_gift_coords::COORDS;
+
+/// The file extension of scripts.
+pub const SCRIPT_EXTENSION: &str = "rhai";
+
+/// The time limit for running `init` and the top level of a script.
+pub const INIT_TIME_LIMIT: Duration = Duration::from_secs(1);
+
+/// The time limit for a single call to `next_frame`.
+pub const FRAME_TIME_LIMIT: Duration = Duration::from_millis(100);
+
+/// How many operations to run between checks of the time limit, since getting the time for every
+/// operation would slow scripts down a lot.
+const OPERATIONS_PER_TIME_CHECK: u64 = 1024;
+
+/// An error from loading or running a script.
+#[derive(Debug, Error)]
+pub enum ScriptError {
+    /// The script file couldn't be read.
+    #[error("unable to read script: {0}")]
+    Io(#[from] io::Error),
+
+    /// There's no script with the given name. See [`is_script_in`].
+    #[error("there is no script called {0:?}")]
+    UnknownScript(String),
+
+    /// The script isn't valid Rhai.
+    #[error("unable to compile script: {0}")]
+    Compile(#[from] rhai::ParseError),
+
+    /// The script ran into an error or went over its time limit.
+    #[error("error in script: {0}")]
+    Runtime(#[from] Box<EvalAltResult>),
+
+    /// The script doesn't have a `next_frame` function.
+    #[error("script has no `next_frame` function")]
+    MissingNextFrame,
+
+    /// The `next_frame` function returned the wrong type.
+    #[error("`next_frame` should return `[frame, milliseconds]` or `()`, not {0}")]
+    BadReturn(String),
+}
+
+/// Get the directory which contains the scripts.
+pub fn scripts_dir() -> PathBuf {
+    PathBuf::from(format!(
+        "{}/effects",
+        std::env::var("DATA_DIR").expect_or_log("DATA_DIR must be defined")
+    ))
+}
+
+/// Get the names of all the scripts in [`scripts_dir`], in alphabetical order.
+pub fn list_scripts() -> Vec<String> {
+    list_scripts_in(&scripts_dir())
+}
+
+/// Get the names of all the scripts in the given directory, in alphabetical order. The name of a
+/// script is its filename without the extension.
+pub fn list_scripts_in(dir: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            if path.extension()? == SCRIPT_EXTENSION {
+                Some(path.file_stem()?.to_str()?.to_string())
+            } else {
+                None
+            }
+        })
+        .collect();
+    names.sort();
+    names
+}
+
+/// Check whether there's a script with the given name in [`scripts_dir`]. See [`is_script_in`].
+pub fn is_script(name: &str) -> bool {
+    is_script_in(&scripts_dir(), name)
+}
+
+/// Check whether there's a script with the given name in the given directory.
+///
+/// Names with path separators or `..` in them are never scripts, even if a file of that name
+/// exists, so that clients can't get the server to read files outside the directory.
+pub fn is_script_in(dir: &Path, name: &str) -> bool {
+    !name.contains(['/', '\\'])
+        && !name.contains("..")
+        && list_scripts_in(dir).iter().any(|script| script == name)
+}
+
+/// Convert a Rhai value to an `f32`, accepting integers as well as floats.
+fn to_float(value: &Dynamic) -> Result<f32, Box<EvalAltResult>> {
+    value
+        .as_float()
+        .or_else(|_| value.as_int().map(|int| int as f32))
+        .map_err(|type_name| format!("Expected a number, not {type_name}").into())
+}
+
+/// Convert a Rhai array like `[255, 0, 0]` to a colour.
+fn to_colour(array: &Array) -> Result<RGBArray, Box<EvalAltResult>> {
+    let [r, g, b] = array.as_slice() else {
+        return Err(format!("Expected a colour like [255, 0, 0], not {array:?}").into());
+    };
+
+    let channel = |value: &Dynamic| -> Result<u8, Box<EvalAltResult>> {
+        Ok(to_float(value)?.round().clamp(0., 255.) as u8)
+    };
+    Ok([channel(r)?, channel(g)?, channel(b)?])
+}
+
+/// Convert every element of a Rhai array to the given type.
+fn cast_all<T: Clone + 'static>(
+    array: Array,
+    type_name: &str,
+) -> Result<Vec<T>, Box<EvalAltResult>> {
+    array
+        .into_iter()
+        .map(|item| {
+            let item_type = item.type_name();
+            item.try_cast::<T>()
+                .ok_or_else(|| format!("Expected {type_name}, not {item_type}").into())
+        })
+        .collect()
+}
+
+/// Register the vector type and its functions with the engine.
+fn register_vec3(engine: &mut Engine) {
+    use glam::Vec3;
+
+    engine
+        .register_type_with_name::<Vec3>("Vec3")
+        .register_fn(
+            "vec3",
+            |x: Dynamic, y: Dynamic, z: Dynamic| -> Result<Vec3, Box<EvalAltResult>> {
+                Ok(Vec3::new(to_float(&x)?, to_float(&y)?, to_float(&z)?))
+            },
+        )
+        .register_get_set("x", |v: &mut Vec3| v.x, |v: &mut Vec3, x: f32| v.x = x)
+        .register_get_set("y", |v: &mut Vec3| v.y, |v: &mut Vec3, y: f32| v.y = y)
+        .register_get_set("z", |v: &mut Vec3| v.z, |v: &mut Vec3, z: f32| v.z = z)
+        .register_fn("+", |a: Vec3, b: Vec3| a + b)
+        .register_fn("-", |a: Vec3, b: Vec3| a - b)
+        .register_fn("-", |a: Vec3| -a)
+        .register_fn("*", |a: Vec3, k: f32| a * k)
+        .register_fn("*", |k: f32, a: Vec3| k * a)
+        .register_fn("/", |a: Vec3, k: f32| a / k)
+        .register_fn("dot", |a: Vec3, b: Vec3| a.dot(b))
+        .register_fn("cross", |a: Vec3, b: Vec3| a.cross(b))
+        .register_fn("length", |a: Vec3| a.length())
+        .register_fn("normalize", |a: Vec3| a.normalize_or_zero())
+        .register_fn("distance", |a: Vec3, b: Vec3| a.distance(b))
+        .register_fn("to_string", |a: &mut Vec3| a.to_string())
+        .register_fn("to_debug", |a: &mut Vec3| format!("{a:?}"));
+}
+
+/// Register the functions to read the coordinates of the lights with the engine.
+fn register_coords(engine: &mut Engine) {
+    use glam::Vec3;
+
+    let coords: Rc<Array> = Rc::new(
+        COORDS
+            .coords()
+            .iter()
+            .map(|&(x, y, z)| Dynamic::from(Vec3::new(x, y, z)))
+            .collect(),
+    );
+
+    engine
+        .register_fn("coords", move || (*coords).clone())
+        .register_fn("lights_num", || COORDS.lights_num() as i64)
+        .register_fn("max_z", || COORDS.max_z())
+        .register_fn("centre", || {
+            let (x, y, z) = COORDS.center();
+            Vec3::new(x, y, z)
+        });
+}
+
+/// Register the frame types and the functions to build them with the engine.
+fn register_frames(engine: &mut Engine) {
+    use glam::Vec3;
+
+    engine
+        .register_type_with_name::<Object>("Object")
+        .register_type_with_name::<FrameObject>("FrameObject")
+        .register_type_with_name::<FrameType>("Frame")
+        .register_fn("plane", |normal: Vec3, k: f32, threshold: f32| {
+            Object::Plane {
+                normal,
+                k,
+                threshold,
+            }
+        })
+        .register_fn("sphere", |center: Vec3, radius: f32| Object::Sphere {
+            center,
+            radius,
+        })
+        .register_fn(
+            "split_plane",
+            |normal: Vec3,
+             k: f32,
+             blend: f32,
+             positive: Array,
+             negative: Array|
+             -> Result<Object, Box<EvalAltResult>> {
+                Ok(Object::SplitPlane {
+                    normal,
+                    k,
+                    blend,
+                    positive_side_colour: to_colour(&positive)?,
+                    negative_side_colour: to_colour(&negative)?,
+                })
+            },
+        )
+        .register_fn(
+            "catmull_rom_spline",
+            |points: Array,
+             threshold: f32,
+             start: Array,
+             end: Array|
+             -> Result<Object, Box<EvalAltResult>> {
+                Ok(Object::CatmullRomSpline {
+                    points: cast_all::<Vec3>(points, "Vec3")?.into_boxed_slice(),
+                    threshold,
+                    start_colour: to_colour(&start)?,
+                    end_colour: to_colour(&end)?,
+                })
+            },
+        )
+        .register_fn(
+            "frame_object",
+            |object: Object,
+             colour: Array,
+             fadeoff: f32|
+             -> Result<FrameObject, Box<EvalAltResult>> {
+                Ok(FrameObject {
+                    object,
+                    colour: to_colour(&colour)?,
+                    fadeoff,
+                })
+            },
+        )
+        .register_fn(
+            "frame_3d",
+            |objects: Array, blend: bool| -> Result<FrameType, Box<EvalAltResult>> {
+                Ok(FrameType::Frame3D(Frame3D::new(
+                    cast_all(objects, "FrameObject")?,
+                    blend,
+                )))
+            },
+        )
+        .register_fn(
+            "raw_data",
+            |colours: Array| -> Result<FrameType, Box<EvalAltResult>> {
+                colours
+                    .iter()
+                    .map(|colour| {
+                        colour
+                            .read_lock::<Array>()
+                            .ok_or_else(|| {
+                                format!("Expected a colour like [255, 0, 0], not {colour}").into()
+                            })
+                            .and_then(|colour| to_colour(&colour))
+                    })
+                    .collect::<Result<_, _>>()
+                    .map(FrameType::RawData)
+            },
+        )
+        .register_fn("off", || FrameType::Off);
+}
+
+/// An effect which runs a Rhai script. See the [module docs](self).
+pub struct ScriptEffect {
+    /// The name of the script.
+    name: String,
+
+    /// The sandboxed engine which runs the script.
+    engine: Engine,
+
+    /// The compiled script.
+    ast: AST,
+
+    /// The variables at the top level of the script.
+    scope: Scope<'static>,
+
+    /// The state of the effect, which the script sees as `this`.
+    state: Dynamic,
+
+    /// When the current call to the script has to finish by.
+    deadline: Rc<Cell<Instant>>,
+
+    /// The timing of the current frame, for `elapsed` and `delta`.
+    frame: Rc<Cell<FrameContext>>,
+}
+
+impl fmt::Debug for ScriptEffect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScriptEffect")
+            .field("name", &self.name)
+            .field("state", &self.state)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ScriptEffect {
+    /// Load the script with the given name from [`scripts_dir`] and initialise it.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an error if there's no script with that name, if the script can't be
+    /// read or compiled, or if its initialisation fails.
+    pub fn load(name: &str) -> Result<Self, ScriptError> {
+        Self::load_from(&scripts_dir(), name)
+    }
+
+    /// Load the script with the given name from the given directory and initialise it.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an error if there's no script with that name, if the script can't be
+    /// read or compiled, or if its initialisation fails.
+    pub fn load_from(dir: &Path, name: &str) -> Result<Self, ScriptError> {
+        if !is_script_in(dir, name) {
+            return Err(ScriptError::UnknownScript(name.to_string()));
+        }
+
+        let path = dir.join(format!("{name}.{SCRIPT_EXTENSION}"));
+        Self::from_source(name, &fs::read_to_string(path)?)
+    }
+
+    /// Compile the given source code and initialise the script.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an error if the script can't be compiled or doesn't have a
+    /// `next_frame` function, or if its initialisation fails.
+    pub fn from_source(name: &str, source: &str) -> Result<Self, ScriptError> {
+        let deadline = Rc::new(Cell::new(Instant::now() + INIT_TIME_LIMIT));
+        let frame = Rc::new(Cell::new(FrameContext::default()));
+        let engine = Self::sandboxed_engine(name, Rc::clone(&deadline), Rc::clone(&frame));
+        let ast = engine.compile(source)?;
+
+        let has_function = |fn_name: &str| ast.iter_functions().any(|f| f.name == fn_name);
+        if !has_function("next_frame") {
+            return Err(ScriptError::MissingNextFrame);
+        }
+        let has_init = has_function("init");
+
+        let mut effect = Self {
+            name: name.to_string(),
+            engine,
+            ast,
+            scope: Scope::new(),
+            state: Dynamic::from_map(Map::new()),
+            deadline,
+            frame,
+        };
+
+        effect.deadline.set(Instant::now() + INIT_TIME_LIMIT);
+        effect
+            .engine
+            .run_ast_with_scope(&mut effect.scope, &effect.ast)?;
+
+        // Anything returned from `init` is ignored, since the state should be in `this`
+        if has_init {
+            let _ = effect.call("init", INIT_TIME_LIMIT)?;
+        }
+
+        info!(name, "Loaded script effect");
+        Ok(effect)
+    }
+
+    /// Create an engine which can't touch anything outside the script and which stops the script
+    /// once it's past the deadline.
+    fn sandboxed_engine(
+        name: &str,
+        deadline: Rc<Cell<Instant>>,
+        frame: Rc<Cell<FrameContext>>,
+    ) -> Engine {
+        let mut engine = Engine::new();
+
+        engine
+            .set_module_resolver(DummyModuleResolver::new())
+            .disable_symbol("eval")
+            .set_max_call_levels(64)
+            .set_max_expr_depths(64, 32)
+            .set_max_string_size(1 << 16)
+            .set_max_array_size(1 << 16)
+            .set_max_map_size(1 << 12)
+            .on_progress(move |operations| {
+                (operations % OPERATIONS_PER_TIME_CHECK == 0 && Instant::now() > deadline.get())
+                    .then(|| Dynamic::from("the time limit"))
+            });
+
+        let print_name = name.to_string();
+        let debug_name = name.to_string();
+        engine
+            .on_print(move |text| info!(script = print_name, "{text}"))
+            .on_debug(move |text, _source, position| {
+                debug!(script = debug_name, %position, "{text}");
+            });
+
+        let rng: Rc<RefCell<StdRng>> = Rc::new(RefCell::new(crate::effects::rng!()));
+        engine
+            .register_fn("random", {
+                let rng = Rc::clone(&rng);
+                move || rng.borrow_mut().random::<f32>()
+            })
+            .register_fn("random_vector", move || {
+                random_vector(&mut *rng.borrow_mut())
+            });
+
+        engine
+            .register_fn("elapsed", {
+                let frame = Rc::clone(&frame);
+                move || frame.get().elapsed_secs()
+            })
+            .register_fn("delta", move || frame.get().delta_secs());
+
+        register_vec3(&mut engine);
+        register_coords(&mut engine);
+        register_frames(&mut engine);
+
+        engine
+    }
+
+    /// Call the script function with the given name, binding `this` to the state of the effect.
+    fn call(&mut self, fn_name: &str, time_limit: Duration) -> Result<Dynamic, ScriptError> {
+        self.deadline.set(Instant::now() + time_limit);
+
+        Ok(self.engine.call_fn_with_options(
+            CallFnOptions::new()
+                .eval_ast(false)
+                .bind_this_ptr(&mut self.state),
+            &mut self.scope,
+            &self.ast,
+            fn_name,
+            (),
+        )?)
+    }
+
+    /// Get the name of the script.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Call `next_frame` with the given frame timing and return the frame and how long to show it
+    /// for, or `None` if the effect has finished.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an error if the script fails, goes over [`FRAME_TIME_LIMIT`], or
+    /// returns something other than `[frame, milliseconds]` or `()`.
+    pub fn try_next_frame(
+        &mut self,
+        ctx: &FrameContext,
+    ) -> Result<Option<(FrameType, Duration)>, ScriptError> {
+        self.frame.set(*ctx);
+        let result = self.call("next_frame", FRAME_TIME_LIMIT)?;
+        if result.is_unit() {
+            return Ok(None);
+        }
+
+        let bad_return = |result: &Dynamic| ScriptError::BadReturn(result.type_name().to_string());
+        let Some(array) = result.read_lock::<Array>() else {
+            return Err(bad_return(&result));
+        };
+        let [frame, milliseconds] = array.as_slice() else {
+            return Err(bad_return(&result));
+        };
+
+        let frame = frame
+            .clone()
+            .try_cast::<FrameType>()
+            .ok_or_else(|| bad_return(&result))?;
+        let milliseconds = to_float(milliseconds).map_err(|_| bad_return(&result))?;
+
+        Ok(Some((
+            frame,
+            Duration::from_micros((milliseconds.max(0.) * 1000.).round() as u64),
+        )))
+    }
+
+    /// Like [`try_next_frame`](Self::try_next_frame), but log any error and end the effect.
+    pub fn next_frame(&mut self, ctx: &FrameContext) -> Option<(FrameType, Duration)> {
+        self.try_next_frame(ctx).unwrap_or_else(|error| {
+            warn!(%error, name = self.name, "Stopping script effect");
+            None
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_unwrap::OptionExt;
+
+    /// A script which moves a plane up the tree and then stops.
+    const RISING_PLANE: &str = r#"
+        fn init() {
+            this.height = 0.0;
+        }
+
+        fn next_frame() {
+            if this.height > max_z() {
+                return ();
+            }
+            this.height += 0.5;
+
+            let plane = plane(vec3(0, 0, 1), this.height, 0.1);
+            let frame = frame_3d([frame_object(plane, [0, 200, 255], 0.1)], false);
+            [frame, 50]
+        }
+    "#;
+
+    #[test]
+    fn rising_plane_test() {
+        let mut effect = ScriptEffect::from_source("rising_plane", RISING_PLANE)
+            .expect_or_log("The script should load");
+
+        let mut frames = 0;
+        while let Some((frame, duration)) = effect
+            .try_next_frame(&FrameContext::default())
+            .expect_or_log("The script should run")
+        {
+            let FrameType::Frame3D(frame) = frame else {
+                panic!("The script should return 3D frames, not {frame:?}");
+            };
+            assert_eq!(duration, Duration::from_millis(50));
+            assert_eq!(frame.to_raw_data().len(), COORDS.lights_num());
+
+            frames += 1;
+            assert!(frames < 100, "The script should stop");
+        }
+
+        assert_eq!(frames, (COORDS.max_z() / 0.5).floor() as usize + 1);
+    }
+
+    #[test]
+    fn frame_context_test() {
+        // Show each frame for as long as the script thinks that the last one took
+        let mut effect = ScriptEffect::from_source(
+            "timing",
+            r#"
+                fn init() {
+                    this.elapsed = [];
+                }
+
+                fn next_frame() {
+                    this.elapsed.push(elapsed());
+                    [off(), delta() * 1000.0]
+                }
+            "#,
+        )
+        .expect_or_log("The script should load");
+
+        let mut clock = crate::SimulatedClock::default();
+        let mut durations = vec![];
+        for step in [30, 20] {
+            clock.advance(Duration::from_millis(step));
+            let (_, duration) = effect
+                .try_next_frame(&clock.tick())
+                .expect_or_log("The script should run")
+                .expect_or_log("The script should return a frame");
+            durations.push(duration.as_millis());
+        }
+
+        assert_eq!(durations, [0, 20], "the first frame has no delta");
+        let elapsed: Vec<f32> = effect
+            .state
+            .read_lock::<Map>()
+            .and_then(|state| state.get("elapsed")?.clone().into_typed_array().ok())
+            .expect_or_log("The script should keep its elapsed times");
+        assert_eq!(
+            elapsed,
+            [30, 50].map(|millis| Duration::from_millis(millis).as_secs_f32())
+        );
+    }
+
+    #[test]
+    fn raw_data_test() {
+        let mut effect = ScriptEffect::from_source(
+            "raw",
+            r#"
+                fn next_frame() {
+                    let colours = [];
+                    for point in coords() {
+                        colours.push(if point.x > 0.0 { [255, 0, 0] } else { [0, 0, 255] });
+                    }
+                    [raw_data(colours), 10.5]
+                }
+            "#,
+        )
+        .expect_or_log("The script should load");
+
+        let (frame, duration) = effect
+            .try_next_frame(&FrameContext::default())
+            .expect_or_log("The script should run")
+            .expect_or_log("The script should return a frame");
+
+        assert_eq!(duration, Duration::from_micros(10_500));
+        let FrameType::RawData(data) = frame else {
+            panic!("The script should return raw data, not {frame:?}");
+        };
+        assert_eq!(data.len(), COORDS.lights_num());
+        for (colour, &(x, _, _)) in data.iter().zip(COORDS.coords()) {
+            assert_eq!(*colour, if x > 0. { [255, 0, 0] } else { [0, 0, 255] });
+        }
+    }
+
+    #[test]
+    fn errors_test() {
+        assert!(matches!(
+            ScriptEffect::from_source("missing", "fn init() {}"),
+            Err(ScriptError::MissingNextFrame)
+        ));
+        assert!(matches!(
+            ScriptEffect::from_source("syntax", "fn next_frame( {"),
+            Err(ScriptError::Compile(_))
+        ));
+        assert!(matches!(
+            ScriptEffect::from_source("no_eval", r#"fn next_frame() { eval("off()") }"#),
+            Err(ScriptError::Compile(_))
+        ));
+        assert!(matches!(
+            ScriptEffect::from_source("no_import", r#"import "secrets"; fn next_frame() {}"#),
+            Err(ScriptError::Runtime(_))
+        ));
+
+        let mut effect = ScriptEffect::from_source("bad_return", "fn next_frame() { 5 }")
+            .expect_or_log("The script should load");
+        assert!(matches!(
+            effect.try_next_frame(&FrameContext::default()),
+            Err(ScriptError::BadReturn(_))
+        ));
+        assert_eq!(
+            effect.next_frame(&FrameContext::default()),
+            None,
+            "errors end the effect"
+        );
+    }
+
+    #[test]
+    fn time_limit_test() {
+        let start = Instant::now();
+        let result = ScriptEffect::from_source("slow_init", "fn next_frame() {} loop {}");
+        assert!(
+            matches!(&result, Err(ScriptError::Runtime(error)) if matches!(**error, EvalAltResult::ErrorTerminated(..))),
+            "an infinite loop at the top level should be stopped, not {result:?}"
+        );
+        assert!(start.elapsed() < INIT_TIME_LIMIT * 5);
+
+        let mut effect = ScriptEffect::from_source("slow_frame", "fn next_frame() { loop {} }")
+            .expect_or_log("The script should load");
+        let start = Instant::now();
+        assert!(matches!(
+            effect.try_next_frame(&FrameContext::default()),
+            Err(ScriptError::Runtime(_))
+        ));
+        assert!(start.elapsed() < FRAME_TIME_LIMIT * 5);
+    }
+
+    #[test]
+    fn example_scripts_test() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../data/effects");
+        let names = list_scripts_in(&dir);
+        assert!(!names.is_empty(), "there should be some example scripts");
+
+        for name in names {
+            let source = fs::read_to_string(dir.join(format!("{name}.{SCRIPT_EXTENSION}")))
+                .expect_or_log("Should be able to read the script");
+            let mut effect = ScriptEffect::from_source(&name, &source)
+                .unwrap_or_else(|error| panic!("{name} should load: {error}"));
+
+            for _ in 0..10 {
+                if let Err(error) = effect.try_next_frame(&FrameContext::default()) {
+                    panic!("{name} should run: {error}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn list_scripts_test() {
+        let dir = std::env::temp_dir().join(format!("ww-scripts-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect_or_log("Should be able to create a temporary directory");
+
+        for filename in ["b.rhai", "a.rhai", "notes.txt"] {
+            fs::write(dir.join(filename), "").expect_or_log("Should be able to write a script");
+        }
+
+        assert_eq!(list_scripts_in(&dir), ["a", "b"]);
+        assert!(list_scripts_in(&dir.join("missing")).is_empty());
+
+        assert!(is_script_in(&dir, "a"));
+        assert!(!is_script_in(&dir, "notes"));
+        assert!(matches!(
+            ScriptEffect::load_from(&dir, "c"),
+            Err(ScriptError::UnknownScript(_))
+        ));
+    }
+
+    #[test]
+    fn path_traversal_test() {
+        let dir = std::env::temp_dir().join(format!("ww-scripts-escape-{}", std::process::id()));
+        let scripts = dir.join("effects");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&scripts)
+            .expect_or_log("Should be able to create a temporary directory");
+
+        // A valid script just outside the scripts directory
+        fs::write(dir.join("outside.rhai"), "fn next_frame() {}")
+            .expect_or_log("Should be able to write a script");
+
+        for name in ["../outside", "..", "./../outside", "..\\outside"] {
+            assert!(
+                !is_script_in(&scripts, name),
+                "{name} should not be a script"
+            );
+            assert!(
+                matches!(
+                    ScriptEffect::load_from(&scripts, name),
+                    Err(ScriptError::UnknownScript(_))
+                ),
+                "{name} should not load"
+            );
+        }
+    }
+}
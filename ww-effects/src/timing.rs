@@ -0,0 +1,284 @@
+//! This module handles the timing of effects with the [`FrameContext`] which is passed to every
+//! call to [`Effect::next_frame`](crate::traits::Effect::next_frame).
+//!
+//! The [`Duration`] returned by `next_frame` is only a request. When the server is busy, frames
+//! can arrive late, so effects which assume that their requested duration was honoured exactly
+//! will slow down and drift. Effects that move things should move them by
+//! [`delta`](FrameContext::delta) instead, which is how long it's actually been since the last
+//! frame, so that they run at the same speed at any frame rate.
+//!
+//! Effects which move in discrete steps, with a duration for each step in their config, can't
+//! move by a fraction of a step. They should use a [`StepSchedule`] instead, which takes extra
+//! steps to catch up when frames are shown for longer than they asked.
+//!
+//! The server makes the contexts with a [`FrameClock`] from the real time. Tests, benchmarks, and
+//! thumbnails use a [`SimulatedClock`] instead, which only moves when it's told to, so their output
+//! doesn't depend on how fast the machine is.
+
+use std::time::Duration;
+
+/// The frame rate that effects should aim for unless they have a reason to use their own.
+pub const DEFAULT_TARGET_FPS: u32 = 50;
+
+/// The longest [`delta`](FrameContext::delta) that a [`FrameClock`] will report. If the server
+/// stalls for longer than this, then effects just slow down rather than jumping forward, so that
+/// simulations don't become unstable.
+pub const MAX_DELTA: Duration = Duration::from_millis(250);
+
+/// The timing information for a single frame of an effect. See the [module docs](self).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FrameContext {
+    /// How long it's been since the effect started.
+    pub elapsed: Duration,
+
+    /// How long it's actually been since the last frame, which is zero for the first frame.
+    pub delta: Duration,
+
+    /// The frame rate that the effect should aim for.
+    pub target_fps: u32,
+}
+
+impl Default for FrameContext {
+    /// The context of the first frame of an effect at the [`DEFAULT_TARGET_FPS`].
+    fn default() -> Self {
+        Self {
+            elapsed: Duration::ZERO,
+            delta: Duration::ZERO,
+            target_fps: DEFAULT_TARGET_FPS,
+        }
+    }
+}
+
+impl FrameContext {
+    /// Get [`elapsed`](Self::elapsed) in seconds.
+    pub fn elapsed_secs(&self) -> f32 {
+        self.elapsed.as_secs_f32()
+    }
+
+    /// Get [`delta`](Self::delta) in seconds.
+    pub fn delta_secs(&self) -> f32 {
+        self.delta.as_secs_f32()
+    }
+
+    /// Get the time between frames at the [target frame rate](Self::target_fps), which is what an
+    /// effect should usually return from `next_frame`.
+    pub fn target_frame_time(&self) -> Duration {
+        Duration::from_secs(1) / self.target_fps.max(1)
+    }
+}
+
+/// Makes the [`FrameContext`] for each frame of an effect from the time since the effect started.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FrameClock {
+    /// The frame rate that the effect should aim for.
+    target_fps: u32,
+
+    /// The elapsed time of the last frame, or `None` if there hasn't been a frame yet.
+    last_elapsed: Option<Duration>,
+}
+
+impl Default for FrameClock {
+    fn default() -> Self {
+        Self::new(DEFAULT_TARGET_FPS)
+    }
+}
+
+impl FrameClock {
+    /// Create a new clock for an effect which hasn't had any frames yet.
+    pub fn new(target_fps: u32) -> Self {
+        Self {
+            target_fps,
+            last_elapsed: None,
+        }
+    }
+
+    /// Make the context for a frame at the given time since the effect started.
+    ///
+    /// The [`delta`](FrameContext::delta) is capped at [`MAX_DELTA`], and it can't be negative, so
+    /// it's zero if `elapsed` goes backwards.
+    pub fn tick(&mut self, elapsed: Duration) -> FrameContext {
+        let delta = self
+            .last_elapsed
+            .map_or(Duration::ZERO, |last| elapsed.saturating_sub(last))
+            .min(MAX_DELTA);
+        self.last_elapsed = Some(elapsed);
+
+        FrameContext {
+            elapsed,
+            delta,
+            target_fps: self.target_fps,
+        }
+    }
+}
+
+/// A [`FrameClock`] with a simulated time that only moves forwards when told to. See the [module
+/// docs](self).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SimulatedClock {
+    /// The clock which makes the contexts.
+    clock: FrameClock,
+
+    /// The simulated time since the effect started.
+    elapsed: Duration,
+}
+
+impl SimulatedClock {
+    /// Create a new simulated clock at the start of an effect.
+    pub fn new(target_fps: u32) -> Self {
+        Self {
+            clock: FrameClock::new(target_fps),
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    /// Make the context for a frame at the current simulated time.
+    pub fn tick(&mut self) -> FrameContext {
+        self.clock.tick(self.elapsed)
+    }
+
+    /// Move the simulated time forwards. Advancing by the duration that the effect returned
+    /// simulates a server that always shows frames on time.
+    pub fn advance(&mut self, duration: Duration) {
+        self.elapsed += duration;
+    }
+}
+
+/// Keeps an effect which moves in discrete steps on schedule when its frames are late. See the
+/// [module docs](self).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StepSchedule {
+    /// How long the effect asked to show its last frame for.
+    requested: Duration,
+
+    /// How far behind schedule the effect is.
+    behind: Duration,
+}
+
+impl StepSchedule {
+    /// Record how much longer than requested the last frame was actually shown for. This should
+    /// be called at the start of every frame.
+    pub fn update(&mut self, ctx: &FrameContext) {
+        self.behind += ctx.delta.saturating_sub(self.requested);
+    }
+
+    /// Get how many extra steps of the given length the effect should take to catch up, and
+    /// count them as taken. A zero length step never needs to catch up.
+    pub fn catch_up_steps(&mut self, step: Duration) -> u32 {
+        if step.is_zero() {
+            return 0;
+        }
+
+        let steps = (self.behind.as_nanos() / step.as_nanos()) as u32;
+        self.behind -= step * steps;
+        steps
+    }
+
+    /// Shorten a pause by as much as the effect is behind schedule, and count that time as caught
+    /// up. This is for pauses which don't need to last as long as they were asked to.
+    pub fn shorten(&mut self, pause: Duration) -> Duration {
+        let cut = self.behind.min(pause);
+        self.behind -= cut;
+        pause - cut
+    }
+
+    /// Remember how long the effect asked to show this frame for, and return it so that it can be
+    /// returned from `next_frame`.
+    pub fn request(&mut self, duration: Duration) -> Duration {
+        self.requested = duration;
+        duration
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_clock_test() {
+        let mut clock = FrameClock::new(25);
+
+        let first = clock.tick(Duration::from_millis(10));
+        assert_eq!(first.elapsed, Duration::from_millis(10));
+        assert_eq!(first.delta, Duration::ZERO, "the first frame has no delta");
+        assert_eq!(first.target_fps, 25);
+        assert_eq!(first.target_frame_time(), Duration::from_millis(40));
+
+        let second = clock.tick(Duration::from_millis(55));
+        assert_eq!(second.delta, Duration::from_millis(45));
+        assert!((second.delta_secs() - 0.045).abs() < 1e-6);
+        assert!((second.elapsed_secs() - 0.055).abs() < 1e-6);
+
+        assert_eq!(
+            clock.tick(Duration::from_secs(10)).delta,
+            MAX_DELTA,
+            "long stalls should be capped"
+        );
+        assert_eq!(
+            clock.tick(Duration::from_secs(5)).delta,
+            Duration::ZERO,
+            "time going backwards shouldn't give a negative delta"
+        );
+    }
+
+    #[test]
+    fn step_schedule_test() {
+        let step = Duration::from_millis(40);
+        let mut schedule = StepSchedule::default();
+        let mut clock = SimulatedClock::default();
+
+        schedule.update(&clock.tick());
+        assert_eq!(
+            schedule.catch_up_steps(step),
+            0,
+            "the first frame isn't late"
+        );
+        assert_eq!(schedule.request(step), step);
+
+        // Shown for 130ms instead of 40ms, so 2 steps behind with 10ms left over
+        clock.advance(Duration::from_millis(130));
+        schedule.update(&clock.tick());
+        assert_eq!(schedule.catch_up_steps(step), 2);
+        assert_eq!(schedule.catch_up_steps(Duration::ZERO), 0);
+        schedule.request(step);
+
+        // Another 35ms late takes the left over time past a whole step
+        clock.advance(Duration::from_millis(75));
+        schedule.update(&clock.tick());
+        assert_eq!(schedule.catch_up_steps(step), 1);
+        schedule.request(Duration::from_millis(100));
+
+        clock.advance(Duration::from_millis(220));
+        schedule.update(&clock.tick());
+        assert_eq!(
+            schedule.shorten(Duration::from_millis(300)),
+            Duration::from_millis(175),
+            "the 5ms left over and the 120ms late should come off the pause"
+        );
+        assert_eq!(
+            schedule.shorten(Duration::from_millis(300)),
+            Duration::from_millis(300)
+        );
+    }
+
+    #[test]
+    fn simulated_clock_test() {
+        let mut clock = SimulatedClock::default();
+        assert_eq!(clock.tick(), FrameContext::default());
+
+        clock.advance(Duration::from_millis(20));
+        clock.advance(Duration::from_millis(30));
+        let context = clock.tick();
+        assert_eq!(context.elapsed, Duration::from_millis(50));
+        assert_eq!(context.delta, Duration::from_millis(50));
+
+        assert_eq!(
+            clock.tick().delta,
+            Duration::ZERO,
+            "the clock shouldn't move by itself"
+        );
+        assert_eq!(
+            FrameContext::default().target_frame_time(),
+            Duration::from_millis(20)
+        );
+    }
+}
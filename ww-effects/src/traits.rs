@@ -29,6 +29,7 @@
 //!
 //!     /// The config for the [`MyEffect`] effect.
 //!     #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, BaseEffectConfig)]
+//!     #[serde(default)]
 //!     pub struct MyEffectConfig {
 //!         // Fields to describe the config of the effect
 //!     }
@@ -45,7 +46,8 @@
 //!         fn render_options_gui(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui) -> bool {
 //!             let mut config_changed = false;
 //!
-//!             // Put relevant GUI code here
+//!             // Put relevant GUI code here. Make sliders with `Self::schema().slider(...)` so
+//!             // that they use the ranges from the `#[schema(...)]` attributes
 //!
 //!             config_changed
 //!         }
@@ -59,6 +61,7 @@
 //!
 //!     /// Describe the effect.
 //!     #[derive(Clone, Debug, PartialEq, BaseEffect)]
+//!     #[effect(tags = ["some", "tags"], unattended)]
 //!     pub struct MyEffect {
 //!         // Fields to define the current state of the effect
 //!         // The effect should NOT contain a field for its config. It should only use the config
@@ -72,9 +75,13 @@
 //!             Self {}
 //!         }
 //!
-//!         fn next_frame(&mut self, config: &MyEffectConfig) -> Option<(FrameType, Duration)> {
-//!             // Generate the next frame of the effect. See the trait docs for what this should
-//!             // return
+//!         fn next_frame(
+//!             &mut self,
+//!             config: &MyEffectConfig,
+//!             ctx: &FrameContext,
+//!         ) -> Option<(FrameType, Duration)> {
+//!             // Generate the next frame of the effect, moving things by `ctx.delta`. See the
+//!             // trait docs for what this should return
 //!         }
 //!
 //!         #[cfg(any(test, feature = "bench"))]
@@ -99,10 +106,28 @@
 //! ```
 //! You should look at the source code for the other effects to see how they do things.
 //!
-//! Once you've created your effect, the effect and its config should be publicly exported in
-//! `ww-effects/src/effects/chosen_category/mod.rs` and `ww-effects/src/effects/mod.rs`. Follow the
-//! examples of the other effects. Then add your effect to the
-//! [`effect_proc_macros::generate_lists_and_impls`] list in `ww-effects/src/lib.rs`.
+//! Numeric fields of the config should have a `#[schema(range = start..=end)]` attribute with the
+//! same range as their slider in the GUI, so that the [schema](crate::schema) of the config can
+//! tell other tools which values make sense.
+//!
+//! The `#[serde(default)]` attribute on the config means that a config file which is missing some
+//! fields, like one saved before a new field was added, still loads with the default values for
+//! those fields. If you rename a field or change what it means, then increase
+//! [`EffectConfig::VERSION`] and override [`EffectConfig::migrate`] to convert old config files.
+//!
+//! The first paragraph of the doc comment of the effect is its description in the client, and the
+//! `#[effect(...)]` attribute declares the rest of its [metadata](crate::metadata).
+//!
+//! If some other configs of your effect look good, then override
+//! [`EffectConfig::factory_presets`] to ship them as [presets](crate::presets).
+//!
+//! Once you've created your effect, declare its module with `pub mod` in
+//! `ww-effects/src/effects/chosen_category/mod.rs`. That's all you need to do. The build script of
+//! `ww-effects` finds every struct deriving [`BaseEffect`] in the modules of the categories,
+//! re-exports the effects and their configs from `chosen_category` and [`crate::effects`], and adds
+//! them to the [lists](crate::list) along with their [category](crate::EffectCategory). The build
+//! fails if a file in a category isn't declared. If you want a new category, create a directory
+//! with a `mod.rs` like the other categories and add it to `ww-effects/src/effects/mod.rs`.
 
 use egui::{Context, Ui};
 use serde::{Deserialize, Serialize};
@@ -111,12 +136,15 @@ use std::fs;
 #[cfg(feature = "config-trait")]
 use tracing_unwrap::ResultExt;
 
+#[cfg(feature = "effect-trait")]
+use crate::timing::FrameContext;
 #[cfg(feature = "effect-trait")]
 use std::time::Duration;
 #[cfg(feature = "effect-trait")]
 use ww_frame::FrameType;
 
-/// Save the given effect config to its appropriate config file.
+/// Save the given effect config to its appropriate config file, with a header for its
+/// [`VERSION`](EffectConfig::VERSION). See [`crate::config_file`].
 #[cfg(feature = "config-trait")]
 pub fn save_effect_config_to_file<T>(filename: &str, config: &T)
 where
@@ -124,8 +152,14 @@ where
 {
     let _ = fs::write(
         filename,
-        ron::ser::to_string_pretty(config, ron::ser::PrettyConfig::default().struct_names(true))
+        crate::config_file::add_version_header(
+            T::VERSION,
+            &ron::ser::to_string_pretty(
+                config,
+                ron::ser::PrettyConfig::default().struct_names(true),
+            )
             .expect_or_log("The effect config should be serializable"),
+        ),
     );
 }
 
@@ -157,7 +191,8 @@ pub(crate) mod private {
 }
 
 /// A trait needed for all implemtors of [`EffectConfig`]. This trait should be derived with
-/// [`effect_proc_macros::BaseEffectConfig`].
+/// [`effect_proc_macros::BaseEffectConfig`], which also generates the methods to access numeric
+/// fields by name.
 pub trait BaseEffectConfig:
     Clone + Default + PartialEq + Serialize + for<'de> Deserialize<'de> + private::Sealed
 {
@@ -166,19 +201,106 @@ pub trait BaseEffectConfig:
     /// implementation](../../effect_proc_macros/derive.BaseEffectConfig.html) will call [`<Self as
     /// EffectConfig>::render_options_gui`](EffectConfig::render_options_gui) in the middle.
     fn render_full_options_gui(&mut self, ctx: &Context, ui: &mut Ui) -> bool;
+
+    /// The names of all the numeric fields in this config, which can be read with
+    /// [`get_numeric_field`](Self::get_numeric_field) and written with
+    /// [`set_numeric_field`](Self::set_numeric_field).
+    ///
+    /// A plain number is named after its field, like `fadeoff`. Each element of an array is named
+    /// with its index, so `colour.0` is the red channel of the colour in the `colour` field.
+    fn numeric_field_names() -> &'static [&'static str];
+
+    /// Get the value of the numeric field with the given name as an `f32`, or `None` if there's no
+    /// numeric field with that name. See [`numeric_field_names`](Self::numeric_field_names).
+    fn get_numeric_field(&self, name: &str) -> Option<f32>;
+
+    /// Set the numeric field with the given name and return whether that field exists. Integer
+    /// fields are rounded and saturate at the limits of their type. See
+    /// [`numeric_field_names`](Self::numeric_field_names).
+    fn set_numeric_field(&mut self, name: &str, value: f32) -> bool;
+
+    /// Describe every field of this config, including its type, doc comment, default value, and
+    /// the range from any `#[schema(range = ...)]` attribute. The schema is only built once. See
+    /// [`crate::schema`].
+    fn schema() -> &'static crate::schema::ConfigSchema;
 }
 
 /// This trait is needed by all structs that want to act as configuration for effects.
 #[cfg(feature = "config-trait")]
 pub trait EffectConfig: BaseEffectConfig {
+    /// The version of this config, which is saved in the config file. This should be increased
+    /// whenever the config changes in a way that [`migrate`](Self::migrate) needs to handle.
+    const VERSION: u32 = 0;
+
+    /// Read a config from the contents of a config file saved with an older
+    /// [`VERSION`](Self::VERSION) of this config.
+    ///
+    /// The default implementation just reads the text as the current version, which works for
+    /// added and removed fields, as long as the config has `#[serde(default)]`. Configs which
+    /// rename fields or change their meaning should read the text into a private copy of the old
+    /// config and convert it. The old config needs `#[serde(rename = "MyEffectConfig")]`, since
+    /// RON checks the struct name.
+    ///
+    /// # Errors
+    ///
+    /// This method should return an error if the text can't be read as the given version.
+    fn migrate(text: &str, from_version: u32) -> Result<Self, ron::error::SpannedError> {
+        let _ = from_version;
+        ron::from_str(text)
+    }
+
+    /// Check that every numeric field is within the range given by its `#[schema(range = ...)]`
+    /// attribute. See [`ConfigSchema::validate`](crate::schema::ConfigSchema::validate).
+    ///
+    /// Configs with rules that depend on several fields can override this and
+    /// [`clamp_to_ranges`](Self::clamp_to_ranges), calling
+    /// [`validate_schema_ranges`](crate::schema::validate_schema_ranges) and
+    /// [`clamp_to_schema_ranges`](crate::schema::clamp_to_schema_ranges) for the simple ranges.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an error for each field which is out of range.
+    fn validate(&self) -> Result<(), Vec<crate::schema::RangeError>> {
+        crate::schema::validate_schema_ranges(self)
+    }
+
+    /// Clamp every numeric field into the range given by its `#[schema(range = ...)]` attribute.
+    /// See [`validate`](Self::validate).
+    fn clamp_to_ranges(&mut self) {
+        crate::schema::clamp_to_schema_ranges(self);
+    }
+
+    /// Get the built-in presets for this effect, as pairs of names and configs. See
+    /// [`crate::presets`].
+    ///
+    /// The default config is always available as a preset called
+    /// [`"Default"`](crate::presets::DEFAULT_PRESET_NAME), so it shouldn't be included here.
+    fn factory_presets() -> Vec<(&'static str, Self)> {
+        Vec::new()
+    }
+
     /// Render the GUI to edit the config of this effect and return whether the config has changed.
     ///
     /// This method _SHOULD NOT_ include a heading or a _Reset to defaults_ button. These should be
     /// handled by [`BaseEffectConfig`], which can be derived.
-    fn render_options_gui(&mut self, ctx: &Context, ui: &mut Ui) -> bool;
+    ///
+    /// The default implementation generates the GUI from the [schema](BaseEffectConfig::schema)
+    /// with [`render_schema_gui`](crate::schema::render_schema_gui), which only handles numbers,
+    /// bools, and colours. Effects with other options should write their own GUI.
+    fn render_options_gui(&mut self, _ctx: &Context, ui: &mut Ui) -> bool {
+        crate::schema::render_schema_gui(self, ui)
+    }
 
     /// Load the effect configuration from the config file, or use the default if the file is
     /// unavailable. Also save the default to the file for future editing.
+    ///
+    /// Config files from older [versions](Self::VERSION) are [migrated](Self::migrate) and saved
+    /// again, and fields which are out of range are [clamped](Self::clamp_to_ranges). If the file
+    /// can't be read at all, then it's [backed
+    /// up](crate::config_file::back_up_unreadable_file) before the default is saved, and files with
+    /// fields out of range are backed up before the clamped config is saved. Files from newer
+    /// versions may have fields that we don't know about, so they're never saved over without a
+    /// backup.
     fn from_file(filename: &str) -> Self {
         let _ = fs::DirBuilder::new().recursive(true).create(format!(
             "{}/config",
@@ -195,7 +317,48 @@ pub trait EffectConfig: BaseEffectConfig {
             return write_and_return_default();
         };
 
-        ron::from_str(&text).unwrap_or_else(|_| write_and_return_default())
+        let version = crate::config_file::read_version(&text);
+        let result = if version < Self::VERSION {
+            Self::migrate(&text, version)
+        } else {
+            ron::from_str(&text)
+        };
+
+        let mut config = match result {
+            Ok(config) => config,
+            Err(error) => {
+                let backup = crate::config_file::back_up_unreadable_file(filename);
+                tracing::warn!(
+                    %error,
+                    ?backup,
+                    "Unable to read config file {filename}, so using the default config"
+                );
+                return write_and_return_default();
+            }
+        };
+
+        let out_of_range = if let Err(errors) = config.validate() {
+            for error in errors {
+                tracing::warn!(%error, "Config in {filename} is out of range, so clamping it");
+            }
+            config.clamp_to_ranges();
+            true
+        } else {
+            false
+        };
+
+        if out_of_range {
+            let backup = crate::config_file::back_up_unreadable_file(filename);
+            tracing::warn!(
+                ?backup,
+                "Backed up config file {filename} before clamping it"
+            );
+        }
+
+        if out_of_range || version < Self::VERSION {
+            save_effect_config_to_file(filename, &config);
+        }
+        config
     }
 
     /// Save the config to the given filename, which should be from the parent effect.
@@ -244,9 +407,15 @@ pub trait Effect: BaseEffect {
     /// run it once, unless it's a procedural aesthetic thing like
     /// [`LavaLamp`](../effects/aesthetic/struct.LavaLamp.html), then that should loop in this
     /// function.
+    ///
+    /// The returned duration is only a request, so effects that move things should move them by
+    /// [`ctx.delta`](FrameContext::delta), which is how long it's actually been since the last
+    /// frame, and usually ask for [`ctx.target_frame_time()`](FrameContext::target_frame_time).
+    /// See [`crate::timing`].
     fn next_frame(
         &mut self,
         config: &<Self as BaseEffect>::Config,
+        ctx: &FrameContext,
     ) -> Option<(FrameType, Duration)>;
 
     /// How many loops should we run in test or benchmark builds?
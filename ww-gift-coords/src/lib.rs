@@ -13,7 +13,7 @@
 use color_eyre::Result;
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
-use std::fs;
+use std::{f32::consts::TAU, fs};
 use tracing_unwrap::ResultExt;
 
 /// A point in 3D space with f32 values.
@@ -164,6 +164,15 @@ impl GIFTCoords {
         &self.coords
     }
 
+    /// The coordinates in cylindrical form, as `(radius, angle, z)`. The angle is measured in
+    /// radians anti-clockwise from the positive x-axis, and is in the range `[0, TAU)`.
+    pub fn cylindrical_coords(&self) -> Vec<PointF> {
+        self.coords
+            .iter()
+            .map(|&(x, y, z)| (x.hypot(y), y.atan2(x).rem_euclid(TAU), z))
+            .collect()
+    }
+
     /// The maximum z value.
     ///
     /// The minimum z value is 0, and the minimum and maximum x and y values and -1 and 1.
@@ -267,6 +276,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn cylindrical_coords_test() {
+        let coords = gift_coords();
+
+        for (&(x, y, z), (r, theta, cz)) in coords.coords().iter().zip(coords.cylindrical_coords())
+        {
+            assert!((0.0..TAU).contains(&theta));
+            assert!(approx_eq!(f32, r * theta.cos(), x, epsilon = 1e-5));
+            assert!(approx_eq!(f32, r * theta.sin(), y, epsilon = 1e-5));
+            assert!(approx_eq!(f32, cz, z));
+        }
+    }
+
     #[test]
     fn within_bounding_box_test() {
         for p in [
@@ -1,24 +1,28 @@
-//! This is the build script for `ww-server`. It checks that exactly one driver feature is enabled.
+//! This is the build script for `ww-server`. It checks that exactly one driver feature is enabled
+//! and renders the thumbnails of the effects for the effect browser in the client.
 
 #![feature(iter_intersperse)]
 
+use std::{env, fs, path::PathBuf};
+use ww_effects::{EffectNameList, Thumbnail, metadata::all_metadata};
+
 /// Names of driver features with leading `driver-` removed, dashes replaced with underscores, and
 /// everything in ALL CAPS.
 const DRIVER_NAMES: &[&str] = &["DEBUG", "VIRTUAL_TREE", "RASPI_WS2811"];
 
+/// The name of the file in `OUT_DIR` that the thumbnails get written to.
+const THUMBNAILS_FILENAME: &str = "thumbnails.bin";
+
 /// Convert the given driver name into the name of the corresponding feature.
 fn driver_name_to_feature_name(driver_name: &&str) -> String {
     format!("driver-{}", driver_name.to_lowercase().replace('_', "-"))
 }
 
-fn main() -> Result<(), String> {
-    if std::env::var("CROSS_COMPILE").is_ok() {
-        println!("cargo:rustc-link-lib=static=clang");
-    }
-
+/// Check that exactly one driver feature is enabled.
+fn check_drivers() -> Result<(), String> {
     let names: Vec<_> = DRIVER_NAMES
         .iter()
-        .filter(|&name| std::env::var(format!("CARGO_FEATURE_DRIVER_{name}")).is_ok())
+        .filter(|&name| env::var(format!("CARGO_FEATURE_DRIVER_{name}")).is_ok())
         .collect();
 
     if names.len() == 1 {
@@ -41,3 +45,53 @@ fn main() -> Result<(), String> {
         ))
     }
 }
+
+/// Get the path of the coordinates file, if `DATA_DIR` and `COORDS_FILENAME` point to one.
+fn coords_file() -> Option<PathBuf> {
+    let path = PathBuf::from(env::var("DATA_DIR").ok()?)
+        .join("coords")
+        .join(env::var("COORDS_FILENAME").ok()?);
+    path.is_file().then_some(path)
+}
+
+/// Render a thumbnail of every effect and write them to [`THUMBNAILS_FILENAME`] in `OUT_DIR`.
+///
+/// Rendering needs the coordinates of the lights, so if we can't find them, then we write no
+/// thumbnails and the client just shows the effects without them. The build dependency on
+/// `ww-effects` enables its `deterministic` feature, so the thumbnails are the same every build.
+fn render_thumbnails() -> Result<(), String> {
+    println!("cargo:rerun-if-env-changed=DATA_DIR");
+    println!("cargo:rerun-if-env-changed=COORDS_FILENAME");
+
+    let thumbnails: Vec<(EffectNameList, Thumbnail)> = if let Some(path) = coords_file() {
+        println!("cargo:rerun-if-changed={}", path.display());
+
+        all_metadata()
+            .into_iter()
+            .map(|metadata| (metadata.effect, Thumbnail::render(metadata.effect)))
+            .collect()
+    } else {
+        println!(
+            "cargo:warning=Couldn't find the coordinates file from DATA_DIR and COORDS_FILENAME, so no effect thumbnails will be rendered"
+        );
+        Vec::new()
+    };
+
+    let bytes = bincode::serialize(&thumbnails)
+        .map_err(|e| format!("Couldn't serialize thumbnails: {e}"))?;
+    let out_dir = env::var("OUT_DIR").map_err(|e| format!("OUT_DIR should be set: {e}"))?;
+    fs::write(PathBuf::from(out_dir).join(THUMBNAILS_FILENAME), bytes)
+        .map_err(|e| format!("Couldn't write thumbnails: {e}"))
+}
+
+fn main() -> Result<(), String> {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=CROSS_COMPILE");
+
+    if env::var("CROSS_COMPILE").is_ok() {
+        println!("cargo:rustc-link-lib=static=clang");
+    }
+
+    render_thumbnails()?;
+    check_drivers()
+}
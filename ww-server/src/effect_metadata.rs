@@ -0,0 +1,28 @@
+//! This module combines the metadata of the effects with the thumbnails that were rendered by the
+//! build script, so that the server can send them to the client for its effect browser.
+
+use lazy_static::lazy_static;
+use tracing_unwrap::ResultExt;
+use ww_effects::{EffectMetadata, EffectNameList, Thumbnail, metadata::all_metadata};
+
+/// The thumbnails rendered by the build script, serialized with `bincode`.
+const THUMBNAILS_BYTES: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/thumbnails.bin"));
+
+lazy_static! {
+    /// The metadata of every effect, with its thumbnail if one was rendered.
+    pub static ref EFFECT_METADATA: Vec<EffectMetadata> = {
+        let thumbnails: Vec<(EffectNameList, Thumbnail)> = bincode::deserialize(THUMBNAILS_BYTES)
+            .expect_or_log("The thumbnails from the build script should deserialize");
+
+        all_metadata()
+            .into_iter()
+            .map(|metadata| EffectMetadata {
+                thumbnail: thumbnails
+                    .iter()
+                    .find(|(effect, _)| *effect == metadata.effect)
+                    .map(|(_, thumbnail)| thumbnail.clone()),
+                ..metadata
+            })
+            .collect()
+    };
+}
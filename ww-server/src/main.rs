@@ -1,6 +1,7 @@
 //! This binary crate runs the server for `Winter WonderLights`.
 
 mod drivers;
+mod effect_metadata;
 mod logging;
 mod run_effect;
 mod run_server;
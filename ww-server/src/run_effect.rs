@@ -1,13 +1,21 @@
-//! This module provides the `run_effect` function to check the given `ClientState` and run the effect.
+//! This module provides the `run_effect` function to check the given `ClientState` and run the
+//! effect, along with any layers drawn on top of it and any modulations of their configs, or run
+//! the external effect if there is one.
 
 use super::WrappedClientState;
+use crate::drivers::DriverWrapper;
 use lazy_static::lazy_static;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::sync::{broadcast, oneshot};
 use tracing::{info, instrument, trace, warn};
 use tracing_unwrap::ResultExt;
 use ww_driver_trait::Driver;
+use ww_effects::{
+    FrameClock, FrameContext, LayerStack, modulation::modulate, plugins::PluginEffect,
+    scripting::ScriptEffect,
+};
 use ww_frame::FrameType;
+use ww_shared::ExternalEffectName;
 
 lazy_static! {
     /// The broadcast sender which lets you send messages to the background thread, which is
@@ -25,6 +33,98 @@ pub enum ThreadMessage {
     Restart,
 }
 
+/// An effect which isn't compiled into the server, loaded and ready to run. Both kinds are boxed
+/// because they hold a whole script engine or WASM store.
+enum ExternalEffect {
+    /// A Rhai script.
+    Script(Box<ScriptEffect>),
+
+    /// A WASM plugin.
+    Plugin(Box<PluginEffect>),
+}
+
+impl ExternalEffect {
+    /// Load the external effect with the given name, logging a warning if it can't be loaded.
+    fn load(name: &ExternalEffectName) -> Option<Self> {
+        let result = match name {
+            ExternalEffectName::Script(script) => ScriptEffect::load(script)
+                .map(|script| Self::Script(Box::new(script)))
+                .map_err(|error| error.to_string()),
+            ExternalEffectName::Plugin(plugin) => PluginEffect::load(plugin)
+                .map(|plugin| Self::Plugin(Box::new(plugin)))
+                .map_err(|error| error.to_string()),
+        };
+
+        result
+            .inspect_err(|error| warn!(%error, ?name, "Unable to load external effect"))
+            .ok()
+    }
+
+    /// Get the next frame of the effect and how long to show it for, or `None` if it has finished.
+    fn next_frame(&mut self, ctx: &FrameContext) -> Option<(FrameType, Duration)> {
+        match self {
+            Self::Script(script) => script.next_frame(ctx),
+            Self::Plugin(plugin) => plugin.next_frame(ctx),
+        }
+    }
+}
+
+/// Run the external effect with the given name once, then pause before it gets looped.
+///
+/// The effect is loaded again every time, so editing a script only needs a restart to take
+/// effect, and plugins also reload themselves when their file changes. Frames are scheduled like
+/// the layers of a [`LayerStack`], from when the last frame was due rather than from when it was
+/// shown, so the time spent making frames doesn't slow the effect down.
+async fn run_external_effect(
+    name: &ExternalEffectName,
+    driver: &mut DriverWrapper,
+    client_state: &WrappedClientState,
+) {
+    let max_brightness = || {
+        client_state
+            .read()
+            .expect_or_log("Should be able to read from client state")
+            .max_brightness
+    };
+
+    let Some(mut effect) = ExternalEffect::load(name) else {
+        driver.display_frame(FrameType::Off, max_brightness());
+
+        // Don't try to load it constantly. `select!` takes control while we're awaiting anyway,
+        // so responding to a message will be fast
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        return;
+    };
+
+    let started = Instant::now();
+    let mut clock = FrameClock::default();
+    let mut due = started;
+    loop {
+        let now = Instant::now();
+        let ctx = clock.tick(now.duration_since(started));
+        let Some((frame, duration)) = effect.next_frame(&ctx) else {
+            break;
+        };
+
+        driver.display_frame(frame, max_brightness());
+
+        // Don't try to catch up if we've fallen a long way behind
+        due = (due + duration).max(now);
+        tokio::time::sleep_until(due.into()).await;
+    }
+
+    driver.display_frame(FrameType::Off, max_brightness());
+
+    // Pause before looping the effect
+    let pause_time_ms = client_state
+        .read()
+        .expect_or_log("Should be able to read from client state")
+        .pause_time_ms;
+    tokio::time::sleep(Duration::from_millis(pause_time_ms)).await;
+
+    info!(?name, "Looping external effect");
+}
+
 /// Run the effect in the `state` with `tokio` and listen for messages on the
 /// [`struct@SEND_MESSAGE_TO_RUN_EFFECT_THREAD`] channel. Intended to be run in a background thread.
 #[instrument(skip_all)]
@@ -82,15 +182,23 @@ pub fn run_effect(client_state: WrappedClientState, kill_thread: oneshot::Receiv
                     // We have to get the effect and then drop the lock so that the
                     // `handle_request()` function can actually write to the client state when the
                     // client requests an effect change
-                    let effect_name = read_state!(state => state.effect_name);
+                    let (effect_name, external_effect) =
+                        read_state!(state => (state.effect_name, state.external_effect.clone()));
 
                     if let Some(effect_name) = effect_name {
-                        let mut effect = effect_name.from_file();
+                        // Each layer runs at its own frame rate, so the stack tells us when the
+                        // next one is due rather than how long to wait
+                        let started = Instant::now();
+                        let mut stack = read_state!(state => LayerStack::new(
+                            effect_name.from_file(),
+                            &state.layers,
+                            started
+                        ));
 
                         loop {
                             // This block is needed to let clippy know that we drop the state
                             // before awaiting the sleep
-                            let (frame, duration) = {
+                            let (frame, next_due) = {
                                 let state = client_state
                                     .read()
                                     .expect_or_log("Should be able to read from client state");
@@ -98,14 +206,29 @@ pub fn run_effect(client_state: WrappedClientState, kill_thread: oneshot::Receiv
                                     drop(state);
                                     break;
                                 };
-                                let Some((frame, duration)) = effect.next_frame(config) else {
+
+                                // The modulations are applied to copies of the configs so that
+                                // the client only ever sees the configs that it set
+                                let now = Instant::now();
+                                let elapsed = now - started;
+                                let config = modulate(config, &state.modulations, elapsed);
+                                let layers: Vec<_> = state
+                                    .layers
+                                    .iter()
+                                    .map(|layer| layer.modulated(elapsed))
+                                    .collect();
+                                drop(state);
+
+                                let Some((frame, next_due)) =
+                                    stack.next_frame(&config, &layers, now)
+                                else {
                                     break;
                                 };
-                                (frame, duration)
+                                (frame, next_due)
                             };
 
                             driver.display_frame(frame, read_state!(state => state.max_brightness));
-                            tokio::time::sleep(duration).await;
+                            tokio::time::sleep_until(next_due.into()).await;
                         }
 
                         driver.display_frame(FrameType::Off, read_state!(state => state.max_brightness));
@@ -119,6 +242,8 @@ pub fn run_effect(client_state: WrappedClientState, kill_thread: oneshot::Receiv
                             "Looping effect {:?}",
                             read_state!(state => state.effect_name.map_or("None", |x| x.effect_name()))
                         );
+                    } else if let Some(name) = external_effect {
+                        run_external_effect(&name, &mut driver, &client_state).await;
                     } else {
                         driver.display_frame(FrameType::Off, read_state!(state => state.max_brightness));
 
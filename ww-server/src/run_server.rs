@@ -2,6 +2,7 @@
 
 use crate::{
     WrappedClientState,
+    effect_metadata::EFFECT_METADATA,
     run_effect::{SEND_MESSAGE_TO_RUN_EFFECT_THREAD, ThreadMessage},
 };
 use color_eyre::{Report, Result};
@@ -17,7 +18,13 @@ use tokio_stream::wrappers::BroadcastStream;
 use tokio_tungstenite::tungstenite;
 use tracing::{debug, error, info, instrument, trace, warn};
 use tracing_unwrap::ResultExt;
-use ww_shared::{ClientToServerMsg, ServerToClientMsg};
+use ww_effects::{
+    layers::MAX_LAYERS,
+    plugins::{is_plugin, list_plugins},
+    presets::{Preset, PresetStore},
+    scripting::{is_script, list_scripts},
+};
+use ww_shared::{ClientToServerMsg, ExternalEffectName, ServerToClientMsg};
 
 lazy_static! {
     /// The broadcast sender which lets you send messages between client tasks to broadcast a
@@ -85,6 +92,22 @@ async fn handle_connection(
             ))
         };
 
+        let send_update_presets = || {
+            send_message(&ServerToClientMsg::UpdatePresets(
+                PresetStore::new().library(),
+            ))
+        };
+
+        let send_update_external_effects = || {
+            send_message(&ServerToClientMsg::UpdateExternalEffects(
+                list_scripts()
+                    .into_iter()
+                    .map(ExternalEffectName::Script)
+                    .chain(list_plugins().into_iter().map(ExternalEffectName::Plugin))
+                    .collect(),
+            ))
+        };
+
         let tungstenite::Message::Binary(bytes) = msg else {
             return future::err(tungstenite::Error::Protocol(
                 tungstenite::error::ProtocolError::ExpectedFragment(
@@ -120,6 +143,11 @@ async fn handle_connection(
                         server_version: crate::CRATE_VERSION.to_string(),
                     });
                     send_update_client_state();
+                    send_update_presets();
+                    send_update_external_effects();
+                    send_message(&ServerToClientMsg::UpdateEffectMetadata(
+                        EFFECT_METADATA.clone(),
+                    ));
                 } else {
                     warn!(
                         client_protocol_version = protocol_version,
@@ -138,9 +166,16 @@ async fn handle_connection(
 
                 send_update_client_state();
             }
-            ClientToServerMsg::UpdateConfig(new_config) => {
+            ClientToServerMsg::UpdateConfig(mut new_config) => {
                 info!(?new_config, "Client requesting config change");
 
+                if let Err(errors) = new_config.validate() {
+                    for error in errors {
+                        warn!(%error, "Client sent a config which is out of range, so clamping it");
+                    }
+                    new_config.clamp_to_ranges();
+                }
+
                 write_state!(state => {
                     state.effect_config = Some(new_config);
                     trace!(?state, "After updating client state config");
@@ -155,6 +190,17 @@ async fn handle_connection(
                 write_state!(state => {
                     state.effect_name = new_effect;
                     state.effect_config = new_effect.map(|effect| effect.config_from_file());
+                    state.external_effect = None;
+
+                    // Only keep the modulations that still make sense for the new effect
+                    let field_names = state
+                        .effect_config
+                        .as_ref()
+                        .map_or(&[][..], |config| config.numeric_field_names());
+                    state
+                        .modulations
+                        .retain(|modulation| field_names.contains(&modulation.field.as_str()));
+
                     trace!(?state, "After updating client state effect name");
                 });
 
@@ -164,6 +210,89 @@ async fn handle_connection(
 
                 send_update_client_state();
             }
+            ClientToServerMsg::ChangeExternalEffect(new_effect) => {
+                info!(?new_effect, "Client requesting external effect change");
+
+                // The name ends up in a path, so only accept effects which are really there
+                let exists = match &new_effect {
+                    ExternalEffectName::Script(name) => is_script(name),
+                    ExternalEffectName::Plugin(name) => is_plugin(name),
+                };
+
+                if exists {
+                    client_state.save_config();
+
+                    write_state!(state => {
+                        state.effect_name = None;
+                        state.effect_config = None;
+                        state.external_effect = Some(new_effect);
+                        state.modulations.clear();
+                        trace!(?state, "After updating client state external effect");
+                    });
+
+                    SEND_MESSAGE_TO_RUN_EFFECT_THREAD
+                        .send(ThreadMessage::Restart)
+                        .expect_or_log("Unable to send ThreadMessage::Restart");
+
+                    send_update_client_state();
+                } else {
+                    warn!(
+                        ?new_effect,
+                        "Client tried to change to an unknown external effect"
+                    );
+                    send_update_external_effects();
+                }
+            }
+            ClientToServerMsg::ListExternalEffects => {
+                info!("Client requesting external effects");
+
+                send_update_external_effects();
+            }
+            ClientToServerMsg::ChangeLayers(mut new_layers) => {
+                info!(?new_layers, "Client requesting layer change");
+
+                if new_layers.len() > MAX_LAYERS {
+                    warn!(
+                        layers = new_layers.len(),
+                        "Client sent too many layers, so dropping the extra ones"
+                    );
+                    new_layers.truncate(MAX_LAYERS);
+                }
+                for layer in &mut new_layers {
+                    layer.clamp_to_ranges();
+                }
+
+                // Changing the opacity, blend mode, or config of a layer can happen while it's
+                // running, but adding, removing, or replacing effects means restarting them
+                let restart;
+                write_state!(state => {
+                    restart = state.layers.len() != new_layers.len()
+                        || state
+                            .layers
+                            .iter()
+                            .zip(&new_layers)
+                            .any(|(old, new)| old.config.effect_name() != new.config.effect_name());
+                    state.layers = new_layers;
+                    trace!(?state, "After updating client state layers");
+                });
+
+                if restart {
+                    SEND_MESSAGE_TO_RUN_EFFECT_THREAD
+                        .send(ThreadMessage::Restart)
+                        .expect_or_log("Unable to send ThreadMessage::Restart");
+                }
+
+                send_update_client_state();
+            }
+            ClientToServerMsg::ChangeModulations(new_modulations) => {
+                info!(?new_modulations, "Client requesting modulation change");
+
+                write_state!(state => {
+                    state.modulations = new_modulations;
+                    trace!(?state, "After updating client state modulations");
+                });
+                send_update_client_state();
+            }
             ClientToServerMsg::ChangePauseTime(new_time) => {
                 info!(?new_time, "Client requesting new pause time");
 
@@ -193,6 +322,84 @@ async fn handle_connection(
 
                 send_update_client_state();
             }
+            ClientToServerMsg::ListPresets => {
+                info!("Client requesting presets");
+
+                send_update_presets();
+            }
+            ClientToServerMsg::SavePreset(name) => {
+                info!(?name, "Client requesting save preset");
+
+                let config = client_state
+                    .read()
+                    .expect_or_log("Should be able to read client state")
+                    .effect_config
+                    .clone();
+
+                if let Some(config) = config {
+                    PresetStore::new().add(Preset::new(name, config));
+                    send_update_presets();
+                } else {
+                    warn!("Client tried to save a preset without an effect");
+                }
+            }
+            ClientToServerMsg::SelectPreset(name) => {
+                info!(?name, "Client requesting select preset");
+
+                let effect_name = client_state
+                    .read()
+                    .expect_or_log("Should be able to read client state")
+                    .effect_name;
+                let preset = effect_name.and_then(|effect| {
+                    PresetStore::new()
+                        .library()
+                        .find(effect, &name)
+                        .map(|preset| preset.config.clone())
+                });
+
+                if let Some(config) = preset {
+                    write_state!(state => {
+                        state.effect_config = Some(config);
+                        trace!(?state, "After updating client state config from preset");
+                    });
+                    send_update_client_state();
+                } else {
+                    warn!(
+                        ?name,
+                        ?effect_name,
+                        "Client tried to select an unknown preset"
+                    );
+                }
+            }
+            ClientToServerMsg::DeletePreset(name) => {
+                info!(?name, "Client requesting delete preset");
+
+                let effect_name = client_state
+                    .read()
+                    .expect_or_log("Should be able to read client state")
+                    .effect_name;
+
+                if let Some(effect) = effect_name
+                    && PresetStore::new().delete(effect, &name)
+                {
+                    send_update_presets();
+                } else {
+                    warn!(
+                        ?name,
+                        ?effect_name,
+                        "Client tried to delete an unknown user preset"
+                    );
+                }
+            }
+            ClientToServerMsg::ImportPresets(bundle) => {
+                info!(
+                    count = bundle.presets.len(),
+                    "Client requesting import presets"
+                );
+
+                PresetStore::new().import(bundle);
+                send_update_presets();
+            }
         };
 
         future::ok(())
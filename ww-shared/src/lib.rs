@@ -2,13 +2,24 @@
 
 use serde::{Deserialize, Serialize};
 use std::fs;
+use tracing::warn;
 use tracing_unwrap::ResultExt;
-use ww_effects::list::{EffectConfigDispatchList, EffectNameList};
+use ww_effects::{
+    config_file::{add_version_header, back_up_unreadable_file, read_version},
+    layers::EffectLayer,
+    list::{EffectConfigDispatchList, EffectNameList},
+    metadata::EffectMetadata,
+    modulation::Modulation,
+    presets::{PresetBundle, PresetLibrary},
+};
 
 /// The version of this crate.
 pub const CRATE_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 /// A message from the server to the client.
+///
+/// Messages are encoded with bincode, which tags each variant with its index, so new variants go
+/// at the end to keep the existing ones compatible.
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum ServerToClientMsg {
     /// Establish a connection with the client by agreeing on a protocol version and specifying the
@@ -40,9 +51,19 @@ pub enum ServerToClientMsg {
 
     /// Terminate the connection between the server and the client.
     TerminateConnection,
+
+    /// Tell the client about all the factory and user presets of every effect.
+    UpdatePresets(PresetLibrary),
+
+    /// Tell the client about all the effects which aren't compiled into the server.
+    UpdateExternalEffects(Vec<ExternalEffectName>),
+
+    /// Tell the client about the metadata of all the effects which are compiled into the server,
+    /// for its effect browser.
+    UpdateEffectMetadata(Vec<EffectMetadata>),
 }
 
-/// A message from the client to the server.
+/// A message from the client to the server. Like [`ServerToClientMsg`], new variants go at the end.
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum ClientToServerMsg {
     /// Establish a connection with the server by agreeing on a protocol version.
@@ -71,10 +92,62 @@ pub enum ClientToServerMsg {
 
     /// Restart the current effect.
     RestartCurrentEffect,
+
+    /// Replace the layers drawn on top of the current effect. See [`ClientState::layers`].
+    ChangeLayers(Vec<EffectLayer>),
+
+    /// Replace the modulations of the current effect's config. See [`ClientState::modulations`].
+    ChangeModulations(Vec<Modulation>),
+
+    /// Request an [`UpdatePresets`](ServerToClientMsg::UpdatePresets) message from the server.
+    ListPresets,
+
+    /// Save the current config as a user preset of the current effect with the given name,
+    /// replacing any user preset with the same name.
+    SavePreset(String),
+
+    /// Replace the current config with the preset of the current effect with the given name.
+    SelectPreset(String),
+
+    /// Delete the user preset of the current effect with the given name.
+    DeletePreset(String),
+
+    /// Save all the presets in the bundle as user presets. See
+    /// [`PresetStore::import`](ww_effects::presets::PresetStore::import).
+    ImportPresets(PresetBundle),
+
+    /// Ask the server to change to an effect which isn't compiled into it. See
+    /// [`ClientState::external_effect`].
+    ChangeExternalEffect(ExternalEffectName),
+
+    /// Ask the server to look for external effects again and send an
+    /// [`UpdateExternalEffects`](ServerToClientMsg::UpdateExternalEffects) message.
+    ListExternalEffects,
+}
+
+/// An effect which isn't compiled into the server, so it can only be referred to by name.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ExternalEffectName {
+    /// A Rhai script in `DATA_DIR/effects`, named after its file without the `.rhai` extension.
+    Script(String),
+
+    /// A WASM plugin in `DATA_DIR/plugins`, named after its file without the `.wasm` extension.
+    Plugin(String),
+}
+
+impl ExternalEffectName {
+    /// Get the name of the effect for the GUI, like `Script: my_effect`.
+    pub fn display_name(&self) -> String {
+        match self {
+            Self::Script(name) => format!("Script: {name}"),
+            Self::Plugin(name) => format!("Plugin: {name}"),
+        }
+    }
 }
 
 /// The state of the client.
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ClientState {
     /// The name of the current effect.
     pub effect_name: Option<EffectNameList>,
@@ -82,6 +155,17 @@ pub struct ClientState {
     /// The config of the current effect.
     pub effect_config: Option<EffectConfigDispatchList>,
 
+    /// The current effect if it isn't compiled into the server, in which case
+    /// [`effect_name`](Self::effect_name) is `None`. External effects have no config, and layers
+    /// and modulations aren't applied to them.
+    pub external_effect: Option<ExternalEffectName>,
+
+    /// The modulations applied to the config of the current effect while it's running.
+    pub modulations: Vec<Modulation>,
+
+    /// The effects drawn on top of the current effect, in order from bottom to top.
+    pub layers: Vec<EffectLayer>,
+
     /// The number of milliseconds to pause before looping the current effect.
     pub pause_time_ms: u64,
 
@@ -89,8 +173,73 @@ pub struct ClientState {
     pub max_brightness: u8,
 }
 
+/// The parts of [`ClientState`] which don't contain any effect configs, so that they can still be
+/// loaded when a saved config can't be.
+#[derive(Deserialize)]
+#[serde(default, rename = "ClientState")]
+struct ClientStateWithoutConfigs {
+    /// See [`ClientState::effect_name`].
+    effect_name: Option<EffectNameList>,
+
+    /// See [`ClientState::external_effect`].
+    external_effect: Option<ExternalEffectName>,
+
+    /// See [`ClientState::modulations`].
+    modulations: Vec<Modulation>,
+
+    /// See [`ClientState::pause_time_ms`].
+    pause_time_ms: u64,
+
+    /// See [`ClientState::max_brightness`].
+    max_brightness: u8,
+}
+
+impl Default for ClientStateWithoutConfigs {
+    fn default() -> Self {
+        let ClientState {
+            effect_name,
+            external_effect,
+            modulations,
+            pause_time_ms,
+            max_brightness,
+            ..
+        } = ClientState::default();
+
+        Self {
+            effect_name,
+            external_effect,
+            modulations,
+            pause_time_ms,
+            max_brightness,
+        }
+    }
+}
+
 impl ClientState {
+    /// The version of the client state, which is saved in the state file with the same `// version:
+    /// N` header as effect configs. See [`ww_effects::config_file`]. This should be increased
+    /// whenever the state changes in a way that [`migrate`](Self::migrate) needs to handle.
+    pub const VERSION: u32 = 1;
+
+    /// Read the client state from the contents of a state file saved with an older
+    /// [`VERSION`](Self::VERSION), like
+    /// [`EffectConfig::migrate`](ww_effects::traits::EffectConfig::migrate).
+    ///
+    /// Version 0 is every state file from before the version header was added, which is the same
+    /// as version 1 apart from the header, so it's just read as the current version.
+    fn migrate(text: &str, from_version: u32) -> Result<Self, ron::error::SpannedError> {
+        let _ = from_version;
+        ron::from_str(text)
+    }
+
     /// Load the client state from a file.
+    ///
+    /// Missing fields take their default values, and files from older [versions](Self::VERSION)
+    /// are [migrated](Self::migrate) and saved again. If the file can't be read, or it's from a
+    /// newer version with fields that we might not know about, then it's backed up with
+    /// [`back_up_unreadable_file`] before anything is saved over it. If only the effect
+    /// configs in it are unreadable, like after an effect's config changes, then everything except
+    /// the layers is kept, and the config of the current effect is loaded from its own config file.
     pub fn from_file(filename: &str) -> Self {
         let _ = fs::DirBuilder::new().recursive(true).create(format!(
             "{}/config",
@@ -103,14 +252,71 @@ impl ClientState {
             default
         };
 
-        let Ok(text) = fs::read_to_string(format!(
+        let path = format!(
             "{}/config/{filename}",
             std::env::var("DATA_DIR").expect_or_log("DATA_DIR must be defined")
-        )) else {
+        );
+        let Ok(text) = fs::read_to_string(&path) else {
             return write_and_return_default();
         };
 
-        ron::from_str(&text).unwrap_or_else(|_| write_and_return_default())
+        let version = read_version(&text);
+        let result = if version < Self::VERSION {
+            Self::migrate(&text, version)
+        } else {
+            ron::from_str(&text)
+        };
+
+        let error = match result {
+            Ok(state) => {
+                if version > Self::VERSION {
+                    let backup = back_up_unreadable_file(&path);
+                    warn!(
+                        ?backup,
+                        version, "Backed up client state from a newer version in {path}"
+                    );
+                }
+                if version != Self::VERSION {
+                    state.save_to_file(filename);
+                }
+                return state;
+            }
+            Err(error) => error,
+        };
+
+        let backup = back_up_unreadable_file(&path);
+        warn!(%error, ?backup, "Unable to read client state from {path}");
+
+        let Ok(ClientStateWithoutConfigs {
+            effect_name,
+            external_effect,
+            modulations,
+            pause_time_ms,
+            max_brightness,
+        }) = ron::from_str(&text)
+        else {
+            return write_and_return_default();
+        };
+
+        let effect_config = effect_name.map(|effect| effect.config_from_file());
+        let field_names = effect_config
+            .as_ref()
+            .map_or(&[][..], |config| config.numeric_field_names());
+
+        let state = Self {
+            effect_name,
+            effect_config,
+            external_effect,
+            modulations: modulations
+                .into_iter()
+                .filter(|modulation| field_names.contains(&modulation.field.as_str()))
+                .collect(),
+            layers: vec![],
+            pause_time_ms,
+            max_brightness,
+        };
+        state.save_to_file(filename);
+        state
     }
 
     /// Save the client to a file.
@@ -120,8 +326,14 @@ impl ClientState {
                 "{}/config/{filename}",
                 std::env::var("DATA_DIR").expect_or_log("DATA_DIR must be defined")
             ),
-            ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default().struct_names(true))
+            add_version_header(
+                Self::VERSION,
+                &ron::ser::to_string_pretty(
+                    self,
+                    ron::ser::PrettyConfig::default().struct_names(true),
+                )
                 .expect_or_log("ClientState should be serializable"),
+            ),
         );
     }
 }
@@ -131,8 +343,144 @@ impl Default for ClientState {
         Self {
             effect_name: None,
             effect_config: None,
+            external_effect: None,
+            modulations: vec![],
+            layers: vec![],
             pause_time_ms: 500,
             max_brightness: 100,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unreadable_config_test() {
+        let filename = format!("client_state_test_{}.ron", std::process::id());
+        let config_dir = format!(
+            "{}/config",
+            std::env::var("DATA_DIR").expect_or_log("DATA_DIR must be defined")
+        );
+        let path = format!("{config_dir}/{filename}");
+        fs::create_dir_all(&config_dir).expect_or_log("Should be able to create DATA_DIR/config");
+
+        // The Helix config is from an incompatible version, but everything else is fine
+        fs::write(
+            &path,
+            r#"ClientState(
+                effect_name: Some(Helix),
+                effect_config: Some(HelixConfig((pitch: "oops"))),
+                modulations: [
+                    Modulation(
+                        field: "pitch",
+                        shape: Triangle,
+                        period_seconds: 5.0,
+                        phase: 0.0,
+                        min: 1.0,
+                        max: 2.0,
+                    ),
+                    Modulation(
+                        field: "not_a_field",
+                        shape: Sine,
+                        period_seconds: 5.0,
+                        phase: 0.0,
+                        min: 1.0,
+                        max: 2.0,
+                    ),
+                ],
+                pause_time_ms: 1234,
+                max_brightness: 40,
+            )"#,
+        )
+        .expect_or_log("Should be able to write the state file");
+
+        let state = ClientState::from_file(&filename);
+        assert_eq!(state.effect_name, Some(EffectNameList::Helix));
+        assert_eq!(
+            state
+                .effect_config
+                .as_ref()
+                .map(EffectConfigDispatchList::effect_name),
+            Some("Helix"),
+            "the config should come from the effect's own config file"
+        );
+        assert_eq!(
+            state
+                .modulations
+                .iter()
+                .map(|modulation| modulation.field.as_str())
+                .collect::<Vec<_>>(),
+            ["pitch"],
+            "only modulations for fields of the config should survive"
+        );
+        assert_eq!(state.pause_time_ms, 1234);
+        assert_eq!(state.max_brightness, 40);
+
+        let text = fs::read_to_string(&path).expect_or_log("The state should be saved");
+        assert!(
+            text.starts_with(&add_version_header(ClientState::VERSION, "")),
+            "the state should be saved with a version header"
+        );
+        assert_eq!(
+            ClientState::from_file(&filename),
+            state,
+            "the saved state should be readable"
+        );
+
+        // Clean up the state file and its backup
+        let _ = fs::remove_file(&path);
+        for backup in backups_of(&config_dir, &filename) {
+            let _ = fs::remove_file(backup);
+        }
+    }
+
+    #[test]
+    fn newer_version_test() {
+        let filename = format!("client_state_newer_test_{}.ron", std::process::id());
+        let config_dir = format!(
+            "{}/config",
+            std::env::var("DATA_DIR").expect_or_log("DATA_DIR must be defined")
+        );
+        let path = format!("{config_dir}/{filename}");
+        fs::create_dir_all(&config_dir).expect_or_log("Should be able to create DATA_DIR/config");
+
+        let text = add_version_header(
+            ClientState::VERSION + 1,
+            "ClientState(pause_time_ms: 999, new_field: true)",
+        );
+        fs::write(&path, &text).expect_or_log("Should be able to write the state file");
+
+        assert_eq!(ClientState::from_file(&filename).pause_time_ms, 999);
+
+        let backups = backups_of(&config_dir, &filename);
+        assert_eq!(backups.len(), 1, "the newer state should be backed up");
+        assert_eq!(fs::read_to_string(&backups[0]).ok(), Some(text));
+        assert_eq!(
+            read_version(&fs::read_to_string(&path).unwrap_or_default()),
+            ClientState::VERSION
+        );
+
+        let _ = fs::remove_file(&path);
+        for backup in backups {
+            let _ = fs::remove_file(backup);
+        }
+    }
+
+    /// Find all the backups of the given state file in the config directory.
+    fn backups_of(config_dir: &str, filename: &str) -> Vec<std::path::PathBuf> {
+        fs::read_dir(config_dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter(|entry| {
+                entry
+                    .file_name()
+                    .to_str()
+                    .is_some_and(|name| name.starts_with(filename) && name.ends_with(".bak"))
+            })
+            .map(|entry| entry.path())
+            .collect()
+    }
+}